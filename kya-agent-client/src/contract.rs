@@ -2,16 +2,19 @@
 
 #![allow(unexpected_cfgs)]
 
-use kya_agent_client::{KyaAgentClientAbi, Message, Operation, Response, TaskEntry};
+use kya_agent_client::{
+    check_capability, merkle, required_scope, CapabilityTally, Delegation, DelegationScope, InstantiationArgument, KyaAgentClientAbi,
+    KyaRegistryAbi, Message, Operation, RegistryTarget, Response, StandingSync, TaskAnchor, TaskEntry,
+    TaskPerformance, TaskReport,
+};
 use linera_sdk::{
-    linera_base_types::WithContractAbi,
+    linera_base_types::{ApplicationId, ChainId, CryptoHash, WithContractAbi},
     views::{RootView, View},
     Contract, ContractRuntime,
 };
 use sha2::{Digest, Sha256};
 
-mod state;
-use state::AgentClientState;
+use kya_agent_client::state::AgentClientState;
 
 linera_sdk::contract!(AgentClientContract);
 
@@ -24,7 +27,7 @@ pub struct AgentClientContract {
 impl Contract for AgentClientContract {
     type Message = Message;
     type Parameters = ();
-    type InstantiationArgument = ();
+    type InstantiationArgument = InstantiationArgument;
     type EventValue = ();
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
@@ -34,35 +37,120 @@ impl Contract for AgentClientContract {
         AgentClientContract { state, runtime }
     }
 
-    async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {
-        // Default initialization
+    async fn instantiate(&mut self, argument: Self::InstantiationArgument) {
+        self.state.set_owner(argument.owner);
+        if let Some(registry_chain_id) = argument.registry_chain_id {
+            let registry_application_id = argument
+                .registry_application_id
+                .expect("A registry application ID is required with a registry chain ID");
+            let agent_id = argument
+                .agent_id
+                .expect("An agent ID is required with a registry chain ID");
+            self.state
+                .initialize(registry_chain_id, registry_application_id, agent_id, argument.owner)
+                .expect("Failed to initialize with registry");
+        }
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> Response {
         match operation {
-            Operation::Initialize { registry_chain_id } => {
-                self.state.initialize(registry_chain_id);
-                Response::Initialized
+            Operation::Initialize {
+                registry_chain_id,
+                registry_application_id,
+                agent_id,
+            } => {
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
+                };
+                match self
+                    .state
+                    .initialize(registry_chain_id, registry_application_id, agent_id, signer)
+                {
+                    Ok(()) => Response::Initialized,
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::MigrateRegistry {
+                registry_chain_id,
+                registry_application_id,
+                agent_id,
+                reason,
+            } => {
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
+                };
+                if let Err(e) = self.state.check_owner(&signer) {
+                    return Response::Error(e);
+                }
+                // Validate first: an error response does not undo a flush
+                if let Err(e) = self
+                    .state
+                    .check_migration((registry_chain_id, registry_application_id, agent_id))
+                    .await
+                {
+                    return Response::Error(e);
+                }
+
+                // Tasks logged under the previous registry are reported there
+                let flushed_tasks = match self.flush().await {
+                    Ok(count) => count,
+                    Err(e) => return Response::Error(e),
+                };
+                let now = self.runtime.system_time();
+                match self
+                    .state
+                    .migrate_registry(
+                        registry_chain_id,
+                        registry_application_id,
+                        agent_id,
+                        reason,
+                        flushed_tasks,
+                        now,
+                    )
+                    .await
+                {
+                    Ok(_) => Response::RegistryMigrated {
+                        registry_chain_id,
+                        agent_id,
+                        flushed_tasks,
+                    },
+                    Err(e) => Response::Error(e),
+                }
             }
 
             Operation::LogTask {
                 description,
+                capability,
                 success,
+                counterparty,
+                details,
             } => {
-                // Get the authenticated signer (agent owner)
-                let agent_id = match self.runtime.authenticated_signer() {
-                    Some(signer) => signer.into(),
+                // The owner or a delegated operator may log; the registry checks again
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
                     None => return Response::Error("Must be authenticated".to_string()),
                 };
+                let now = self.runtime.system_time();
+                if let Err(e) = self.state.check_scope(&signer, DelegationScope::LogTasks, now).await {
+                    return Response::Error(e);
+                }
 
-                // Get registry chain ID
-                let registry_chain_id = match self.state.get_registry() {
-                    Some(id) => id,
-                    None => return Response::Error("Not initialized with registry".to_string()),
-                };
+                if self.state.get_registry().is_none() {
+                    return Response::Error("Not initialized with registry".to_string());
+                }
+                if let Err(e) = details.validate(success) {
+                    return Response::Error(e);
+                }
+                if let Err(e) = check_capability(self.state.badge.get().as_ref(), &capability) {
+                    return Response::Error(e);
+                }
 
-                // Hash the task description
+                // Hash the sequence number and description
                 let mut hasher = Sha256::new();
+                hasher.update(self.state.task_count.get().to_be_bytes());
                 hasher.update(description.as_bytes());
                 let task_hash: [u8; 32] = hasher.finalize().into();
 
@@ -71,9 +159,12 @@ impl Contract for AgentClientContract {
                 // Create task entry
                 let entry = TaskEntry {
                     task_hash,
+                    capability: capability.clone(),
                     success,
+                    counterparty,
                     timestamp,
                     description,
+                    details: details.clone(),
                 };
 
                 // Log locally
@@ -81,31 +172,69 @@ impl Contract for AgentClientContract {
                     return Response::Error(e);
                 }
 
-                // Send activity log to registry hub
-                let message = Message::ActivityLog {
-                    agent_id,
+                // Queue for the registry hub, sending once the batch is due
+                self.state.buffer_task(TaskReport {
                     task_hash,
+                    capability,
                     success,
+                    counterparty,
                     timestamp,
+                    details,
+                });
+                let sent = self.flush_if_due().await;
+
+                Response::TaskLogged { task_hash, sent }
+            }
+
+            Operation::ConfigureBatching {
+                max_tasks,
+                max_age_secs,
+                anchor_roots,
+            } => {
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
                 };
+                if let Err(e) = self.state.check_owner(&signer) {
+                    return Response::Error(e);
+                }
+
+                self.state.configure_batching(max_tasks, max_age_secs, anchor_roots);
+                self.flush_if_due().await;
+                Response::BatchingConfigured {
+                    max_tasks,
+                    max_age_secs,
+                    anchor_roots,
+                }
+            }
 
-                self.runtime
-                    .prepare_message(message)
-                    .with_authentication()
-                    .with_tracking()
-                    .send_to(registry_chain_id);
+            Operation::FlushTasks => {
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
+                };
+                let now = self.runtime.system_time();
+                if let Err(e) = self.state.check_scope(&signer, DelegationScope::LogTasks, now).await {
+                    return Response::Error(e);
+                }
 
-                Response::TaskLogged { task_hash }
+                match self.flush().await {
+                    Ok(count) => Response::TasksFlushed { count },
+                    Err(e) => Response::Error(e),
+                }
             }
 
             Operation::RequestAudit => {
-                let agent_id = match self.runtime.authenticated_signer() {
-                    Some(signer) => signer.into(),
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
                     None => return Response::Error("Must be authenticated".to_string()),
                 };
+                if let Err(e) = self.state.check_owner(&signer) {
+                    return Response::Error(e);
+                }
 
-                let registry_chain_id = match self.state.get_registry() {
-                    Some(id) => id,
+                let (registry_chain_id, registry_application_id, agent_id) = match self.state.current_registry() {
+                    Some(registry) => registry,
                     None => return Response::Error("Not initialized with registry".to_string()),
                 };
 
@@ -113,23 +242,175 @@ impl Contract for AgentClientContract {
 
                 let message = Message::AuditRequest {
                     agent_id,
+                    request_id: self.state.next_audit_request_id(),
                     timestamp,
                 };
 
-                self.runtime
-                    .prepare_message(message)
-                    .with_authentication()
-                    .with_tracking()
-                    .send_to(registry_chain_id);
+                if let Err(e) = self
+                    .send_to_registry(message, registry_chain_id, registry_application_id)
+                    .await
+                {
+                    return Response::Error(e);
+                }
 
                 Response::AuditRequested
             }
+
+            Operation::SyncStanding => {
+                let Some((_, application_id, agent_id)) = self.state.current_registry() else {
+                    return Response::Error("Not initialized with registry".to_string());
+                };
+                // The registry keeps the updates it sends to this chain in its own
+                // state here; read the ones not yet seen
+                let request = kya_registry::Operation::ReadAgentUpdates {
+                    agent_id,
+                    offset: *self.state.standing_cursor.get(),
+                    limit: STANDING_PAGE,
+                };
+                let response =
+                    self.runtime
+                        .call_application(false, application_id.with_abi::<KyaRegistryAbi>(), &request);
+                match StandingSync::from_response(response, agent_id, self.runtime.system_time()) {
+                    Ok(sync) => {
+                        let received = sync.notifications.len() as u32;
+                        self.state.store_standing(sync);
+                        Response::StandingSynced { received }
+                    }
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::GrantDelegation {
+                operator,
+                scopes,
+                duration,
+            } => {
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
+                };
+                if let Err(e) = self.state.check_owner(&signer) {
+                    return Response::Error(e);
+                }
+
+                let delegation = Delegation::new(operator, scopes, self.runtime.system_time(), duration);
+                let expires_at = delegation.expires_at;
+                match self.state.grant_delegation(delegation) {
+                    Ok(()) => Response::DelegationGranted {
+                        operator,
+                        expires_at,
+                    },
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::AddRegistryTarget {
+                registry_chain_id,
+                registry_application_id,
+                agent_id,
+                capabilities,
+                attested_only,
+            } => {
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
+                };
+                let target = RegistryTarget {
+                    chain_id: registry_chain_id,
+                    application_id: registry_application_id,
+                    agent_id,
+                    capabilities,
+                    attested_only,
+                    added_at: self.runtime.system_time(),
+                };
+                match self.state.add_registry_target(target, signer).await {
+                    Ok(()) => Response::RegistryTargetAdded {
+                        registry_chain_id,
+                        registry_application_id,
+                    },
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::RemoveRegistryTarget {
+                registry_chain_id,
+                registry_application_id,
+            } => {
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
+                };
+                match self
+                    .state
+                    .remove_registry_target((registry_chain_id, registry_application_id), signer)
+                    .await
+                {
+                    Ok(()) => Response::RegistryTargetRemoved {
+                        registry_chain_id,
+                        registry_application_id,
+                    },
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::RetryUndelivered { ids } => {
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
+                };
+                let now = self.runtime.system_time();
+                // Retrying needs the same permission as sending each message did
+                let entries = match self.state.undelivered_entries().await {
+                    Ok(entries) => entries,
+                    Err(e) => return Response::Error(e),
+                };
+                for entry in entries.iter().filter(|entry| ids.is_empty() || ids.contains(&entry.id)) {
+                    let allowed = match required_scope(&entry.message) {
+                        Some(scope) => self.state.check_scope(&signer, scope, now).await,
+                        None => self.state.check_owner(&signer),
+                    };
+                    if let Err(e) = allowed {
+                        return Response::Error(format!("Cannot retry message {}: {e}", entry.id));
+                    }
+                }
+                let messages = match self.state.retry_undelivered(&ids, now).await {
+                    Ok(messages) => messages,
+                    Err(e) => return Response::Error(e),
+                };
+                let count = messages.len() as u32;
+                for (message, registry_chain_id) in messages {
+                    self.send_tracked(message, registry_chain_id);
+                }
+                Response::MessagesRetried { count }
+            }
+
+            Operation::RevokeDelegation { operator } => {
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
+                };
+                if let Err(e) = self.state.check_owner(&signer) {
+                    return Response::Error(e);
+                }
+
+                match self.state.revoke_delegation(&operator).await {
+                    Ok(()) => Response::DelegationRevoked { operator },
+                    Err(e) => Response::Error(e),
+                }
+            }
         }
     }
 
-    async fn execute_message(&mut self, _message: Message) {
-        // Agent client doesn't receive messages from registry in this design
-        // Could be extended to receive score updates, audit results, etc.
+    async fn execute_message(&mut self, message: Message) {
+        // Messages we sent come back here when the registry rejects them. Nothing
+        // else arrives: the registry's standing updates go to the registry
+        // application on this chain, and are read with `SyncStanding`.
+        if self.runtime.message_is_bouncing() == Some(true) {
+            let now = self.runtime.system_time();
+            if let Some(origin) = self.runtime.message_origin_chain_id() {
+                let _ = self.state.mark_bounced(&message, origin, now).await;
+            }
+        }
     }
 
     async fn store(mut self) {
@@ -137,6 +418,176 @@ impl Contract for AgentClientContract {
     }
 }
 
+impl AgentClientContract {
+    /// Send the outgoing batch if it has reached a threshold, returning whether it
+    /// was sent. A failed send leaves the batch buffered and records the error.
+    async fn flush_if_due(&mut self) -> bool {
+        let now = self.runtime.system_time();
+        if !self.state.batch_due(now) {
+            return false;
+        }
+        match self.flush().await {
+            Ok(_) => true,
+            Err(e) => {
+                self.state.last_flush_error.set(Some(e));
+                false
+            }
+        }
+    }
+
+    /// Send every buffered task to the primary registry, returning how many were
+    /// sent, and the tasks each extra registry target accepts to that target.
+    /// A single task goes out as a plain `ActivityLog`; in anchoring mode the
+    /// primary registry gets a Merkle root instead. Every message is built
+    /// before any is sent, and the batch is only emptied once they are queued.
+    async fn flush(&mut self) -> Result<u32, String> {
+        let (registry_chain_id, registry_application_id, agent_id) = self
+            .state
+            .current_registry()
+            .ok_or("Not initialized with registry")?;
+        if self.state.outgoing_batch.get().is_empty() {
+            return Ok(0);
+        }
+        let tasks = self.state.outgoing_batch.get().clone();
+        let count = tasks.len() as u32;
+
+        let mut messages = Vec::new();
+        for target in self.state.get_registry_targets().await? {
+            let accepted = tasks
+                .iter()
+                .filter(|task| target.accepts(task))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !accepted.is_empty() {
+                messages.push((
+                    activity_message(target.agent_id, accepted),
+                    target.chain_id,
+                    target.application_id,
+                ));
+            }
+        }
+
+        let anchor = if *self.state.batch_anchor_roots.get() {
+            let (anchor, attested_tasks) = self.build_anchor(tasks).await?;
+            messages.push((
+                Message::TaskRoot {
+                    agent_id,
+                    anchor: anchor.clone(),
+                    attested_tasks,
+                },
+                registry_chain_id,
+                registry_application_id,
+            ));
+            Some(anchor)
+        } else {
+            messages.push((activity_message(agent_id, tasks), registry_chain_id, registry_application_id));
+            None
+        };
+
+        if let Some(anchor) = anchor {
+            self.state.anchors.push(anchor);
+        }
+        for (message, chain_id, application_id) in messages {
+            self.send_to_registry(message, chain_id, application_id).await?;
+        }
+        self.state.clear_batch();
+        Ok(count)
+    }
+
+    /// Anchor a batch under a Merkle root with per-capability counts, returning
+    /// the anchor and the tasks awaiting counterparty attestation
+    async fn build_anchor(&mut self, tasks: Vec<TaskReport>) -> Result<(TaskAnchor, Vec<TaskReport>), String> {
+        let task_count = tasks.len() as u64;
+        // Every logged task is buffered, so the batch is the tail of the task log
+        let first_task = *self.state.task_count.get() - task_count;
+        let entries = self.state.read_tasks(first_task..first_task + task_count).await?;
+        let leaves = entries.iter().map(merkle::leaf_hash).collect::<Vec<_>>();
+
+        let mut tallies: Vec<CapabilityTally> = Vec::new();
+        let mut performance = TaskPerformance::default();
+        let mut attested_tasks = Vec::new();
+        for task in tasks {
+            if task.counterparty.is_some() {
+                attested_tasks.push(task);
+                continue;
+            }
+            let index = match tallies.iter().position(|tally| tally.capability == task.capability) {
+                Some(index) => index,
+                None => {
+                    tallies.push(CapabilityTally {
+                        capability: task.capability.clone(),
+                        successes: 0,
+                        failures: 0,
+                    });
+                    tallies.len() - 1
+                }
+            };
+            performance.record(task.success, &task.details);
+            if task.success {
+                tallies[index].successes += 1;
+            } else {
+                tallies[index].failures += 1;
+            }
+        }
+
+        let anchor = TaskAnchor {
+            epoch: self.state.next_anchor_epoch(),
+            root: merkle::merkle_root(&leaves),
+            first_task,
+            task_count,
+            tallies,
+            performance,
+            timestamp: self.runtime.system_time(),
+        };
+        Ok((anchor, attested_tasks))
+    }
+
+    /// Send an authenticated, tracked message to the registry hub, keeping it in
+    /// the outbox so a bounce can be detected and retried
+    async fn send_to_registry(
+        &mut self,
+        message: Message,
+        registry_chain_id: ChainId,
+        registry_application_id: ApplicationId,
+    ) -> Result<(), String> {
+        let now = self.runtime.system_time();
+        self.state
+            .record_sent(message.clone(), registry_chain_id, registry_application_id, now)
+            .await?;
+        self.send_tracked(message, registry_chain_id);
+        Ok(())
+    }
+
+    fn send_tracked(&mut self, message: Message, registry_chain_id: ChainId) {
+        self.runtime
+            .prepare_message(message)
+            .with_authentication()
+            .with_tracking()
+            .send_to(registry_chain_id);
+    }
+}
+
+/// Most standing updates read from the registry per `SyncStanding`
+const STANDING_PAGE: u64 = 100;
+
+/// A single task as a plain `ActivityLog`, several as an `ActivityBatch`
+fn activity_message(agent_id: CryptoHash, mut tasks: Vec<TaskReport>) -> Message {
+    if tasks.len() == 1 {
+        let task = tasks.remove(0);
+        Message::ActivityLog {
+            agent_id,
+            task_hash: task.task_hash,
+            capability: task.capability,
+            success: task.success,
+            counterparty: task.counterparty,
+            timestamp: task.timestamp,
+            details: task.details,
+        }
+    } else {
+        Message::ActivityBatch { agent_id, tasks }
+    }
+}
+
 impl WithContractAbi for AgentClientContract {
     type Abi = KyaAgentClientAbi;
 }
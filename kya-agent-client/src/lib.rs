@@ -4,8 +4,20 @@
 //! - Local task logging
 //! - Cross-chain messaging to the KYA Registry hub
 
-use linera_sdk::linera_base_types::{AccountOwner, ChainId, Timestamp};
+pub mod merkle;
+pub mod state;
+#[cfg(test)]
+mod tests;
+
+use linera_sdk::linera_base_types::{AccountOwner, ApplicationId, ChainId, CryptoHash, TimeDelta, Timestamp};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub use kya_registry::{
+    parse_hash, AgentNotificationKind, AgentStatus, AgentUpdate, BadgeSnapshot, CapabilityTally, Delegation,
+    DelegationScope, FailureModeCount, FailureReason, KyaRegistryAbi, Message, TaskAnchor, TaskDetails,
+    TaskPerformance, TaskReport,
+};
 
 /// The Application Binary Interface for KYA Agent Client
 pub struct KyaAgentClientAbi;
@@ -16,16 +28,160 @@ pub struct KyaAgentClientAbi;
 
 /// A logged task entry
 #[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
 pub struct TaskEntry {
-    /// SHA-256 hash of the task description as hex string
+    /// SHA-256 hash of the task's sequence number and description, so repeated
+    /// descriptions still get distinct hashes
     #[graphql(skip)]
     pub task_hash: [u8; 32],
+    /// Capability the task exercised (one of the agent's declared capabilities)
+    pub capability: String,
     /// Whether the task succeeded
     pub success: bool,
+    /// Account that received the work and must attest the outcome for it to
+    /// count towards reputation
+    pub counterparty: Option<AccountOwner>,
     /// Timestamp of the task
     pub timestamp: Timestamp,
     /// Optional task description (for local reference)
     pub description: String,
+    /// Optional timing, cost and failure details
+    pub details: TaskDetails,
+}
+
+#[async_graphql::ComplexObject]
+impl TaskEntry {
+    /// Task hash as a hex string
+    async fn task_hash_hex(&self) -> String {
+        hex::encode(self.task_hash)
+    }
+}
+
+/// Argument given when the application is created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstantiationArgument {
+    /// The agent's owner, the only account that may point the client at a registry
+    pub owner: AccountOwner,
+    /// Registry chain to report to from the start; otherwise set by `Initialize`
+    #[serde(default)]
+    pub registry_chain_id: Option<ChainId>,
+    /// The registry's application ID, required with `registry_chain_id`
+    #[serde(default)]
+    pub registry_application_id: Option<ApplicationId>,
+    /// The agent's registry ID, required with `registry_chain_id`
+    #[serde(default)]
+    pub agent_id: Option<CryptoHash>,
+}
+
+/// A change of the registry the client reports to
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct RegistryMigration {
+    /// Registry chain reported to before
+    pub from_chain_id: ChainId,
+    /// Registry application reported to before
+    pub from_application_id: ApplicationId,
+    /// The agent's ID in the previous registry
+    pub from_agent_id: CryptoHash,
+    /// Registry chain reported to from now on
+    pub to_chain_id: ChainId,
+    /// Registry application reported to from now on
+    pub to_application_id: ApplicationId,
+    /// The agent's ID in the new registry
+    pub to_agent_id: CryptoHash,
+    /// Why the owner migrated
+    pub reason: String,
+    /// Buffered tasks sent to the previous registry before switching
+    pub flushed_tasks: u32,
+    /// When the migration happened
+    pub timestamp: Timestamp,
+}
+
+impl RegistryMigration {
+    /// Describe the switch from the `current` registry (chain, application and
+    /// agent ID) to `to`. Fails if the client is not initialized or already
+    /// reports to `to`.
+    pub fn new(
+        current: Option<(ChainId, ApplicationId, CryptoHash)>,
+        to: (ChainId, ApplicationId, CryptoHash),
+        reason: String,
+        flushed_tasks: u32,
+        timestamp: Timestamp,
+    ) -> Result<Self, String> {
+        let (from_chain_id, from_application_id, from_agent_id) = check_migration(current, to)?;
+        let (to_chain_id, to_application_id, to_agent_id) = to;
+        Ok(RegistryMigration {
+            from_chain_id,
+            from_application_id,
+            from_agent_id,
+            to_chain_id,
+            to_application_id,
+            to_agent_id,
+            reason,
+            flushed_tasks,
+            timestamp,
+        })
+    }
+}
+
+/// An extra registry that receives a filtered copy of the agent's task reports,
+/// next to the primary registry set by `Initialize`. Targets are keyed by chain
+/// and application ID, so one chain can host several of them. Messages are
+/// addressed to the chain; the application ID tells their deliveries apart.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct RegistryTarget {
+    /// Chain the registry runs on
+    pub chain_id: ChainId,
+    /// Application ID of the registry deployment
+    pub application_id: ApplicationId,
+    /// The agent's ID in this registry
+    pub agent_id: CryptoHash,
+    /// Capabilities whose tasks are reported; empty reports every capability
+    pub capabilities: Vec<String>,
+    /// Only report tasks that name a counterparty
+    pub attested_only: bool,
+    /// When the target was added
+    pub added_at: Timestamp,
+}
+
+impl RegistryTarget {
+    /// Whether the target receives `report`
+    pub fn accepts(&self, report: &TaskReport) -> bool {
+        (self.capabilities.is_empty() || self.capabilities.contains(&report.capability))
+            && (!self.attested_only || report.counterparty.is_some())
+    }
+}
+
+/// Messages and tasks sent to one registry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct DeliveryStats {
+    /// Messages sent, retries included
+    pub messages_sent: u64,
+    /// Tasks reported (counting every task under an anchored root)
+    pub tasks_sent: u64,
+    /// Messages that bounced
+    pub messages_bounced: u64,
+    /// When a message was last sent
+    pub last_sent_at: Option<Timestamp>,
+}
+
+impl DeliveryStats {
+    /// Count a first send of `message`
+    pub fn record_sent(&mut self, message: &Message, now: Timestamp) {
+        self.messages_sent += 1;
+        self.tasks_sent += message.task_count();
+        self.last_sent_at = Some(now);
+    }
+
+    /// Count a re-send of a bounced message; its tasks were counted the first time
+    pub fn record_retry(&mut self, now: Timestamp) {
+        self.messages_sent += 1;
+        self.last_sent_at = Some(now);
+    }
+
+    /// Count a bounce
+    pub fn record_bounce(&mut self) {
+        self.messages_bounced += 1;
+    }
 }
 
 // ============================================================================
@@ -35,17 +191,88 @@ pub struct TaskEntry {
 /// Operations that can be performed by the agent client
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Operation {
-    /// Initialize the client with the registry chain ID
+    /// Initialize the client with the registry chain ID and the agent's registry ID
+    /// (owner only, once); use `MigrateRegistry` to change them later
     Initialize {
         registry_chain_id: ChainId,
+        /// The registry's application ID, used to read standing updates
+        registry_application_id: ApplicationId,
+        /// ID returned by the registry's `RegisterAgent` operation
+        agent_id: CryptoHash,
+    },
+    /// Point the client at another registry (owner only). Buffered tasks are sent
+    /// to the previous registry first, and the change is kept in `migrations`.
+    MigrateRegistry {
+        registry_chain_id: ChainId,
+        registry_application_id: ApplicationId,
+        agent_id: CryptoHash,
+        reason: String,
     },
-    /// Log a completed task
+    /// Log a completed task; naming the counterparty that received the work lets
+    /// it attest the outcome on the registry, which weighs attested outcomes heavily
     LogTask {
         description: String,
+        /// Capability the task exercised; the registry rejects tags missing
+        /// from the agent's manifest
+        capability: String,
         success: bool,
+        counterparty: Option<AccountOwner>,
+        /// Optional timing, cost and failure details
+        details: TaskDetails,
+    },
+    /// Buffer logged tasks and send them to the registry in batches of up to
+    /// `max_tasks`, or once the oldest buffered task is `max_age_secs` old
+    /// (owner only). `max_tasks` of 0 or 1 sends every task on its own.
+    /// The age is checked lazily, when a task is logged or batching is
+    /// configured; an idle batch waits for the next one or for `FlushTasks`.
+    /// With `anchor_roots`, each batch is sent as a Merkle root plus counts;
+    /// only tasks naming a counterparty are sent in full.
+    ConfigureBatching {
+        max_tasks: u32,
+        max_age_secs: Option<u64>,
+        anchor_roots: bool,
     },
+    /// Send all buffered tasks to the registry now
+    FlushTasks,
     /// Request an audit from the registry
     RequestAudit,
+    /// Read the standing updates the registry sent to this chain since the last
+    /// sync, caching the latest badge and adding them to the inbox
+    SyncStanding,
+    /// Grant an operator key scoped permissions (owner only), replacing any earlier grant
+    GrantDelegation {
+        operator: AccountOwner,
+        scopes: Vec<DelegationScope>,
+        /// Delegation length in seconds; `None` lasts until revoked
+        duration: Option<u64>,
+    },
+    /// Revoke an operator key's permissions (owner only)
+    RevokeDelegation {
+        operator: AccountOwner,
+    },
+    /// Also report tasks to another registry (owner only), replacing any target
+    /// with the same chain and application ID. Only tasks passing the filters are
+    /// sent, as full entries even when the primary registry gets anchored roots.
+    AddRegistryTarget {
+        registry_chain_id: ChainId,
+        registry_application_id: ApplicationId,
+        agent_id: CryptoHash,
+        /// Capabilities to report; empty reports every capability
+        capabilities: Vec<String>,
+        /// Only report tasks that name a counterparty
+        attested_only: bool,
+    },
+    /// Stop reporting to an extra registry (owner only)
+    RemoveRegistryTarget {
+        registry_chain_id: ChainId,
+        registry_application_id: ApplicationId,
+    },
+    /// Re-send messages the registry bounced, to the chain each was first sent to.
+    /// An empty `ids` retries every undelivered message. The signer needs the
+    /// permission each message took to send: audit requests are the owner's only.
+    RetryUndelivered {
+        ids: Vec<u64>,
+    },
 }
 
 /// Response from an operation
@@ -53,10 +280,28 @@ pub enum Operation {
 pub enum Response {
     /// Client initialized
     Initialized,
-    /// Task logged successfully
-    TaskLogged { task_hash: [u8; 32] },
+    /// Client now reports to another registry
+    RegistryMigrated { registry_chain_id: ChainId, agent_id: CryptoHash, flushed_tasks: u32 },
+    /// Task logged successfully; `sent` is false while it waits in the batch buffer
+    TaskLogged { task_hash: [u8; 32], sent: bool },
+    /// Batching settings updated
+    BatchingConfigured { max_tasks: u32, max_age_secs: Option<u64>, anchor_roots: bool },
+    /// Buffered tasks sent to the registry
+    TasksFlushed { count: u32 },
     /// Audit requested
     AuditRequested,
+    /// Standing updates read from the registry
+    StandingSynced { received: u32 },
+    /// Operator key delegated
+    DelegationGranted { operator: AccountOwner, expires_at: Option<Timestamp> },
+    /// Operator key revoked
+    DelegationRevoked { operator: AccountOwner },
+    /// Bounced messages re-sent
+    MessagesRetried { count: u32 },
+    /// Extra registry added or updated
+    RegistryTargetAdded { registry_chain_id: ChainId, registry_application_id: ApplicationId },
+    /// Extra registry removed
+    RegistryTargetRemoved { registry_chain_id: ChainId, registry_application_id: ApplicationId },
     /// Error occurred
     Error(String),
 }
@@ -65,21 +310,288 @@ pub enum Response {
 // Messages (Cross-chain communication)
 // ============================================================================
 
-/// Messages sent to the registry hub
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum Message {
-    /// Activity log sent to registry
-    ActivityLog {
-        agent_id: AccountOwner,
-        task_hash: [u8; 32],
-        success: bool,
-        timestamp: Timestamp,
-    },
-    /// Audit request sent to registry
-    AuditRequest {
-        agent_id: AccountOwner,
-        timestamp: Timestamp,
-    },
+/// Fail unless `signer` is the `owner`
+pub fn check_owner(owner: Option<AccountOwner>, signer: &AccountOwner) -> Result<(), String> {
+    if owner != Some(*signer) {
+        return Err("Only the owner can do this".to_string());
+    }
+    Ok(())
+}
+
+/// Fail unless `signer` is the `owner` and no registry is set yet; later
+/// changes go through `MigrateRegistry`
+pub fn check_initialize(owner: Option<AccountOwner>, signer: &AccountOwner, initialized: bool) -> Result<(), String> {
+    check_owner(owner, signer)?;
+    if initialized {
+        return Err("Already initialized; use MigrateRegistry to change registry".to_string());
+    }
+    Ok(())
+}
+
+/// Fail unless the client can switch from the `current` registry (chain,
+/// application and agent ID) to `to`, returning the current one
+pub fn check_migration(
+    current: Option<(ChainId, ApplicationId, CryptoHash)>,
+    to: (ChainId, ApplicationId, CryptoHash),
+) -> Result<(ChainId, ApplicationId, CryptoHash), String> {
+    let current = current.ok_or("Not initialized with registry")?;
+    if current == to {
+        return Err("Already reporting to this registry".to_string());
+    }
+    Ok(current)
+}
+
+/// Fail if the cached `badge` shows `capability` is not declared in the
+/// agent's manifest. The registry rejects a task root that uses an undeclared
+/// capability, so such tasks are refused before they are logged. Without a
+/// cached badge the registry's own check is all there is.
+pub fn check_capability(badge: Option<&BadgeSnapshot>, capability: &str) -> Result<(), String> {
+    match badge {
+        Some(badge) if !badge.capabilities.iter().any(|declared| declared == capability) => {
+            Err(format!("Capability {} is not declared in the manifest", capability))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether a batch (oldest task first) has reached `max_tasks` tasks (at least 1)
+/// or its oldest task is `max_age_secs` old at `now`
+pub fn batch_due(batch: &[TaskReport], max_tasks: u32, max_age_secs: Option<u64>, now: Timestamp) -> bool {
+    let Some(oldest) = batch.first() else {
+        return false;
+    };
+    if batch.len() >= max_tasks.max(1) as usize {
+        return true;
+    }
+    max_age_secs.is_some_and(|max_age| now.delta_since(oldest.timestamp) >= TimeDelta::from_secs(max_age))
+}
+
+/// Take the outbox IDs to retry out of `undelivered`: those in `ids`, or all of
+/// them if `ids` is empty. Fails without changes if an ID is not undelivered.
+pub fn take_retries(undelivered: &mut Vec<u64>, ids: &[u64]) -> Result<Vec<u64>, String> {
+    if let Some(id) = ids.iter().find(|id| !undelivered.contains(id)) {
+        return Err(format!("Message {id} is not undelivered"));
+    }
+    let (retried, remaining) = std::mem::take(undelivered)
+        .into_iter()
+        .partition(|id| ids.is_empty() || ids.contains(id));
+    *undelivered = remaining;
+    Ok(retried)
+}
+
+/// Permission needed to send `message`, and so to retry it; `None` means the
+/// owner only
+pub fn required_scope(message: &Message) -> Option<DelegationScope> {
+    match message {
+        Message::ActivityLog { .. } | Message::ActivityBatch { .. } | Message::TaskRoot { .. } => {
+            Some(DelegationScope::LogTasks)
+        }
+        _ => None,
+    }
+}
+
+/// Key identifying a message sent to `chain_id`, used to match a bounced copy
+/// to its outbox entry. Bounces only name the chain, so copies sent to several
+/// registries on one chain share a key (see `bounced_entry`).
+pub fn delivery_key(message: &Message, chain_id: ChainId) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(<[u8; 32]>::from(chain_id.0));
+    match message {
+        Message::ActivityLog { task_hash, .. } => {
+            hasher.update([0u8]);
+            hasher.update(task_hash);
+        }
+        Message::ActivityBatch { tasks, .. } => {
+            hasher.update([1u8]);
+            for task in tasks {
+                hasher.update(task.task_hash);
+            }
+        }
+        Message::TaskRoot { anchor, .. } => {
+            hasher.update([2u8]);
+            hasher.update(anchor.epoch.to_be_bytes());
+            hasher.update(anchor.root);
+        }
+        Message::AuditRequest { request_id, .. } => {
+            hasher.update([3u8]);
+            hasher.update(request_id.to_be_bytes());
+        }
+        other => {
+            // The client never sends the registry's other messages
+            hasher.update([4u8]);
+            hasher.update(other.kind().as_bytes());
+        }
+    }
+    hasher.finalize().into()
+}
+
+/// A notification received from the registry
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct AgentNotification {
+    /// What changed
+    pub kind: AgentNotificationKind,
+    /// Human-readable description of the change
+    pub detail: String,
+    /// Score after the change
+    pub reputation_score: u16,
+    /// Tier after the change
+    pub tier: String,
+    /// When the registry sent it
+    pub timestamp: Timestamp,
+    /// When it arrived
+    pub received_at: Timestamp,
+}
+
+impl AgentNotification {
+    /// Notification for a standing update read at `received_at`
+    pub fn from_update(update: &AgentUpdate, received_at: Timestamp) -> Self {
+        Self {
+            kind: update.kind,
+            detail: update.detail.clone(),
+            reputation_score: update.badge.reputation_score,
+            tier: update.badge.tier.clone(),
+            timestamp: update.timestamp,
+            received_at,
+        }
+    }
+}
+
+/// Standing updates read from the registry application on this chain, ready to store
+#[derive(Debug, Clone)]
+pub struct StandingSync {
+    /// Offset to read the registry's updates from next time
+    pub next: u64,
+    /// Badge carried by the newest update, if any arrived
+    pub badge: Option<BadgeSnapshot>,
+    /// One notification per update, oldest first
+    pub notifications: Vec<AgentNotification>,
+}
+
+impl StandingSync {
+    /// Check the registry's reply to `ReadAgentUpdates` for `agent_id`
+    pub fn from_response(
+        response: kya_registry::Response,
+        agent_id: CryptoHash,
+        received_at: Timestamp,
+    ) -> Result<Self, String> {
+        let (updates, next) = match response {
+            kya_registry::Response::AgentUpdates {
+                agent_id: reported_id,
+                updates,
+                next,
+            } if reported_id == agent_id => (updates, next),
+            kya_registry::Response::Error(e) => return Err(e),
+            _ => return Err("Unexpected response from the registry".to_string()),
+        };
+        if updates.iter().any(|update| update.agent_id != agent_id) {
+            return Err("Registry returned updates for another agent".to_string());
+        }
+        Ok(Self {
+            next,
+            badge: updates.last().map(|update| update.badge.clone()),
+            notifications: updates
+                .iter()
+                .map(|update| AgentNotification::from_update(update, received_at))
+                .collect(),
+        })
+    }
+}
+
+/// Whether a sent message has come back rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum DeliveryStatus {
+    /// Sent and not bounced: accepted, or not yet processed by the registry
+    Sent,
+    /// Rejected by the registry and bounced back; can be retried
+    Bounced,
+}
+
+/// A message sent to the registry, kept to detect and retry bounces
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
+pub struct OutboxEntry {
+    /// Sequence number of the entry
+    pub id: u64,
+    /// The message as sent
+    #[graphql(skip)]
+    pub message: Message,
+    /// Chain the message was last sent to
+    pub registry_chain_id: ChainId,
+    /// Registry application the message was meant for
+    pub registry_application_id: ApplicationId,
+    /// Delivery status
+    pub status: DeliveryStatus,
+    /// Number of times the message was sent
+    pub attempts: u32,
+    /// When the message was last sent
+    pub sent_at: Timestamp,
+    /// When the message last bounced
+    pub bounced_at: Option<Timestamp>,
+}
+
+impl OutboxEntry {
+    /// Entry for a message first sent to the registry `registry_application_id`
+    /// on `registry_chain_id` at `now`
+    pub fn new(
+        id: u64,
+        message: Message,
+        registry_chain_id: ChainId,
+        registry_application_id: ApplicationId,
+        now: Timestamp,
+    ) -> Self {
+        Self {
+            id,
+            message,
+            registry_chain_id,
+            registry_application_id,
+            status: DeliveryStatus::Sent,
+            attempts: 1,
+            sent_at: now,
+            bounced_at: None,
+        }
+    }
+
+    /// Record that the message came back rejected at `now`
+    pub fn mark_bounced(&mut self, now: Timestamp) {
+        self.status = DeliveryStatus::Bounced;
+        self.bounced_at = Some(now);
+    }
+
+    /// Record that the message was sent again at `now`
+    pub fn mark_resent(&mut self, now: Timestamp) {
+        self.status = DeliveryStatus::Sent;
+        self.attempts += 1;
+        self.sent_at = now;
+    }
+}
+
+/// The entry a bounced copy belongs to among the `entries` sharing its delivery
+/// key: the oldest one still in flight. Identical copies sent to several
+/// registries on one chain cannot be told apart.
+pub fn bounced_entry(entries: &[OutboxEntry]) -> Option<u64> {
+    entries
+        .iter()
+        .filter(|entry| entry.status == DeliveryStatus::Sent)
+        .min_by_key(|entry| entry.id)
+        .map(|entry| entry.id)
+}
+
+#[async_graphql::ComplexObject]
+impl OutboxEntry {
+    /// Message type (`ActivityLog`, `ActivityBatch`, `TaskRoot` or `AuditRequest`)
+    async fn kind(&self) -> &'static str {
+        self.message.kind()
+    }
+
+    /// Number of tasks the message reports
+    async fn task_count(&self) -> u64 {
+        self.message.task_count()
+    }
+
+    /// Hashes of the tasks the message carries in full, as hex strings
+    async fn task_hashes_hex(&self) -> Vec<String> {
+        self.message.task_hashes().iter().map(hex::encode).collect()
+    }
 }
 
 // ============================================================================
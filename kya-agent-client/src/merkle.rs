@@ -0,0 +1,22 @@
+//! Merkle trees over task log entries
+//!
+//! Each flushed batch of tasks is committed to by the root of a binary Merkle
+//! tree, built with the same rules the registry checks proofs with (see
+//! `kya_registry::merkle`).
+
+pub use kya_registry::merkle::{merkle_proof, merkle_root, verify_proof};
+
+use crate::TaskEntry;
+
+/// Hash of a task log entry as committed to in the tree
+pub fn leaf_hash(entry: &TaskEntry) -> [u8; 32] {
+    kya_registry::merkle::task_leaf_hash(
+        &entry.task_hash,
+        &entry.capability,
+        entry.success,
+        entry.timestamp,
+        &entry.description,
+        entry.counterparty,
+        &entry.details,
+    )
+}
@@ -4,23 +4,24 @@
 
 use std::sync::Arc;
 
-use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
-use kya_agent_client::KyaAgentClientAbi;
+use async_graphql::{ComplexObject, EmptySubscription, InputObject, Object, Request, Response, Schema, SimpleObject};
+use kya_agent_client::{
+    merkle, parse_hash, AgentNotification, BadgeSnapshot, Delegation, DeliveryStats, KyaAgentClientAbi, OutboxEntry,
+    RegistryMigration, RegistryTarget, TaskAnchor, TaskEntry, TaskReport,
+};
 use linera_sdk::{
-    linera_base_types::WithServiceAbi,
+    linera_base_types::{ApplicationId, ChainId, Timestamp, WithServiceAbi},
     views::View,
     Service, ServiceRuntime,
 };
 
-mod state;
-use state::AgentClientState;
+use kya_agent_client::state::AgentClientState;
 
 linera_sdk::service!(AgentClientService);
 
 /// The Agent Client service
 pub struct AgentClientService {
-    state: AgentClientState,
-    #[allow(dead_code)]
+    state: Arc<AgentClientState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -32,7 +33,7 @@ impl Service for AgentClientService {
             .await
             .expect("Failed to load state");
         AgentClientService {
-            state,
+            state: Arc::new(state),
             runtime: Arc::new(runtime),
         }
     }
@@ -44,6 +45,8 @@ impl Service for AgentClientService {
                 total_tasks: total,
                 success_count: success,
                 failure_count: failure,
+                state: self.state.clone(),
+                now: self.runtime.system_time(),
             },
             MutationRoot,
             EmptySubscription,
@@ -65,6 +68,8 @@ struct QueryRoot {
     total_tasks: u64,
     success_count: u64,
     failure_count: u64,
+    state: Arc<AgentClientState>,
+    now: Timestamp,
 }
 
 #[Object]
@@ -92,6 +97,348 @@ impl QueryRoot {
             (self.success_count as f64 / self.total_tasks as f64) * 100.0
         }
     }
+
+    /// Get logged tasks, newest first, optionally filtered; `offset` and `limit`
+    /// (default 20, at most 100) page through the matches
+    async fn tasks(
+        &self,
+        filter: Option<TaskFilter>,
+        offset: Option<u32>,
+        limit: Option<u32>,
+    ) -> async_graphql::Result<Vec<TaskRecord>> {
+        let TaskFilter {
+            success,
+            capability,
+            since,
+            until,
+        } = filter.unwrap_or_default();
+        let mut skip = offset.unwrap_or(0) as usize;
+        let limit = limit.unwrap_or(20).min(100) as usize;
+        let mut records = Vec::new();
+        if limit == 0 {
+            return Ok(records);
+        }
+        self.state
+            .scan_tasks_newest_first(|index, entry| {
+                // The log is in time order, so nothing older can match
+                if since.is_some_and(|since| entry.timestamp < since) {
+                    return false;
+                }
+                let matches = until.is_none_or(|until| entry.timestamp <= until)
+                    && success.is_none_or(|success| entry.success == success)
+                    && capability.as_ref().is_none_or(|cap| &entry.capability == cap);
+                if matches {
+                    if skip > 0 {
+                        skip -= 1;
+                    } else {
+                        records.push(TaskRecord {
+                            index,
+                            entry: entry.clone(),
+                        });
+                    }
+                }
+                records.len() < limit
+            })
+            .await?;
+        Ok(records)
+    }
+
+    /// Look up a logged task by its hex-encoded hash
+    async fn task(&self, task_hash_hex: String) -> async_graphql::Result<Option<TaskRecord>> {
+        let task_hash = parse_hash(&task_hash_hex).ok_or("Task hash must be 32 hex-encoded bytes")?;
+        Ok(self
+            .state
+            .find_task(&task_hash)
+            .await?
+            .map(|(index, entry)| TaskRecord { index, entry }))
+    }
+
+    /// Success rates over the last hour, day and week
+    async fn recent_stats(&self) -> async_graphql::Result<RecentStats> {
+        // Only tasks younger than the window count; the log is in time order, so
+        // each window starts at a position found by timestamp
+        let cutoff = |window_secs: u64| {
+            self.now
+                .micros()
+                .checked_sub(window_secs.saturating_mul(1_000_000))
+                .map(Timestamp::from)
+        };
+        let mut windows = Vec::new();
+        for window_secs in [HOUR_SECS, DAY_SECS, WEEK_SECS] {
+            let (total, successes) = self.state.stats_after(cutoff(window_secs)).await?;
+            windows.push(WindowStats::new(window_secs, total, successes));
+        }
+        let week = windows.pop().expect("three windows");
+        let day = windows.pop().expect("three windows");
+        let hour = windows.pop().expect("three windows");
+        Ok(RecentStats {
+            last_hour: hour,
+            last_day: day,
+            last_week: week,
+        })
+    }
+
+    /// Get the tasks buffered for the next batch to the registry, oldest first
+    async fn outgoing_batch(&self) -> &Vec<TaskReport> {
+        self.state.outgoing_batch.get()
+    }
+
+    /// Why the last automatic send of a due batch failed, if it is still buffered
+    async fn last_flush_error(&self) -> &Option<String> {
+        self.state.last_flush_error.get()
+    }
+
+    /// Largest batch buffered before sending (0 or 1 means no buffering)
+    async fn batch_max_tasks(&self) -> u32 {
+        *self.state.batch_max_tasks.get()
+    }
+
+    /// Oldest a buffered task may get before the batch is sent, in seconds
+    async fn batch_max_age_secs(&self) -> Option<u64> {
+        *self.state.batch_max_age_secs.get()
+    }
+
+    /// Whether batches are sent as Merkle roots plus counts
+    async fn batch_anchor_roots(&self) -> bool {
+        *self.state.batch_anchor_roots.get()
+    }
+
+    /// Merkle roots of the batches sent as anchors, oldest first
+    async fn anchors(&self) -> Vec<TaskAnchor> {
+        let count = self.state.anchors.count();
+        self.state.anchors.read(0..count).await.unwrap_or_default()
+    }
+
+    /// Inclusion proof for the task at `index` in the task log, to check against
+    /// the root the registry stored for the batch's epoch
+    async fn task_proof(&self, index: u64) -> Option<TaskProof> {
+        let anchor = self.state.anchor_for_task(index).await.ok()??;
+        let entries = self
+            .state
+            .read_tasks(anchor.first_task..anchor.first_task + anchor.task_count)
+            .await
+            .ok()?;
+        let leaves = entries.iter().map(merkle::leaf_hash).collect::<Vec<_>>();
+        let leaf_index = (index - anchor.first_task) as usize;
+        let siblings = merkle::merkle_proof(&leaves, leaf_index)?;
+        Some(TaskProof {
+            epoch: anchor.epoch,
+            leaf_index: leaf_index as u64,
+            leaf_count: anchor.task_count,
+            leaf: leaves[leaf_index],
+            root: anchor.root,
+            siblings,
+            entry: entries[leaf_index].clone(),
+        })
+    }
+
+    /// The agent's badge as last pushed by the registry (`None` until the first notification)
+    async fn badge(&self) -> &Option<BadgeSnapshot> {
+        self.state.badge.get()
+    }
+
+    /// Notifications received from the registry, newest first; `offset` and `limit`
+    /// (default 20, at most 100) page through them
+    async fn inbox(&self, offset: Option<u64>, limit: Option<u64>) -> async_graphql::Result<Vec<AgentNotification>> {
+        let count = self.state.inbox.count() as u64;
+        let end = count.saturating_sub(offset.unwrap_or(0));
+        let start = end.saturating_sub(limit.unwrap_or(20).min(100));
+        let mut notifications = self.state.inbox.read(start as usize..end as usize).await?;
+        notifications.reverse();
+        Ok(notifications)
+    }
+
+    /// Messages the registry bounced that have not been retried, oldest first
+    async fn undelivered_messages(&self) -> async_graphql::Result<Vec<OutboxEntry>> {
+        Ok(self.state.undelivered_entries().await?)
+    }
+
+    /// Number of tasks in bounced messages that have not been retried
+    async fn undelivered_task_count(&self) -> async_graphql::Result<u64> {
+        let entries = self.state.undelivered_entries().await?;
+        Ok(entries.iter().map(|entry| entry.message.task_count()).sum())
+    }
+
+    /// Messages sent to the registry, newest first; `offset` and `limit`
+    /// (default 20, at most 100) page through them
+    async fn sent_messages(&self, offset: Option<u64>, limit: Option<u64>) -> async_graphql::Result<Vec<OutboxEntry>> {
+        let total = *self.state.next_outbox_id.get();
+        let end = total.saturating_sub(offset.unwrap_or(0));
+        let start = end.saturating_sub(limit.unwrap_or(20).min(100));
+        let mut entries = Vec::new();
+        for id in (start..end).rev() {
+            if let Some(entry) = self.state.outbox.get(&id).await? {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Extra registries that receive filtered task reports
+    async fn registry_targets(&self) -> async_graphql::Result<Vec<RegistryTarget>> {
+        Ok(self.state.get_registry_targets().await?)
+    }
+
+    /// Delivery counters for each registry messages were sent to
+    async fn delivery_stats(&self) -> async_graphql::Result<Vec<RegistryDelivery>> {
+        let primary = self
+            .state
+            .current_registry()
+            .map(|(chain_id, application_id, _)| (chain_id, application_id));
+        let mut deliveries = Vec::new();
+        for (chain_id, application_id) in self.state.delivery_stats.indices().await? {
+            if let Some(stats) = self.state.delivery_stats.get(&(chain_id, application_id)).await? {
+                deliveries.push(RegistryDelivery {
+                    chain_id,
+                    application_id,
+                    primary: Some((chain_id, application_id)) == primary,
+                    stats,
+                });
+            }
+        }
+        Ok(deliveries)
+    }
+
+    /// Changes of the registry the client reports to, oldest first
+    async fn migrations(&self) -> async_graphql::Result<Vec<RegistryMigration>> {
+        let count = self.state.migrations.count();
+        Ok(self.state.migrations.read(0..count).await?)
+    }
+
+    /// Get the operator keys currently delegated by the owner (expired grants are omitted)
+    async fn delegations(&self) -> Vec<Delegation> {
+        self.state.active_delegations(self.now).await.unwrap_or_default()
+    }
+}
+
+const HOUR_SECS: u64 = 60 * 60;
+const DAY_SECS: u64 = 24 * HOUR_SECS;
+const WEEK_SECS: u64 = 7 * DAY_SECS;
+
+/// Delivery counters for one registry
+#[derive(SimpleObject)]
+struct RegistryDelivery {
+    /// The registry chain
+    chain_id: ChainId,
+    /// The registry application
+    application_id: ApplicationId,
+    /// Whether this is the primary registry
+    primary: bool,
+    /// Messages and tasks sent to it
+    stats: DeliveryStats,
+}
+
+/// Which tasks `tasks` returns; unset fields match everything
+#[derive(InputObject, Default)]
+struct TaskFilter {
+    /// Only tasks with this outcome
+    success: Option<bool>,
+    /// Only tasks exercising this capability
+    capability: Option<String>,
+    /// Only tasks logged at or after this time
+    since: Option<Timestamp>,
+    /// Only tasks logged at or before this time
+    until: Option<Timestamp>,
+}
+
+/// A task log entry and its position in the log
+#[derive(SimpleObject)]
+struct TaskRecord {
+    /// Position in the task log (as used by `taskProof`)
+    index: u64,
+    /// The task log entry
+    entry: TaskEntry,
+}
+
+/// Task outcomes over one trailing time window
+#[derive(SimpleObject)]
+#[graphql(complex)]
+struct WindowStats {
+    /// Length of the window in seconds
+    window_secs: u64,
+    /// Tasks logged in the window
+    total: u64,
+    /// Successful tasks in the window
+    successes: u64,
+    /// Failed tasks in the window
+    failures: u64,
+}
+
+impl WindowStats {
+    fn new(window_secs: u64, total: u64, successes: u64) -> Self {
+        WindowStats {
+            window_secs,
+            total,
+            successes,
+            failures: total - successes,
+        }
+    }
+}
+
+#[ComplexObject]
+impl WindowStats {
+    /// Success rate in the window as a percentage (0 when no tasks were logged)
+    async fn success_rate(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.successes as f64 / self.total as f64) * 100.0
+        }
+    }
+}
+
+/// Success rates over trailing windows
+#[derive(SimpleObject)]
+struct RecentStats {
+    /// The last hour
+    last_hour: WindowStats,
+    /// The last 24 hours
+    last_day: WindowStats,
+    /// The last 7 days
+    last_week: WindowStats,
+}
+
+/// A task log entry with the Merkle proof tying it to an anchored root
+#[derive(SimpleObject)]
+#[graphql(complex)]
+struct TaskProof {
+    /// Epoch of the anchor whose root covers the task
+    epoch: u64,
+    /// Position of the task within the anchored batch
+    leaf_index: u64,
+    /// Number of tasks in the anchored batch
+    leaf_count: u64,
+    #[graphql(skip)]
+    leaf: [u8; 32],
+    #[graphql(skip)]
+    root: [u8; 32],
+    #[graphql(skip)]
+    siblings: Vec<[u8; 32]>,
+    /// The task log entry
+    entry: TaskEntry,
+}
+
+#[ComplexObject]
+impl TaskProof {
+    /// Leaf hash of the entry (see `merkle::task_leaf_hash`) as a hex string
+    async fn leaf_hex(&self) -> String {
+        hex::encode(self.leaf)
+    }
+
+    /// Anchored root as a hex string
+    async fn root_hex(&self) -> String {
+        hex::encode(self.root)
+    }
+
+    /// Sibling hashes from the leaf up to the root, as hex strings
+    async fn siblings_hex(&self) -> Vec<String> {
+        self.siblings.iter().map(hex::encode).collect()
+    }
+
+    /// Task hash as a hex string
+    async fn task_hash_hex(&self) -> String {
+        hex::encode(self.entry.task_hash)
+    }
 }
 
 struct MutationRoot;
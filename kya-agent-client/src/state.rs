@@ -1,8 +1,12 @@
 //! KYA Agent Client State - Local task logging with linera-views
 
-use kya_agent_client::TaskEntry;
-use linera_sdk::linera_base_types::ChainId;
-use linera_sdk::views::{linera_views, LogView, RegisterView, RootView, ViewStorageContext};
+use crate::{
+    batch_due, bounced_entry, delivery_key, take_retries, AgentNotification, BadgeSnapshot, Delegation, DelegationScope,
+    DeliveryStats, Message, OutboxEntry, RegistryMigration, RegistryTarget, StandingSync, TaskAnchor, TaskEntry, TaskReport,
+};
+use linera_sdk::linera_base_types::{AccountOwner, ApplicationId, ChainId, CryptoHash, Timestamp};
+use linera_sdk::views::{linera_views, LogView, MapView, RegisterView, RootView, ViewStorageContext};
+use serde::{de::DeserializeOwned, Serialize};
 
 /// The root state of the KYA Agent Client application
 #[derive(RootView, async_graphql::SimpleObject)]
@@ -10,32 +14,214 @@ use linera_sdk::views::{linera_views, LogView, RegisterView, RootView, ViewStora
 pub struct AgentClientState {
     /// The chain ID of the KYA Registry hub
     pub registry_chain_id: RegisterView<Option<ChainId>>,
+    /// The KYA Registry's application ID, called on this chain to read standing updates
+    pub registry_application_id: RegisterView<Option<ApplicationId>>,
+    /// This agent's ID in the KYA Registry
+    pub agent_id: RegisterView<Option<CryptoHash>>,
+    /// The agent's owner, set at instantiation
+    pub owner: RegisterView<Option<AccountOwner>>,
+    /// Changes of the registry the client reports to, oldest first
+    pub migrations: LogView<RegistryMigration>,
+    /// Number of anchors sent before the last migration; epochs restart for each registry
+    pub anchor_epoch_base: RegisterView<u64>,
+    /// Operator keys delegated by the owner
+    pub delegations: MapView<AccountOwner, Delegation>,
     /// Log of all tasks performed by this agent
     pub task_log: LogView<TaskEntry>,
+    /// Position of each task in the log, by task hash
+    #[graphql(skip)]
+    pub task_index: MapView<[u8; 32], u64>,
+    /// Number of successful tasks up to and including each position of the log
+    #[graphql(skip)]
+    pub successes_through: LogView<u64>,
     /// Total number of tasks logged
     pub task_count: RegisterView<u64>,
     /// Number of successful tasks
     pub success_count: RegisterView<u64>,
     /// Number of failed tasks
     pub failure_count: RegisterView<u64>,
+    /// Largest batch to buffer before sending; 0 or 1 sends every task on its own
+    pub batch_max_tasks: RegisterView<u32>,
+    /// Oldest a buffered task may get before the batch is sent, in seconds;
+    /// checked only when a task is logged or batching is configured
+    pub batch_max_age_secs: RegisterView<Option<u64>>,
+    /// Tasks logged but not yet sent to the registry, oldest first
+    pub outgoing_batch: RegisterView<Vec<TaskReport>>,
+    /// Why the last automatic send of a due batch failed; its tasks stay buffered
+    pub last_flush_error: RegisterView<Option<String>>,
+    /// Whether batches are sent as Merkle roots plus counts
+    pub batch_anchor_roots: RegisterView<bool>,
+    /// Merkle roots of the batches sent as anchors, by epoch
+    pub anchors: LogView<TaskAnchor>,
+    /// Every message sent to the registry, by sequence number
+    pub outbox: MapView<u64, OutboxEntry>,
+    /// Outbox sequence numbers of the messages sent with each delivery key
+    #[graphql(skip)]
+    pub outbox_keys: MapView<[u8; 32], Vec<u64>>,
+    /// Next outbox sequence number
+    pub next_outbox_id: RegisterView<u64>,
+    /// Outbox entries that bounced and have not been retried
+    pub undelivered: RegisterView<Vec<u64>>,
+    /// Total number of bounces received
+    pub bounced_count: RegisterView<u64>,
+    /// Number of audit requests sent, numbering each request
+    pub audit_request_count: RegisterView<u64>,
+    /// Extra registries that receive filtered task reports, by chain and application ID
+    #[graphql(skip)]
+    pub registry_targets: MapView<(ChainId, ApplicationId), RegistryTarget>,
+    /// Delivery counters per registry, primary and extra alike, by chain and application ID
+    #[graphql(skip)]
+    pub delivery_stats: MapView<(ChainId, ApplicationId), DeliveryStats>,
+    /// The agent's badge as of the last standing update read from the registry
+    pub badge: RegisterView<Option<BadgeSnapshot>>,
+    /// Standing updates read from the registry, oldest first
+    pub inbox: LogView<AgentNotification>,
+    /// Offset of the next standing update to read from the registry
+    pub standing_cursor: RegisterView<u64>,
 }
 
 impl AgentClientState {
-    /// Initialize with a registry chain ID
-    pub fn initialize(&mut self, registry_chain_id: ChainId) {
+    /// Record the owner given at instantiation
+    pub fn set_owner(&mut self, owner: AccountOwner) {
+        self.owner.set(Some(owner));
+    }
+
+    /// Set the registry chain and application IDs and the agent's registry ID
+    /// (owner only, once)
+    pub fn initialize(
+        &mut self,
+        registry_chain_id: ChainId,
+        registry_application_id: ApplicationId,
+        agent_id: CryptoHash,
+        signer: AccountOwner,
+    ) -> Result<(), String> {
+        crate::check_initialize(*self.owner.get(), &signer, self.get_registry().is_some())?;
+        self.registry_chain_id.set(Some(registry_chain_id));
+        self.registry_application_id.set(Some(registry_application_id));
+        self.agent_id.set(Some(agent_id));
+        Ok(())
+    }
+
+    /// The registry reported to: chain, application and the agent's ID there
+    pub fn current_registry(&self) -> Option<(ChainId, ApplicationId, CryptoHash)> {
+        let (chain_id, agent_id) = self.get_registry()?;
+        let application_id = (*self.registry_application_id.get())?;
+        Some((chain_id, application_id, agent_id))
+    }
+
+    /// Fail unless the client can switch to the registry `to`; nothing is
+    /// flushed or changed before this passes
+    pub async fn check_migration(&self, to: (ChainId, ApplicationId, CryptoHash)) -> Result<(), String> {
+        crate::check_migration(self.current_registry(), to)?;
+        if self
+            .registry_targets
+            .contains_key(&(to.0, to.1))
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            return Err("This registry is already a target; remove it first".to_string());
+        }
+        Ok(())
+    }
+
+    /// Switch to another registry, logging the change. Anchor epochs restart at 0
+    /// for the new registry, and the cached badge is dropped until it reports.
+    pub async fn migrate_registry(
+        &mut self,
+        registry_chain_id: ChainId,
+        registry_application_id: ApplicationId,
+        agent_id: CryptoHash,
+        reason: String,
+        flushed_tasks: u32,
+        now: Timestamp,
+    ) -> Result<RegistryMigration, String> {
+        let to = (registry_chain_id, registry_application_id, agent_id);
+        self.check_migration(to).await?;
+        let migration = RegistryMigration::new(self.current_registry(), to, reason, flushed_tasks, now)?;
+        self.migrations.push(migration.clone());
         self.registry_chain_id.set(Some(registry_chain_id));
+        self.registry_application_id.set(Some(registry_application_id));
+        self.agent_id.set(Some(agent_id));
+        self.anchor_epoch_base.set(self.anchors.count() as u64);
+        self.badge.set(None);
+        self.standing_cursor.set(0);
+        Ok(migration)
+    }
+
+    /// Epoch of the next anchor sent to the current registry
+    pub fn next_anchor_epoch(&self) -> u64 {
+        self.anchors.count() as u64 - *self.anchor_epoch_base.get()
+    }
+
+    /// Fail unless `signer` is the owner
+    pub fn check_owner(&self, signer: &AccountOwner) -> Result<(), String> {
+        crate::check_owner(*self.owner.get(), signer)
+    }
+
+    /// Fail unless `signer` is the owner or an operator currently delegated `scope`
+    pub async fn check_scope(
+        &self,
+        signer: &AccountOwner,
+        scope: DelegationScope,
+        now: Timestamp,
+    ) -> Result<(), String> {
+        if *self.owner.get() == Some(*signer) {
+            return Ok(());
+        }
+        match self.delegations.get(signer).await.map_err(|e| e.to_string())? {
+            Some(delegation) if delegation.allows(scope, now) => Ok(()),
+            _ => Err(format!("Signer is neither the owner nor delegated {scope:?}")),
+        }
+    }
+
+    /// Grant (or re-grant) scoped permissions to an operator key
+    pub fn grant_delegation(&mut self, delegation: Delegation) -> Result<(), String> {
+        if Some(delegation.operator) == *self.owner.get() {
+            return Err("The owner cannot delegate to itself".to_string());
+        }
+        if delegation.scopes.is_empty() {
+            return Err("A delegation needs at least one scope".to_string());
+        }
+        let operator = delegation.operator;
+        self.delegations
+            .insert(&operator, delegation)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Revoke an operator key's permissions
+    pub async fn revoke_delegation(&mut self, operator: &AccountOwner) -> Result<(), String> {
+        if !self.delegations.contains_key(operator).await.map_err(|e| e.to_string())? {
+            return Err("Operator has no delegation".to_string());
+        }
+        self.delegations.remove(operator).map_err(|e| e.to_string())
     }
 
-    /// Get the registry chain ID
-    pub fn get_registry(&self) -> Option<ChainId> {
-        *self.registry_chain_id.get()
+    /// Get the delegations that have not expired at `now`
+    pub async fn active_delegations(&self, now: Timestamp) -> Result<Vec<Delegation>, String> {
+        let mut delegations = Vec::new();
+        for operator in self.delegations.indices().await.map_err(|e| e.to_string())? {
+            if let Some(delegation) = self.delegations.get(&operator).await.map_err(|e| e.to_string())? {
+                if delegation.is_active(now) {
+                    delegations.push(delegation);
+                }
+            }
+        }
+        Ok(delegations)
+    }
+
+    /// Get the registry chain ID and the agent's registry ID
+    pub fn get_registry(&self) -> Option<(ChainId, CryptoHash)> {
+        (*self.registry_chain_id.get()).zip(*self.agent_id.get())
     }
 
     /// Log a new task
     pub async fn log_task(&mut self, entry: TaskEntry) -> Result<(), String> {
         // Update counters
-        let count = self.task_count.get();
+        let count = *self.task_count.get();
         self.task_count.set(count + 1);
+        self.task_index
+            .insert(&entry.task_hash, self.task_log.count() as u64)
+            .map_err(|e| e.to_string())?;
 
         if entry.success {
             let success = self.success_count.get();
@@ -44,6 +230,7 @@ impl AgentClientState {
             let failure = self.failure_count.get();
             self.failure_count.set(failure + 1);
         }
+        self.successes_through.push(*self.success_count.get());
 
         // Append to log
         self.task_log.push(entry);
@@ -51,6 +238,288 @@ impl AgentClientState {
         Ok(())
     }
 
+    /// Change the batching thresholds and mode
+    pub fn configure_batching(&mut self, max_tasks: u32, max_age_secs: Option<u64>, anchor_roots: bool) {
+        self.batch_max_tasks.set(max_tasks);
+        self.batch_max_age_secs.set(max_age_secs);
+        self.batch_anchor_roots.set(anchor_roots);
+    }
+
+    /// Add a task to the outgoing batch
+    pub fn buffer_task(&mut self, report: TaskReport) {
+        self.outgoing_batch.get_mut().push(report);
+    }
+
+    /// Whether the outgoing batch has reached the count or age threshold at `now`
+    pub fn batch_due(&self, now: Timestamp) -> bool {
+        batch_due(
+            self.outgoing_batch.get(),
+            *self.batch_max_tasks.get(),
+            *self.batch_max_age_secs.get(),
+            now,
+        )
+    }
+
+    /// Empty the outgoing batch once its messages are queued
+    pub fn clear_batch(&mut self) {
+        self.outgoing_batch.get_mut().clear();
+        self.last_flush_error.set(None);
+    }
+
+    /// Get the task log entries in `range` of indices
+    pub async fn read_tasks(&self, range: std::ops::Range<u64>) -> Result<Vec<TaskEntry>, String> {
+        self.task_log
+            .read(range.start as usize..range.end as usize)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Find a task and its position in the log by its hash
+    pub async fn find_task(&self, task_hash: &[u8; 32]) -> Result<Option<(u64, TaskEntry)>, String> {
+        let Some(index) = self.task_index.get(task_hash).await.map_err(|e| e.to_string())? else {
+            return Ok(None);
+        };
+        let entry = self.task_log.get(index as usize).await.map_err(|e| e.to_string())?;
+        Ok(entry.map(|entry| (index, entry)))
+    }
+
+    /// Walk the task log from newest to oldest, passing each entry and its
+    /// position to `visit` until it returns `false`
+    pub async fn scan_tasks_newest_first(
+        &self,
+        mut visit: impl FnMut(u64, &TaskEntry) -> bool,
+    ) -> Result<(), String> {
+        const PAGE: usize = 100;
+        let mut end = self.task_log.count();
+        while end > 0 {
+            let start = end.saturating_sub(PAGE);
+            let page = self.task_log.read(start..end).await.map_err(|e| e.to_string())?;
+            for (offset, entry) in page.iter().enumerate().rev() {
+                if !visit((start + offset) as u64, entry) {
+                    return Ok(());
+                }
+            }
+            end = start;
+        }
+        Ok(())
+    }
+
+    /// Number of tasks, and of successful tasks, logged after `cutoff` (every
+    /// task for `None`)
+    pub async fn stats_after(&self, cutoff: Option<Timestamp>) -> Result<(u64, u64), String> {
+        let first = match cutoff {
+            // The log is in time order
+            Some(cutoff) => partition_point(&self.task_log, |entry| entry.timestamp <= cutoff).await?,
+            None => 0,
+        };
+        let successes_before = match first.checked_sub(1) {
+            Some(position) => self
+                .successes_through
+                .get(position)
+                .await
+                .map_err(|e| e.to_string())?
+                .unwrap_or_default(),
+            None => 0,
+        };
+        Ok((
+            self.task_log.count() as u64 - first as u64,
+            *self.success_count.get() - successes_before,
+        ))
+    }
+
+    /// Find the anchor whose batch contains the task at `index`
+    pub async fn anchor_for_task(&self, index: u64) -> Result<Option<TaskAnchor>, String> {
+        // Anchors cover consecutive ranges of the log in order, so only the last
+        // one starting at or before `index` can contain it
+        let after = partition_point(&self.anchors, |anchor| anchor.first_task <= index).await?;
+        let Some(position) = after.checked_sub(1) else {
+            return Ok(None);
+        };
+        let anchor = self.anchors.get(position).await.map_err(|e| e.to_string())?;
+        Ok(anchor.filter(|anchor| index < anchor.first_task + anchor.task_count))
+    }
+
+    /// Record a message sent to the registry `registry_application_id` on
+    /// `registry_chain_id`, returning its outbox sequence number
+    pub async fn record_sent(
+        &mut self,
+        message: Message,
+        registry_chain_id: ChainId,
+        registry_application_id: ApplicationId,
+        now: Timestamp,
+    ) -> Result<u64, String> {
+        let id = *self.next_outbox_id.get();
+        self.next_outbox_id.set(id + 1);
+        self.outbox_keys
+            .get_mut_or_default(&delivery_key(&message, registry_chain_id))
+            .await
+            .map_err(|e| e.to_string())?
+            .push(id);
+        self.update_delivery_stats((registry_chain_id, registry_application_id), |stats| {
+            stats.record_sent(&message, now)
+        })
+        .await?;
+        self.outbox
+            .insert(
+                &id,
+                OutboxEntry::new(id, message, registry_chain_id, registry_application_id, now),
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(id)
+    }
+
+    /// Mark the outbox entry of a message bounced by `origin` as undelivered,
+    /// returning its sequence number (`None` if the message was never recorded)
+    pub async fn mark_bounced(
+        &mut self,
+        message: &Message,
+        origin: ChainId,
+        now: Timestamp,
+    ) -> Result<Option<u64>, String> {
+        let ids = self
+            .outbox_keys
+            .get(&delivery_key(message, origin))
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default();
+        let mut entries = Vec::new();
+        for id in ids {
+            if let Some(entry) = self.outbox.get(&id).await.map_err(|e| e.to_string())? {
+                entries.push(entry);
+            }
+        }
+        let Some(id) = bounced_entry(&entries) else {
+            return Ok(None);
+        };
+        let Some(mut entry) = entries.into_iter().find(|entry| entry.id == id) else {
+            return Ok(None);
+        };
+        let count = self.bounced_count.get();
+        self.bounced_count.set(count + 1);
+        self.update_delivery_stats(
+            (entry.registry_chain_id, entry.registry_application_id),
+            DeliveryStats::record_bounce,
+        )
+        .await?;
+        entry.mark_bounced(now);
+        self.outbox.insert(&id, entry).map_err(|e| e.to_string())?;
+        let undelivered = self.undelivered.get_mut();
+        if !undelivered.contains(&id) {
+            undelivered.push(id);
+        }
+        Ok(Some(id))
+    }
+
+    /// Number the next audit request
+    pub fn next_audit_request_id(&mut self) -> u64 {
+        let id = *self.audit_request_count.get();
+        self.audit_request_count.set(id + 1);
+        id
+    }
+
+    /// Get the outbox entries that bounced and have not been retried, oldest first
+    pub async fn undelivered_entries(&self) -> Result<Vec<OutboxEntry>, String> {
+        let mut entries = Vec::new();
+        for id in self.undelivered.get() {
+            if let Some(entry) = self.outbox.get(id).await.map_err(|e| e.to_string())? {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Mark undelivered entries (all of them if `ids` is empty) as sent again,
+    /// returning the messages to re-send and the chains they were first sent to
+    pub async fn retry_undelivered(&mut self, ids: &[u64], now: Timestamp) -> Result<Vec<(Message, ChainId)>, String> {
+        let retried = take_retries(self.undelivered.get_mut(), ids)?;
+
+        let mut messages = Vec::new();
+        for id in retried {
+            let Some(mut entry) = self.outbox.get(&id).await.map_err(|e| e.to_string())? else {
+                continue;
+            };
+            self.update_delivery_stats((entry.registry_chain_id, entry.registry_application_id), |stats| {
+                stats.record_retry(now)
+            })
+            .await?;
+            entry.mark_resent(now);
+            messages.push((entry.message.clone(), entry.registry_chain_id));
+            self.outbox.insert(&id, entry).map_err(|e| e.to_string())?;
+        }
+        Ok(messages)
+    }
+
+    /// Store standing updates read from the registry: cache the newest badge,
+    /// add the notifications to the inbox and move the read cursor on
+    pub fn store_standing(&mut self, sync: StandingSync) {
+        if let Some(badge) = sync.badge {
+            self.badge.set(Some(badge));
+        }
+        for notification in sync.notifications {
+            self.inbox.push(notification);
+        }
+        self.standing_cursor.set(sync.next);
+    }
+
+    /// Apply `update` to the delivery counters of `registry` (chain and application ID)
+    async fn update_delivery_stats(
+        &mut self,
+        registry: (ChainId, ApplicationId),
+        update: impl FnOnce(&mut DeliveryStats),
+    ) -> Result<(), String> {
+        let stats = self
+            .delivery_stats
+            .get_mut_or_default(&registry)
+            .await
+            .map_err(|e| e.to_string())?;
+        update(stats);
+        Ok(())
+    }
+
+    /// Add or replace an extra registry target (owner only). The primary
+    /// registry cannot also be a target.
+    pub async fn add_registry_target(&mut self, target: RegistryTarget, signer: AccountOwner) -> Result<(), String> {
+        self.check_owner(&signer)?;
+        let (primary_chain_id, primary_application_id, _) =
+            self.current_registry().ok_or("Not initialized with registry")?;
+        if (target.chain_id, target.application_id) == (primary_chain_id, primary_application_id) {
+            return Err("This is the primary registry".to_string());
+        }
+        let key = (target.chain_id, target.application_id);
+        self.registry_targets
+            .insert(&key, target)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Remove an extra registry target (owner only)
+    pub async fn remove_registry_target(
+        &mut self,
+        registry: (ChainId, ApplicationId),
+        signer: AccountOwner,
+    ) -> Result<(), String> {
+        self.check_owner(&signer)?;
+        if !self
+            .registry_targets
+            .contains_key(&registry)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            return Err("No registry target with this chain and application ID".to_string());
+        }
+        self.registry_targets.remove(&registry).map_err(|e| e.to_string())
+    }
+
+    /// Get every extra registry target
+    pub async fn get_registry_targets(&self) -> Result<Vec<RegistryTarget>, String> {
+        let mut targets = Vec::new();
+        for registry in self.registry_targets.indices().await.map_err(|e| e.to_string())? {
+            if let Some(target) = self.registry_targets.get(&registry).await.map_err(|e| e.to_string())? {
+                targets.push(target);
+            }
+        }
+        Ok(targets)
+    }
+
     /// Get task statistics
     pub fn get_stats(&self) -> (u64, u64, u64) {
         (
@@ -60,3 +529,26 @@ impl AgentClientState {
         )
     }
 }
+
+/// Number of leading entries of `log` for which `before` holds, given that it
+/// holds for a prefix of the log and for nothing after it
+async fn partition_point<T>(log: &LogView<T>, before: impl Fn(&T) -> bool) -> Result<usize, String>
+where
+    T: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    let (mut low, mut high) = (0, log.count());
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let entry = log
+            .get(middle)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("Log entry missing")?;
+        if before(&entry) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    Ok(low)
+}
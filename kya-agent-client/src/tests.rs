@@ -0,0 +1,464 @@
+//! Unit tests for KYA Agent Client
+//!
+//! These tests cover the pure logic shared by the contract and service; state
+//! views and messaging need Linera's test harness.
+
+use super::*;
+
+fn test_entry(seq: u8, success: bool, micros: u64) -> TaskEntry {
+    TaskEntry {
+        task_hash: [seq; 32],
+        capability: "defi".to_string(),
+        success,
+        counterparty: None,
+        timestamp: Timestamp::from(micros),
+        description: format!("task {}", seq),
+        details: TaskDetails::default(),
+    }
+}
+
+#[test]
+fn test_client_proofs_verify_against_registry_root() {
+    for count in 1..=9u8 {
+        let entries: Vec<TaskEntry> = (0..count).map(|seq| test_entry(seq, seq % 3 != 0, seq as u64)).collect();
+        let leaves: Vec<[u8; 32]> = entries.iter().map(merkle::leaf_hash).collect();
+        // The root the client anchors is the one the registry checks proofs against
+        let root = merkle::merkle_root(&leaves);
+        assert_eq!(root, kya_registry::merkle::merkle_root(&leaves));
+
+        for (index, entry) in entries.iter().enumerate() {
+            let siblings = merkle::merkle_proof(&leaves, index).unwrap();
+            let leaf = kya_registry::merkle::task_leaf_hash(
+                &entry.task_hash,
+                &entry.capability,
+                entry.success,
+                entry.timestamp,
+                &entry.description,
+                entry.counterparty,
+                &entry.details,
+            );
+            assert!(kya_registry::merkle::verify_proof(leaf, index as u64, count as u64, &siblings, root));
+
+            // The proof is bound to its position in the batch
+            if count > 1 {
+                let other = (index + 1) % count as usize;
+                assert!(!kya_registry::merkle::verify_proof(leaf, other as u64, count as u64, &siblings, root));
+            }
+            assert!(!kya_registry::merkle::verify_proof(leaf, count as u64, count as u64, &siblings, root));
+        }
+        assert_eq!(merkle::merkle_proof(&leaves, count as usize), None);
+    }
+}
+
+fn test_update(agent_id: CryptoHash, score: u16, kind: AgentNotificationKind, micros: u64) -> AgentUpdate {
+    AgentUpdate {
+        agent_id,
+        kind,
+        detail: format!("Score -> {score}"),
+        badge: BadgeSnapshot {
+            reputation_score: score,
+            tier: "Bronze".to_string(),
+            status: AgentStatus::Active,
+            stake_balance: linera_sdk::linera_base_types::Amount::ZERO,
+            spam_flags: 0,
+            last_passed_audit: None,
+            customer_rating: None,
+            capabilities: vec!["defi".to_string()],
+        },
+        timestamp: Timestamp::from(micros),
+    }
+}
+
+#[test]
+fn test_standing_sync_stores_registry_updates() {
+    use linera_sdk::abi::ContractAbi;
+
+    let agent_id = CryptoHash::from([9; 32]);
+    let updates = vec![
+        test_update(agent_id, 510, AgentNotificationKind::ScoreChanged, 10),
+        test_update(agent_id, 540, AgentNotificationKind::AuditPassed, 20),
+    ];
+    // The reply to `ReadAgentUpdates` crosses the application boundary serialized
+    // with the registry's ABI, as `call_application` does
+    let bytes = KyaRegistryAbi::serialize_response(kya_registry::Response::AgentUpdates {
+        agent_id,
+        updates: updates.clone(),
+        next: 2,
+    })
+    .unwrap();
+    let response = KyaRegistryAbi::deserialize_response(bytes).unwrap();
+
+    let sync = StandingSync::from_response(response, agent_id, Timestamp::from(30)).unwrap();
+    assert_eq!(sync.next, 2);
+    assert_eq!(sync.badge, Some(updates[1].badge.clone()));
+    assert_eq!(sync.notifications.len(), 2);
+    assert_eq!(sync.notifications[0].kind, AgentNotificationKind::ScoreChanged);
+    assert_eq!(sync.notifications[0].reputation_score, 510);
+    assert_eq!(sync.notifications[1].kind, AgentNotificationKind::AuditPassed);
+    assert_eq!(sync.notifications[1].timestamp, Timestamp::from(20));
+    assert_eq!(sync.notifications[1].received_at, Timestamp::from(30));
+
+    // Nothing new keeps the cached badge and the cursor
+    let empty = kya_registry::Response::AgentUpdates { agent_id, updates: Vec::new(), next: 2 };
+    let sync = StandingSync::from_response(empty, agent_id, Timestamp::from(40)).unwrap();
+    assert_eq!((sync.next, sync.badge, sync.notifications.len()), (2, None, 0));
+}
+
+#[test]
+fn test_standing_sync_rejects_other_agents_and_errors() {
+    let agent_id = CryptoHash::from([9; 32]);
+    let other = CryptoHash::from([8; 32]);
+
+    let for_other = kya_registry::Response::AgentUpdates {
+        agent_id: other,
+        updates: vec![test_update(other, 500, AgentNotificationKind::ScoreChanged, 1)],
+        next: 1,
+    };
+    assert!(StandingSync::from_response(for_other, agent_id, Timestamp::from(2)).is_err());
+
+    let mixed = kya_registry::Response::AgentUpdates {
+        agent_id,
+        updates: vec![test_update(other, 500, AgentNotificationKind::ScoreChanged, 1)],
+        next: 1,
+    };
+    assert!(StandingSync::from_response(mixed, agent_id, Timestamp::from(2)).is_err());
+
+    let error = kya_registry::Response::Error("boom".to_string());
+    assert_eq!(
+        StandingSync::from_response(error, agent_id, Timestamp::from(2)).unwrap_err(),
+        "boom"
+    );
+}
+
+fn test_chain(byte: u8) -> ChainId {
+    ChainId(CryptoHash::from([byte; 32]))
+}
+
+fn test_owner(byte: u8) -> AccountOwner {
+    AccountOwner::Address32(CryptoHash::from([byte; 32]))
+}
+
+fn test_report(seq: u8, capability: &str, counterparty: Option<AccountOwner>) -> TaskReport {
+    TaskReport {
+        task_hash: [seq; 32],
+        capability: capability.to_string(),
+        success: true,
+        counterparty,
+        timestamp: Timestamp::from(u64::from(seq)),
+        details: TaskDetails::default(),
+    }
+}
+
+fn test_target(capabilities: &[&str], attested_only: bool) -> RegistryTarget {
+    RegistryTarget {
+        chain_id: test_chain(5),
+        application_id: ApplicationId::new(CryptoHash::from([5; 32])),
+        agent_id: CryptoHash::from([6; 32]),
+        capabilities: capabilities.iter().map(|capability| capability.to_string()).collect(),
+        attested_only,
+        added_at: Timestamp::from(0),
+    }
+}
+
+#[test]
+fn test_registry_target_filters() {
+    let plain_defi = test_report(1, "defi", None);
+    let attested_defi = test_report(2, "defi", Some(test_owner(3)));
+    let attested_nft = test_report(3, "nft", Some(test_owner(3)));
+
+    // No filters: every task
+    let everything = test_target(&[], false);
+    assert!(everything.accepts(&plain_defi));
+    assert!(everything.accepts(&attested_nft));
+
+    // Capability filter only
+    let defi_only = test_target(&["defi", "lending"], false);
+    assert!(defi_only.accepts(&plain_defi));
+    assert!(defi_only.accepts(&attested_defi));
+    assert!(!defi_only.accepts(&attested_nft));
+
+    // Attested-only filter
+    let attested = test_target(&[], true);
+    assert!(!attested.accepts(&plain_defi));
+    assert!(attested.accepts(&attested_defi));
+    assert!(attested.accepts(&attested_nft));
+
+    // Both filters must pass
+    let attested_defi_only = test_target(&["defi"], true);
+    assert!(!attested_defi_only.accepts(&plain_defi));
+    assert!(attested_defi_only.accepts(&attested_defi));
+    assert!(!attested_defi_only.accepts(&attested_nft));
+}
+
+#[test]
+fn test_delivery_stats_counting() {
+    let agent_id = CryptoHash::from([9; 32]);
+    let single = Message::ActivityLog {
+        agent_id,
+        task_hash: [1; 32],
+        capability: "defi".to_string(),
+        success: true,
+        counterparty: None,
+        timestamp: Timestamp::from(1),
+        details: TaskDetails::default(),
+    };
+    let batch = Message::ActivityBatch {
+        agent_id,
+        tasks: vec![test_report(2, "defi", None), test_report(3, "nft", None)],
+    };
+    let anchor = TaskAnchor {
+        epoch: 0,
+        root: [7; 32],
+        first_task: 0,
+        task_count: 5,
+        tallies: Vec::new(),
+        performance: TaskPerformance::default(),
+        timestamp: Timestamp::from(4),
+    };
+    let root = Message::TaskRoot {
+        agent_id,
+        anchor,
+        attested_tasks: vec![test_report(4, "defi", Some(test_owner(3)))],
+    };
+    let audit = Message::AuditRequest { agent_id, request_id: 0, timestamp: Timestamp::from(5) };
+
+    let mut stats = DeliveryStats::default();
+    stats.record_sent(&single, Timestamp::from(10));
+    stats.record_sent(&batch, Timestamp::from(11));
+    // Every task under an anchored root counts, not just the attested ones sent in full
+    stats.record_sent(&root, Timestamp::from(12));
+    stats.record_sent(&audit, Timestamp::from(13));
+    assert_eq!(
+        stats,
+        DeliveryStats {
+            messages_sent: 4,
+            tasks_sent: 8,
+            messages_bounced: 0,
+            last_sent_at: Some(Timestamp::from(13)),
+        }
+    );
+
+    // A bounce and its retry count as messages, not as more tasks
+    stats.record_bounce();
+    stats.record_retry(Timestamp::from(20));
+    assert_eq!(
+        stats,
+        DeliveryStats {
+            messages_sent: 5,
+            tasks_sent: 8,
+            messages_bounced: 1,
+            last_sent_at: Some(Timestamp::from(20)),
+        }
+    );
+}
+
+#[test]
+fn test_batch_due_thresholds() {
+    let secs = |secs: u64| Timestamp::from(secs * 1_000_000);
+    let report_at = |seq: u8, at: Timestamp| TaskReport { timestamp: at, ..test_report(seq, "defi", None) };
+
+    // Nothing buffered is never due
+    assert!(!batch_due(&[], 3, Some(0), secs(100)));
+
+    // 0 or 1 sends every task on its own
+    let one = vec![report_at(1, secs(10))];
+    assert!(batch_due(&one, 0, None, secs(10)));
+    assert!(batch_due(&one, 1, None, secs(10)));
+
+    // Count threshold
+    let two = vec![report_at(1, secs(10)), report_at(2, secs(11))];
+    assert!(!batch_due(&two, 3, None, secs(1000)));
+    let three = vec![report_at(1, secs(10)), report_at(2, secs(11)), report_at(3, secs(12))];
+    assert!(batch_due(&three, 3, None, secs(12)));
+
+    // Age threshold, measured from the oldest task
+    assert!(!batch_due(&two, 3, Some(60), secs(69)));
+    assert!(batch_due(&two, 3, Some(60), secs(70)));
+}
+
+#[test]
+fn test_outbox_entry_bounce_and_resend() {
+    let message = Message::AuditRequest {
+        agent_id: CryptoHash::from([9; 32]),
+        request_id: 0,
+        timestamp: Timestamp::from(1),
+    };
+    let (chain_id, application_id, _) = test_registry(5);
+    let mut entry = OutboxEntry::new(4, message, chain_id, application_id, Timestamp::from(10));
+    assert_eq!((entry.status, entry.attempts, entry.bounced_at), (DeliveryStatus::Sent, 1, None));
+
+    entry.mark_bounced(Timestamp::from(20));
+    assert_eq!(entry.status, DeliveryStatus::Bounced);
+    assert_eq!(entry.bounced_at, Some(Timestamp::from(20)));
+
+    entry.mark_resent(Timestamp::from(30));
+    assert_eq!((entry.status, entry.attempts, entry.sent_at), (DeliveryStatus::Sent, 2, Timestamp::from(30)));
+    // The last bounce stays on record
+    assert_eq!(entry.bounced_at, Some(Timestamp::from(20)));
+}
+
+#[test]
+fn test_take_retries_requeues_bounced_messages() {
+    // Selected IDs are taken; the rest stay queued in order
+    let mut undelivered = vec![3, 5, 8];
+    assert_eq!(take_retries(&mut undelivered, &[8, 3]).unwrap(), vec![3, 8]);
+    assert_eq!(undelivered, vec![5]);
+
+    // An ID that is not undelivered fails without taking anything
+    let mut undelivered = vec![3, 5];
+    assert!(take_retries(&mut undelivered, &[5, 7]).is_err());
+    assert_eq!(undelivered, vec![3, 5]);
+
+    // No IDs takes everything
+    assert_eq!(take_retries(&mut undelivered, &[]).unwrap(), vec![3, 5]);
+    assert!(undelivered.is_empty());
+
+    // A message that bounces again after its retry is queued again
+    undelivered.push(5);
+    assert_eq!(take_retries(&mut undelivered, &[5]).unwrap(), vec![5]);
+}
+
+#[test]
+fn test_delivery_keys_tell_messages_apart() {
+    let agent_id = CryptoHash::from([9; 32]);
+    let audit = |request_id: u64| Message::AuditRequest { agent_id, request_id, timestamp: Timestamp::from(7) };
+
+    // Audit requests sent in the same block get distinct keys
+    assert_ne!(delivery_key(&audit(0), test_chain(1)), delivery_key(&audit(1), test_chain(1)));
+    assert_eq!(delivery_key(&audit(0), test_chain(1)), delivery_key(&audit(0), test_chain(1)));
+
+    // The same message sent to two registry chains gets two keys
+    let log = test_activity_log(agent_id);
+    assert_ne!(delivery_key(&log, test_chain(1)), delivery_key(&log, test_chain(2)));
+}
+
+fn test_activity_log(agent_id: CryptoHash) -> Message {
+    let report = test_report(1, "defi", None);
+    Message::ActivityLog {
+        agent_id,
+        task_hash: report.task_hash,
+        capability: report.capability,
+        success: report.success,
+        counterparty: report.counterparty,
+        timestamp: report.timestamp,
+        details: report.details,
+    }
+}
+
+#[test]
+fn test_retry_permissions_follow_the_message() {
+    let agent_id = CryptoHash::from([9; 32]);
+    assert_eq!(required_scope(&test_activity_log(agent_id)), Some(DelegationScope::LogTasks));
+    assert_eq!(
+        required_scope(&Message::ActivityBatch { agent_id, tasks: vec![test_report(1, "defi", None)] }),
+        Some(DelegationScope::LogTasks)
+    );
+    // Only the owner may request audits, so only the owner may retry one
+    assert_eq!(
+        required_scope(&Message::AuditRequest { agent_id, request_id: 0, timestamp: Timestamp::from(1) }),
+        None
+    );
+
+    // A LogTasks operator may retry activity but not an audit request
+    let delegation = Delegation::new(test_owner(4), vec![DelegationScope::LogTasks], Timestamp::from(0), None);
+    let may_retry = |message: &Message| {
+        required_scope(message).is_some_and(|scope| delegation.allows(scope, Timestamp::from(1)))
+    };
+    assert!(may_retry(&test_activity_log(agent_id)));
+    assert!(!may_retry(&Message::AuditRequest { agent_id, request_id: 0, timestamp: Timestamp::from(1) }));
+}
+
+fn test_registry(byte: u8) -> (ChainId, ApplicationId, CryptoHash) {
+    (test_chain(byte), ApplicationId::new(CryptoHash::from([byte; 32])), CryptoHash::from([byte + 100; 32]))
+}
+
+#[test]
+fn test_initialize_only_once_by_owner() {
+    let owner = test_owner(1);
+    assert!(check_initialize(Some(owner), &owner, false).is_ok());
+    assert!(check_initialize(Some(owner), &test_owner(2), false).is_err());
+    // No owner recorded means nobody can initialize
+    assert!(check_initialize(None, &owner, false).is_err());
+    // A second Initialize must go through MigrateRegistry instead
+    assert!(check_initialize(Some(owner), &owner, true).is_err());
+}
+
+#[test]
+fn test_migrate_registry_owner_only() {
+    let owner = test_owner(1);
+    assert!(check_owner(Some(owner), &owner).is_ok());
+    assert!(check_owner(Some(owner), &test_owner(2)).is_err());
+    assert!(check_owner(None, &owner).is_err());
+}
+
+#[test]
+fn test_registry_migration_log() {
+    let now = Timestamp::from(1_000);
+    // Migrating needs a registry to migrate from, and a different one to go to
+    assert!(RegistryMigration::new(None, test_registry(1), String::new(), 0, now).is_err());
+    assert!(RegistryMigration::new(Some(test_registry(1)), test_registry(1), String::new(), 0, now).is_err());
+
+    // Each entry leaves from where the previous one arrived
+    let mut current = test_registry(1);
+    let mut log = Vec::new();
+    for (byte, flushed_tasks) in [(2u8, 3u32), (3, 0)] {
+        let migration =
+            RegistryMigration::new(Some(current), test_registry(byte), format!("to {}", byte), flushed_tasks, now)
+                .unwrap();
+        current = (migration.to_chain_id, migration.to_application_id, migration.to_agent_id);
+        log.push(migration);
+    }
+    assert_eq!(log.len(), 2);
+    assert_eq!(
+        (log[0].from_chain_id, log[0].from_application_id, log[0].from_agent_id),
+        test_registry(1)
+    );
+    assert_eq!(
+        (log[1].from_chain_id, log[1].from_application_id, log[1].from_agent_id),
+        test_registry(2)
+    );
+    assert_eq!(current, test_registry(3));
+    assert_eq!(log[0].flushed_tasks, 3);
+    assert_eq!(log[1].reason, "to 3");
+
+    // Keeping the chain but switching application or agent ID is still a migration
+    let (chain_id, application_id, _) = test_registry(3);
+    let same_chain = (chain_id, application_id, CryptoHash::from([7; 32]));
+    assert!(RegistryMigration::new(Some(current), same_chain, String::new(), 0, now).is_ok());
+}
+
+#[test]
+fn test_undeclared_capabilities_are_refused_before_logging() {
+    let badge = test_update(CryptoHash::from([1; 32]), 500, AgentNotificationKind::ScoreChanged, 0).badge;
+    assert!(check_capability(Some(&badge), "defi").is_ok());
+    assert!(check_capability(Some(&badge), "social").is_err());
+    // Before the first standing update the registry's check is the only one
+    assert!(check_capability(None, "social").is_ok());
+}
+
+#[test]
+fn test_migration_is_checked_before_anything_is_flushed() {
+    // The contract runs this check before flushing the buffered batch
+    assert_eq!(check_migration(Some(test_registry(1)), test_registry(2)), Ok(test_registry(1)));
+    assert!(check_migration(None, test_registry(2)).is_err());
+    assert!(check_migration(Some(test_registry(1)), test_registry(1)).is_err());
+}
+
+#[test]
+fn test_bounces_match_the_oldest_copy_in_flight() {
+    let message = test_activity_log(CryptoHash::from([9; 32]));
+    // The same report sent to two registries on one chain shares a delivery key
+    let (chain_id, public_registry, _) = test_registry(5);
+    let consortium_registry = ApplicationId::new(CryptoHash::from([6; 32]));
+    let mut entries = vec![
+        OutboxEntry::new(3, message.clone(), chain_id, public_registry, Timestamp::from(10)),
+        OutboxEntry::new(7, message, chain_id, consortium_registry, Timestamp::from(10)),
+    ];
+    assert_eq!(bounced_entry(&entries), Some(3));
+
+    // Once that copy is marked, the next bounce belongs to the other registry
+    entries[0].mark_bounced(Timestamp::from(20));
+    assert_eq!(bounced_entry(&entries), Some(7));
+    entries[1].mark_bounced(Timestamp::from(20));
+    assert_eq!(bounced_entry(&entries), None);
+}
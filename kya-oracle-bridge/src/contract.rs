@@ -2,7 +2,7 @@
 
 #![allow(unexpected_cfgs)]
 
-use kya_oracle_bridge::{KyaOracleBridgeAbi, Message, Operation, Response, ScoreCommitment};
+use kya_oracle_bridge::{InstantiationArgument, KyaOracleBridgeAbi, Message, Operation, Response, ScoreCommitment};
 use linera_sdk::{
     linera_base_types::WithContractAbi,
     views::{RootView, View},
@@ -10,8 +10,7 @@ use linera_sdk::{
 };
 use sha2::{Digest, Sha256};
 
-mod state;
-use state::OracleBridgeState;
+use kya_oracle_bridge::state::OracleBridgeState;
 
 linera_sdk::contract!(OracleBridgeContract);
 
@@ -24,7 +23,7 @@ pub struct OracleBridgeContract {
 impl Contract for OracleBridgeContract {
     type Message = Message;
     type Parameters = ();
-    type InstantiationArgument = ();
+    type InstantiationArgument = InstantiationArgument;
     type EventValue = ();
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
@@ -34,15 +33,41 @@ impl Contract for OracleBridgeContract {
         OracleBridgeContract { state, runtime }
     }
 
-    async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {
-        // Default initialization
+    async fn instantiate(&mut self, argument: Self::InstantiationArgument) {
+        self.state.set_owner(argument.owner);
+        if let Some(registry_chain_id) = argument.registry_chain_id {
+            self.state
+                .initialize(registry_chain_id, argument.owner)
+                .expect("Failed to initialize with registry");
+        }
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> Response {
         match operation {
             Operation::Initialize { registry_chain_id } => {
-                self.state.initialize(registry_chain_id);
-                Response::Initialized
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
+                };
+                match self.state.initialize(registry_chain_id, signer) {
+                    Ok(()) => Response::Initialized,
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::MigrateRegistry {
+                registry_chain_id,
+                reason,
+            } => {
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
+                };
+                let now = self.runtime.system_time();
+                match self.state.migrate_registry(registry_chain_id, reason, signer, now) {
+                    Ok(()) => Response::RegistryMigrated { registry_chain_id },
+                    Err(e) => Response::Error(e),
+                }
             }
 
             Operation::RequestCommitment { agent_id } => {
@@ -66,6 +91,13 @@ impl Contract for OracleBridgeContract {
             }
 
             Operation::RegisterCommitment { commitment } => {
+                let signer = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Must be authenticated".to_string()),
+                };
+                if let Err(e) = self.state.check_owner(&signer) {
+                    return Response::Error(e);
+                }
                 let hash = commitment.commitment_hash;
                 if let Err(e) = self.state.store_commitment(commitment).await {
                     return Response::Error(e);
@@ -3,7 +3,12 @@
 //! This crate provides a bridge for exporting Linera reputation data
 //! to external chains like Base/Solana for DeFi integration.
 
-use linera_sdk::linera_base_types::{AccountOwner, ChainId, Timestamp};
+pub mod state;
+
+#[cfg(test)]
+mod tests;
+
+use linera_sdk::linera_base_types::{AccountOwner, ChainId, CryptoHash, Timestamp};
 use serde::{Deserialize, Serialize};
 
 /// The Application Binary Interface for KYA Oracle Bridge
@@ -16,8 +21,8 @@ pub struct KyaOracleBridgeAbi;
 /// A signed commitment of an agent's score for external verification
 #[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 pub struct ScoreCommitment {
-    /// The agent's ID
-    pub agent_id: AccountOwner,
+    /// The agent's registry ID
+    pub agent_id: CryptoHash,
     /// The reputation score at time of commitment
     pub score: u16,
     /// The tier at time of commitment
@@ -31,6 +36,69 @@ pub struct ScoreCommitment {
     pub commitment_hash: [u8; 32],
 }
 
+/// Argument given when the application is created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstantiationArgument {
+    /// The bridge's owner, the only account that may point it at a registry
+    pub owner: AccountOwner,
+    /// Registry chain to use from the start; otherwise set by `Initialize`
+    #[serde(default)]
+    pub registry_chain_id: Option<ChainId>,
+}
+
+/// A change of the registry the bridge requests scores from
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct RegistryMigration {
+    /// Registry chain used before
+    pub from_chain_id: ChainId,
+    /// Registry chain used from now on
+    pub to_chain_id: ChainId,
+    /// Why the owner migrated
+    pub reason: String,
+    /// When the migration happened
+    pub timestamp: Timestamp,
+}
+
+impl RegistryMigration {
+    /// Describe the switch from the `current` registry chain to `to_chain_id`.
+    /// Fails if the bridge is not initialized or already uses `to_chain_id`.
+    pub fn new(
+        current: Option<ChainId>,
+        to_chain_id: ChainId,
+        reason: String,
+        timestamp: Timestamp,
+    ) -> Result<Self, String> {
+        let from_chain_id = current.ok_or("Not initialized with registry")?;
+        if from_chain_id == to_chain_id {
+            return Err("Already using this registry".to_string());
+        }
+        Ok(RegistryMigration {
+            from_chain_id,
+            to_chain_id,
+            reason,
+            timestamp,
+        })
+    }
+}
+
+/// Fail unless `signer` is the `owner`
+pub fn check_owner(owner: Option<AccountOwner>, signer: &AccountOwner) -> Result<(), String> {
+    if owner != Some(*signer) {
+        return Err("Only the owner can do this".to_string());
+    }
+    Ok(())
+}
+
+/// Fail unless `signer` is the `owner` and no registry is set yet; later
+/// changes go through `MigrateRegistry`
+pub fn check_initialize(owner: Option<AccountOwner>, signer: &AccountOwner, initialized: bool) -> Result<(), String> {
+    check_owner(owner, signer)?;
+    if initialized {
+        return Err("Already initialized; use MigrateRegistry to change registry".to_string());
+    }
+    Ok(())
+}
+
 /// External chain target for score export
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ExternalChain {
@@ -47,15 +115,21 @@ pub enum ExternalChain {
 /// Operations that can be performed by the oracle bridge
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Operation {
-    /// Initialize the bridge with registry chain ID
+    /// Initialize the bridge with registry chain ID (owner only, once); use
+    /// `MigrateRegistry` to change it later
     Initialize {
         registry_chain_id: ChainId,
     },
+    /// Point the bridge at another registry (owner only); the change is kept in `migrations`
+    MigrateRegistry {
+        registry_chain_id: ChainId,
+        reason: String,
+    },
     /// Request a score commitment for an agent
     RequestCommitment {
-        agent_id: AccountOwner,
+        agent_id: CryptoHash,
     },
-    /// Register a score commitment (after receiving from registry)
+    /// Register a score commitment (after receiving from registry; owner only)
     RegisterCommitment {
         commitment: ScoreCommitment,
     },
@@ -66,8 +140,10 @@ pub enum Operation {
 pub enum Response {
     /// Bridge initialized
     Initialized,
+    /// Bridge now requests scores from another registry
+    RegistryMigrated { registry_chain_id: ChainId },
     /// Commitment requested
-    CommitmentRequested { agent_id: AccountOwner },
+    CommitmentRequested { agent_id: CryptoHash },
     /// Commitment registered
     CommitmentRegistered { commitment_hash: [u8; 32] },
     /// Error occurred
@@ -83,12 +159,12 @@ pub enum Response {
 pub enum Message {
     /// Request score from registry
     ScoreRequest {
-        agent_id: AccountOwner,
+        agent_id: CryptoHash,
         requester_chain: ChainId,
     },
     /// Score response from registry
     ScoreResponse {
-        agent_id: AccountOwner,
+        agent_id: CryptoHash,
         score: u16,
         tier: String,
         timestamp: Timestamp,
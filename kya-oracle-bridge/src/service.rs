@@ -5,15 +5,14 @@
 use std::sync::Arc;
 
 use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
-use kya_oracle_bridge::KyaOracleBridgeAbi;
+use kya_oracle_bridge::{KyaOracleBridgeAbi, RegistryMigration};
 use linera_sdk::{
-    linera_base_types::WithServiceAbi,
+    linera_base_types::{AccountOwner, ChainId, WithServiceAbi},
     views::View,
     Service, ServiceRuntime,
 };
 
-mod state;
-use state::OracleBridgeState;
+use kya_oracle_bridge::state::OracleBridgeState;
 
 linera_sdk::service!(OracleBridgeService);
 
@@ -39,9 +38,14 @@ impl Service for OracleBridgeService {
 
     async fn handle_query(&self, request: Request) -> Response {
         let total_commitments = *self.state.total_commitments.get();
+        let count = self.state.migrations.count();
+        let migrations = self.state.migrations.read(0..count).await.unwrap_or_default();
         let schema = Schema::build(
             QueryRoot {
                 total_commitments,
+                owner: *self.state.owner.get(),
+                registry_chain_id: *self.state.registry_chain_id.get(),
+                migrations,
             },
             MutationRoot,
             EmptySubscription,
@@ -61,6 +65,9 @@ impl WithServiceAbi for OracleBridgeService {
 
 struct QueryRoot {
     total_commitments: u64,
+    owner: Option<AccountOwner>,
+    registry_chain_id: Option<ChainId>,
+    migrations: Vec<RegistryMigration>,
 }
 
 #[Object]
@@ -69,6 +76,21 @@ impl QueryRoot {
     async fn total_commitments(&self) -> u64 {
         self.total_commitments
     }
+
+    /// Get the bridge's owner
+    async fn owner(&self) -> Option<AccountOwner> {
+        self.owner
+    }
+
+    /// Get the registry chain scores are requested from
+    async fn registry_chain_id(&self) -> Option<ChainId> {
+        self.registry_chain_id
+    }
+
+    /// Changes of the registry chain, oldest first
+    async fn migrations(&self) -> &Vec<RegistryMigration> {
+        &self.migrations
+    }
 }
 
 struct MutationRoot;
@@ -1,8 +1,8 @@
 //! KYA Oracle Bridge State - Store score commitments
 
-use kya_oracle_bridge::ScoreCommitment;
-use linera_sdk::linera_base_types::{AccountOwner, ChainId};
-use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext};
+use crate::{RegistryMigration, ScoreCommitment};
+use linera_sdk::linera_base_types::{AccountOwner, ChainId, CryptoHash, Timestamp};
+use linera_sdk::views::{linera_views, LogView, MapView, RegisterView, RootView, ViewStorageContext};
 
 /// The root state of the KYA Oracle Bridge application
 #[derive(RootView, async_graphql::SimpleObject)]
@@ -10,16 +10,47 @@ use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, ViewStora
 pub struct OracleBridgeState {
     /// The chain ID of the KYA Registry hub
     pub registry_chain_id: RegisterView<Option<ChainId>>,
+    /// The bridge's owner, set at instantiation
+    pub owner: RegisterView<Option<AccountOwner>>,
+    /// Changes of the registry chain, oldest first
+    pub migrations: LogView<RegistryMigration>,
     /// Map of agent IDs to their latest score commitments
-    pub commitments: MapView<AccountOwner, ScoreCommitment>,
+    pub commitments: MapView<CryptoHash, ScoreCommitment>,
     /// Total number of commitments generated
     pub total_commitments: RegisterView<u64>,
 }
 
 impl OracleBridgeState {
-    /// Initialize with registry chain ID
-    pub fn initialize(&mut self, registry_chain_id: ChainId) {
+    /// Record the owner given at instantiation
+    pub fn set_owner(&mut self, owner: AccountOwner) {
+        self.owner.set(Some(owner));
+    }
+
+    /// Fail unless `signer` is the owner
+    pub fn check_owner(&self, signer: &AccountOwner) -> Result<(), String> {
+        crate::check_owner(*self.owner.get(), signer)
+    }
+
+    /// Initialize with registry chain ID (owner only, once)
+    pub fn initialize(&mut self, registry_chain_id: ChainId, signer: AccountOwner) -> Result<(), String> {
+        crate::check_initialize(*self.owner.get(), &signer, self.get_registry().is_some())?;
         self.registry_chain_id.set(Some(registry_chain_id));
+        Ok(())
+    }
+
+    /// Switch to another registry chain, logging the change
+    pub fn migrate_registry(
+        &mut self,
+        registry_chain_id: ChainId,
+        reason: String,
+        signer: AccountOwner,
+        now: Timestamp,
+    ) -> Result<(), String> {
+        self.check_owner(&signer)?;
+        let migration = RegistryMigration::new(self.get_registry(), registry_chain_id, reason, now)?;
+        self.migrations.push(migration);
+        self.registry_chain_id.set(Some(registry_chain_id));
+        Ok(())
     }
 
     /// Get the registry chain ID
@@ -43,7 +74,7 @@ impl OracleBridgeState {
     /// Get a commitment for an agent
     pub async fn get_commitment(
         &self,
-        agent_id: &AccountOwner,
+        agent_id: &CryptoHash,
     ) -> Result<Option<ScoreCommitment>, String> {
         self.commitments.get(agent_id).await.map_err(|e| e.to_string())
     }
@@ -0,0 +1,56 @@
+//! Unit tests for KYA Oracle Bridge
+//!
+//! These tests cover the pure logic behind the contract's owner checks; state
+//! views and messaging need Linera's test harness.
+
+use super::*;
+
+fn test_chain(byte: u8) -> ChainId {
+    ChainId(CryptoHash::from([byte; 32]))
+}
+
+fn test_owner(byte: u8) -> AccountOwner {
+    AccountOwner::Address32(CryptoHash::from([byte; 32]))
+}
+
+#[test]
+fn test_initialize_only_once_by_owner() {
+    let owner = test_owner(1);
+    assert!(check_initialize(Some(owner), &owner, false).is_ok());
+    assert!(check_initialize(Some(owner), &test_owner(2), false).is_err());
+    // No owner recorded means nobody can initialize
+    assert!(check_initialize(None, &owner, false).is_err());
+    // A second Initialize must go through MigrateRegistry instead
+    assert!(check_initialize(Some(owner), &owner, true).is_err());
+}
+
+#[test]
+fn test_owner_only_operations() {
+    // MigrateRegistry and RegisterCommitment both go through this check
+    let owner = test_owner(1);
+    assert!(check_owner(Some(owner), &owner).is_ok());
+    assert!(check_owner(Some(owner), &test_owner(2)).is_err());
+    assert!(check_owner(None, &owner).is_err());
+}
+
+#[test]
+fn test_registry_migration_log() {
+    let now = Timestamp::from(1_000);
+    // Migrating needs a registry to migrate from, and a different one to go to
+    assert!(RegistryMigration::new(None, test_chain(1), String::new(), now).is_err());
+    assert!(RegistryMigration::new(Some(test_chain(1)), test_chain(1), String::new(), now).is_err());
+
+    // Each entry leaves from where the previous one arrived
+    let mut current = test_chain(1);
+    let mut log = Vec::new();
+    for byte in [2u8, 3] {
+        let migration = RegistryMigration::new(Some(current), test_chain(byte), format!("to {}", byte), now).unwrap();
+        current = migration.to_chain_id;
+        log.push(migration);
+    }
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].from_chain_id, test_chain(1));
+    assert_eq!(log[1].from_chain_id, test_chain(2));
+    assert_eq!(current, test_chain(3));
+    assert_eq!(log[1].reason, "to 3");
+}
@@ -0,0 +1,466 @@
+//! KYA Package Hash - Canonical code_hash computation for agent packages
+//!
+//! This crate defines how the `code_hash` registered in the KYA Registry is computed
+//! from an agent package, so that owners and independent verifiers agree on it:
+//! - Directories and `.tar` / `.tar.gz` / `.tgz` archives hash identically
+//! - Paths are sorted and use `/` separators
+//! - Permissions are normalized to 0o644, or 0o755 for executables
+//! - Ownership, timestamps and empty directories are ignored
+//! - Hard links in archives hash as copies of their target; device and FIFO entries are rejected
+//! - Configurable glob patterns exclude files from the hash
+
+#[cfg(test)]
+mod tests;
+
+pub mod registry;
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path};
+
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use sha2::{Digest, Sha256};
+
+/// Domain separator prefixed to every tree hash
+const TREE_HASH_DOMAIN: &[u8] = b"kya-package-v1\0";
+
+/// Leading bytes of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Patterns excluded from the hash unless explicitly disabled
+pub const DEFAULT_IGNORE_PATTERNS: &[&str] = &[".git/**", "**/.DS_Store"];
+
+/// Name of the optional ignore file read from the package root
+pub const IGNORE_FILE_NAME: &str = ".kyaignore";
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// Errors that can occur while hashing a package
+#[derive(Debug, thiserror::Error)]
+pub enum HashError {
+    #[error("I/O error on {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("invalid ignore pattern {pattern:?}: {source}")]
+    InvalidPattern {
+        pattern: String,
+        source: globset::Error,
+    },
+    #[error("unsupported package format: {0}")]
+    UnsupportedFormat(String),
+    #[error("invalid entry path in archive: {0}")]
+    InvalidPath(String),
+    #[error("hard link {path} points to {target}, which is not an earlier file in the archive")]
+    UnresolvedHardLink { path: String, target: String },
+    #[error("unsupported entry type {kind} in archive: {path}")]
+    UnsupportedEntry { path: String, kind: String },
+    #[error("invalid code hash {0:?}: expected 64 hex characters")]
+    InvalidCodeHash(String),
+}
+
+impl HashError {
+    fn io(path: impl AsRef<Path>, source: io::Error) -> Self {
+        HashError::Io {
+            path: path.as_ref().display().to_string(),
+            source,
+        }
+    }
+}
+
+// ============================================================================
+// Data Types
+// ============================================================================
+
+/// Options controlling which entries take part in the hash
+#[derive(Debug, Clone)]
+pub struct HashOptions {
+    /// Glob patterns (matched against `/`-separated relative paths) to exclude
+    pub ignore_patterns: Vec<String>,
+    /// Whether to also read patterns from a `.kyaignore` file in the package root
+    pub use_ignore_file: bool,
+    /// Number of leading path components to strip from archive entries (like `tar`)
+    pub strip_components: usize,
+}
+
+impl Default for HashOptions {
+    fn default() -> Self {
+        Self {
+            ignore_patterns: DEFAULT_IGNORE_PATTERNS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+            use_ignore_file: true,
+            strip_components: 0,
+        }
+    }
+}
+
+/// Kind of a package entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryKind {
+    File = 0,
+    Symlink = 1,
+}
+
+/// A normalized package entry
+#[derive(Debug, Clone)]
+struct Entry {
+    kind: EntryKind,
+    /// Normalized permissions (0o644 or 0o755)
+    mode: u32,
+    /// SHA-256 of the file contents, or of the link target for symlinks
+    content_hash: [u8; 32],
+}
+
+/// The result of hashing a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageHash {
+    /// The 32-byte `code_hash` to register
+    pub code_hash: [u8; 32],
+    /// Number of entries included in the hash
+    pub entry_count: usize,
+}
+
+impl PackageHash {
+    /// Get the code hash as hex string
+    pub fn code_hash_hex(&self) -> String {
+        hex::encode(self.code_hash)
+    }
+}
+
+// ============================================================================
+// Hashing
+// ============================================================================
+
+/// Hash a package directory or archive, picking the format from the path
+pub fn hash_path(path: &Path, options: &HashOptions) -> Result<PackageHash, HashError> {
+    let metadata = fs::metadata(path).map_err(|e| HashError::io(path, e))?;
+    if metadata.is_dir() {
+        return hash_directory(path, options);
+    }
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let file = File::open(path).map_err(|e| HashError::io(path, e))?;
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        hash_archive(flate2::read::GzDecoder::new(file), options)
+    } else if name.ends_with(".tar") {
+        hash_archive(file, options)
+    } else {
+        Err(HashError::UnsupportedFormat(path.display().to_string()))
+    }
+}
+
+/// Hash a package directory
+pub fn hash_directory(root: &Path, options: &HashOptions) -> Result<PackageHash, HashError> {
+    let mut patterns = options.ignore_patterns.clone();
+    if options.use_ignore_file {
+        let ignore_file = root.join(IGNORE_FILE_NAME);
+        if ignore_file.is_file() {
+            let contents =
+                fs::read_to_string(&ignore_file).map_err(|e| HashError::io(&ignore_file, e))?;
+            patterns.extend(parse_ignore_file(&contents));
+        }
+    }
+    let ignore = build_ignore_set(&patterns)?;
+
+    let mut entries = BTreeMap::new();
+    collect_directory(root, root, &ignore, &mut entries)?;
+    Ok(tree_hash(&entries))
+}
+
+/// Hash a tar stream (already decompressed if needed)
+pub fn hash_archive<R: Read>(reader: R, options: &HashOptions) -> Result<PackageHash, HashError> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = BTreeMap::new();
+    let mut ignore_file = None;
+
+    let archive_entries = archive
+        .entries()
+        .map_err(|e| HashError::io("<archive>", e))?;
+    for entry in archive_entries {
+        let mut entry = entry.map_err(|e| HashError::io("<archive>", e))?;
+        let raw_path = entry
+            .path()
+            .map_err(|e| HashError::io("<archive>", e))?
+            .into_owned();
+        let Some(path) = normalize_archive_path(&raw_path, options.strip_components)? else {
+            continue;
+        };
+
+        let header = entry.header();
+        let mode = normalize_mode(header.mode().unwrap_or(0o644));
+        let entry_type = header.entry_type();
+
+        if entry_type.is_symlink() {
+            let target = entry
+                .link_name()
+                .map_err(|e| HashError::io(&path, e))?
+                .map(|target| target.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            entries.insert(
+                path,
+                Entry {
+                    kind: EntryKind::Symlink,
+                    mode: 0o777,
+                    content_hash: Sha256::digest(target.as_bytes()).into(),
+                },
+            );
+        } else if entry_type.is_hard_link() {
+            // Unpacked, a hard link is just another name for its target's contents
+            let target = entry
+                .link_name()
+                .map_err(|e| HashError::io(&path, e))?
+                .map(|target| target.into_owned())
+                .unwrap_or_default();
+            let resolved = normalize_archive_path(&target, options.strip_components)?
+                .and_then(|target| entries.get(&target).cloned())
+                .ok_or_else(|| HashError::UnresolvedHardLink {
+                    path: path.clone(),
+                    target: target.display().to_string(),
+                })?;
+            entries.insert(path, resolved);
+        } else if entry_type.is_file() {
+            let mut contents = Vec::new();
+            entry
+                .read_to_end(&mut contents)
+                .map_err(|e| HashError::io(&path, e))?;
+            if options.use_ignore_file && path == IGNORE_FILE_NAME {
+                ignore_file = Some(String::from_utf8_lossy(&contents).into_owned());
+            }
+            entries.insert(
+                path,
+                Entry {
+                    kind: EntryKind::File,
+                    mode,
+                    content_hash: Sha256::digest(&contents).into(),
+                },
+            );
+        } else if !(entry_type.is_dir()
+            || entry_type.is_pax_global_extensions()
+            || entry_type.is_pax_local_extensions()
+            || entry_type.is_gnu_longname()
+            || entry_type.is_gnu_longlink())
+        {
+            // Devices and FIFOs have no content to hash and cannot be part of a package
+            return Err(HashError::UnsupportedEntry {
+                path,
+                kind: format!("{entry_type:?}"),
+            });
+        }
+        // Directories and metadata headers do not contribute to the hash
+    }
+
+    // Ignore patterns are applied after reading, since `.kyaignore` may come last
+    let mut patterns = options.ignore_patterns.clone();
+    if let Some(contents) = ignore_file {
+        patterns.extend(parse_ignore_file(&contents));
+    }
+    let ignore = build_ignore_set(&patterns)?;
+    entries.retain(|path, _| !is_ignored(&ignore, path));
+
+    Ok(tree_hash(&entries))
+}
+
+/// Hash an in-memory `.tar` or `.tar.gz` archive, detecting compression from its magic bytes
+pub fn hash_archive_bytes(bytes: &[u8], options: &HashOptions) -> Result<PackageHash, HashError> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        hash_archive(flate2::read::GzDecoder::new(bytes), options)
+    } else {
+        hash_archive(bytes, options)
+    }
+}
+
+/// Parse a hex-encoded code hash (with or without a "0x" prefix)
+pub fn parse_code_hash(value: &str) -> Result<[u8; 32], HashError> {
+    let bytes = hex::decode(value.trim().trim_start_matches("0x"))
+        .map_err(|_| HashError::InvalidCodeHash(value.to_string()))?;
+    bytes
+        .try_into()
+        .map_err(|_| HashError::InvalidCodeHash(value.to_string()))
+}
+
+/// Combine sorted entries into the canonical tree hash
+fn tree_hash(entries: &BTreeMap<String, Entry>) -> PackageHash {
+    let mut hasher = Sha256::new();
+    hasher.update(TREE_HASH_DOMAIN);
+    for (path, entry) in entries {
+        hasher.update([entry.kind as u8]);
+        hasher.update(entry.mode.to_be_bytes());
+        hasher.update((path.len() as u64).to_be_bytes());
+        hasher.update(path.as_bytes());
+        hasher.update(entry.content_hash);
+    }
+    PackageHash {
+        code_hash: hasher.finalize().into(),
+        entry_count: entries.len(),
+    }
+}
+
+/// Recursively collect the entries of a directory
+fn collect_directory(
+    root: &Path,
+    dir: &Path,
+    ignore: &GlobSet,
+    entries: &mut BTreeMap<String, Entry>,
+) -> Result<(), HashError> {
+    for child in fs::read_dir(dir).map_err(|e| HashError::io(dir, e))? {
+        let child = child.map_err(|e| HashError::io(dir, e))?;
+        let path = child.path();
+        let relative = relative_path(root, &path)?;
+        if is_ignored(ignore, &relative) {
+            continue;
+        }
+
+        let metadata = fs::symlink_metadata(&path).map_err(|e| HashError::io(&path, e))?;
+        let file_type = metadata.file_type();
+        if file_type.is_dir() {
+            collect_directory(root, &path, ignore, entries)?;
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(&path).map_err(|e| HashError::io(&path, e))?;
+            let target = target.to_string_lossy().replace('\\', "/");
+            entries.insert(
+                relative,
+                Entry {
+                    kind: EntryKind::Symlink,
+                    mode: 0o777,
+                    content_hash: Sha256::digest(target.as_bytes()).into(),
+                },
+            );
+        } else if file_type.is_file() {
+            let mut hasher = Sha256::new();
+            let mut file = File::open(&path).map_err(|e| HashError::io(&path, e))?;
+            io::copy(&mut file, &mut hasher).map_err(|e| HashError::io(&path, e))?;
+            entries.insert(
+                relative,
+                Entry {
+                    kind: EntryKind::File,
+                    mode: normalize_mode(file_mode(&metadata)),
+                    content_hash: hasher.finalize().into(),
+                },
+            );
+        } else {
+            // FIFOs, sockets and device nodes have no portable content to hash
+            return Err(HashError::UnsupportedEntry {
+                path: relative,
+                kind: special_file_kind(&file_type).to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Build the `/`-separated path of `path` relative to `root`
+fn relative_path(root: &Path, path: &Path) -> Result<String, HashError> {
+    let relative = path
+        .strip_prefix(root)
+        .map_err(|_| HashError::InvalidPath(path.display().to_string()))?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    Ok(parts.join("/"))
+}
+
+/// Normalize an archive entry path, returning `None` if nothing is left after stripping
+fn normalize_archive_path(path: &Path, strip_components: usize) -> Result<Option<String>, HashError> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            _ => return Err(HashError::InvalidPath(path.display().to_string())),
+        }
+    }
+    if parts.len() <= strip_components {
+        return Ok(None);
+    }
+    Ok(Some(parts[strip_components..].join("/")))
+}
+
+/// Reduce permissions to 0o755 for executables and 0o644 for everything else
+fn normalize_mode(mode: u32) -> u32 {
+    if mode & 0o111 != 0 {
+        0o755
+    } else {
+        0o644
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> u32 {
+    0o644
+}
+
+#[cfg(unix)]
+fn special_file_kind(file_type: &fs::FileType) -> &'static str {
+    use std::os::unix::fs::FileTypeExt;
+    if file_type.is_fifo() {
+        "fifo"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_block_device() || file_type.is_char_device() {
+        "device"
+    } else {
+        "unknown"
+    }
+}
+
+#[cfg(not(unix))]
+fn special_file_kind(_file_type: &fs::FileType) -> &'static str {
+    "unknown"
+}
+
+/// Parse `.kyaignore` contents: one pattern per line, `#` starts a comment
+fn parse_ignore_file(contents: &str) -> impl Iterator<Item = String> + '_ {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+}
+
+/// Compile ignore patterns. Patterns without a `/` match a file name at any depth,
+/// and a pattern matching a directory also excludes everything below it.
+fn build_ignore_set(patterns: &[String]) -> Result<GlobSet, HashError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let trimmed = pattern.trim_end_matches('/');
+        let anchored = if trimmed.contains('/') {
+            trimmed.trim_start_matches('/').to_string()
+        } else {
+            format!("**/{trimmed}")
+        };
+        for candidate in [anchored.clone(), format!("{anchored}/**")] {
+            builder.add(compile_glob(&candidate, pattern)?);
+        }
+    }
+    builder.build().map_err(|source| HashError::InvalidPattern {
+        pattern: patterns.join(", "),
+        source,
+    })
+}
+
+fn compile_glob(candidate: &str, pattern: &str) -> Result<Glob, HashError> {
+    GlobBuilder::new(candidate)
+        .literal_separator(true)
+        .build()
+        .map_err(|source| HashError::InvalidPattern {
+            pattern: pattern.to_string(),
+            source,
+        })
+}
+
+fn is_ignored(ignore: &GlobSet, path: &str) -> bool {
+    ignore.is_match(path)
+}
@@ -0,0 +1,134 @@
+//! KYA Package Hash CLI - Compute and verify agent package code hashes
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand};
+use kya_package_hash::{
+    hash_path, parse_code_hash, registry::fetch_registered_package, HashOptions,
+    DEFAULT_IGNORE_PATTERNS,
+};
+
+#[derive(Parser)]
+#[command(name = "kya-package-hash", version, about = "Compute canonical KYA code hashes")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the code_hash of a package directory or archive
+    Hash {
+        /// Package directory, .tar, .tar.gz or .tgz archive
+        path: PathBuf,
+        #[command(flatten)]
+        options: OptionArgs,
+    },
+    /// Check a local package against an expected or registered code_hash
+    Verify {
+        /// Package directory, .tar, .tar.gz or .tgz archive
+        path: PathBuf,
+        /// Expected code hash (hex)
+        #[arg(long, conflicts_with_all = ["registry_url", "agent_id"])]
+        expected: Option<String>,
+        /// Registry service endpoint, e.g. http://localhost:8080/chains/<chain>/applications/<app>
+        #[arg(long, requires = "agent_id")]
+        registry_url: Option<String>,
+        /// Agent ID (64 hex characters) whose registered badge to verify against
+        #[arg(long, requires = "registry_url")]
+        agent_id: Option<String>,
+        #[command(flatten)]
+        options: OptionArgs,
+    },
+}
+
+#[derive(Args)]
+struct OptionArgs {
+    /// Additional glob pattern to exclude (repeatable)
+    #[arg(long = "ignore", value_name = "PATTERN")]
+    ignore: Vec<String>,
+    /// Do not apply the default ignore patterns
+    #[arg(long)]
+    no_default_ignores: bool,
+    /// Do not read patterns from the package's .kyaignore file
+    #[arg(long)]
+    no_ignore_file: bool,
+    /// Strip this many leading path components from archive entries
+    #[arg(long, default_value_t = 0)]
+    strip_components: usize,
+}
+
+impl OptionArgs {
+    fn into_options(self) -> HashOptions {
+        let mut ignore_patterns: Vec<String> = if self.no_default_ignores {
+            Vec::new()
+        } else {
+            DEFAULT_IGNORE_PATTERNS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect()
+        };
+        ignore_patterns.extend(self.ignore);
+        HashOptions {
+            ignore_patterns,
+            use_ignore_file: !self.no_ignore_file,
+            strip_components: self.strip_components,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode, String> {
+    match cli.command {
+        Command::Hash { path, options } => {
+            let hash = hash_path(&path, &options.into_options()).map_err(|e| e.to_string())?;
+            println!("{}", hash.code_hash_hex());
+            Ok(ExitCode::SUCCESS)
+        }
+
+        Command::Verify {
+            path,
+            expected,
+            registry_url,
+            agent_id,
+            options,
+        } => {
+            let expected = match (expected, registry_url, agent_id) {
+                (Some(expected), _, _) => parse_code_hash(&expected).map_err(|e| e.to_string())?,
+                (None, Some(url), Some(agent_id)) => {
+                    let package =
+                        fetch_registered_package(&url, &agent_id).map_err(|e| e.to_string())?;
+                    eprintln!(
+                        "registered package: {} {}",
+                        package.storage_provider, package.storage_cid
+                    );
+                    package.code_hash
+                }
+                _ => return Err("pass --expected or --registry-url with --agent-id".to_string()),
+            };
+
+            let hash = hash_path(&path, &options.into_options()).map_err(|e| e.to_string())?;
+            if hash.code_hash == expected {
+                println!("OK {}", hash.code_hash_hex());
+                Ok(ExitCode::SUCCESS)
+            } else {
+                println!(
+                    "MISMATCH computed {} expected {}",
+                    hash.code_hash_hex(),
+                    hex::encode(expected)
+                );
+                Ok(ExitCode::FAILURE)
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Lookup of registered code hashes through the KYA Registry GraphQL service
+
+use serde_json::{json, Value};
+
+use crate::parse_code_hash;
+
+/// Errors that can occur while querying the registry service
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    #[error("request to registry service failed: {0}")]
+    Request(String),
+    #[error("registry service returned errors: {0}")]
+    GraphQl(String),
+    #[error("agent {0} is not registered")]
+    NotFound(String),
+    #[error("unexpected registry response: {0}")]
+    InvalidResponse(String),
+}
+
+/// The code hash and storage location registered for an agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredPackage {
+    /// The registered 32-byte code hash
+    pub code_hash: [u8; 32],
+    /// Storage provider name as reported by the service (e.g. "IPFS")
+    pub storage_provider: String,
+    /// Content ID for off-chain storage
+    pub storage_cid: String,
+}
+
+const BADGE_QUERY: &str = "query Badge($agentId: String!) {
+    getBadge(agentId: $agentId) { codeHashHex storageProvider storageCid }
+}";
+
+/// Fetch the registered package of an agent from a registry service endpoint
+/// (e.g. `http://localhost:8080/chains/<chain>/applications/<app>`)
+pub fn fetch_registered_package(
+    service_url: &str,
+    agent_id: &str,
+) -> Result<RegisteredPackage, RegistryError> {
+    let response: Value = ureq::post(service_url)
+        .send_json(json!({
+            "query": BADGE_QUERY,
+            "variables": { "agentId": agent_id },
+        }))
+        .map_err(|e| RegistryError::Request(e.to_string()))?
+        .into_json()
+        .map_err(|e| RegistryError::Request(e.to_string()))?;
+
+    parse_badge_response(&response, agent_id)
+}
+
+/// Extract the registered package from a `getBadge` GraphQL response
+pub fn parse_badge_response(
+    response: &Value,
+    agent_id: &str,
+) -> Result<RegisteredPackage, RegistryError> {
+    if let Some(errors) = response.get("errors").filter(|errors| !errors.is_null()) {
+        return Err(RegistryError::GraphQl(errors.to_string()));
+    }
+
+    let badge = &response["data"]["getBadge"];
+    if badge.is_null() {
+        return Err(RegistryError::NotFound(agent_id.to_string()));
+    }
+
+    let field = |name: &str| {
+        badge[name]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| RegistryError::InvalidResponse(format!("missing field {name}")))
+    };
+    let code_hash = parse_code_hash(&field("codeHashHex")?)
+        .map_err(|e| RegistryError::InvalidResponse(e.to_string()))?;
+
+    Ok(RegisteredPackage {
+        code_hash,
+        storage_provider: field("storageProvider")?,
+        storage_cid: field("storageCid")?,
+    })
+}
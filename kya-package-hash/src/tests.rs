@@ -0,0 +1,247 @@
+//! Unit tests for canonical package hashing
+
+use super::*;
+use std::path::PathBuf;
+
+fn write_file(root: &Path, relative: &str, contents: &str) {
+    let path = root.join(relative);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+fn sample_package() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_file(dir.path(), "main.py", "print('hello')\n");
+    write_file(dir.path(), "lib/util.py", "X = 1\n");
+    write_file(dir.path(), "README.md", "# Agent\n");
+    dir
+}
+
+fn build_tarball(root: &Path, prefix: &str, gzip: bool) -> (tempfile::TempDir, PathBuf) {
+    let out = tempfile::tempdir().unwrap();
+    let name = if gzip { "package.tar.gz" } else { "package.tar" };
+    let path = out.path().join(name);
+    let file = File::create(&path).unwrap();
+    if gzip {
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        builder.append_dir_all(prefix, root).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+    } else {
+        let mut builder = tar::Builder::new(file);
+        builder.append_dir_all(prefix, root).unwrap();
+        builder.finish().unwrap();
+    }
+    (out, path)
+}
+
+#[test]
+fn test_hash_is_deterministic() {
+    let package = sample_package();
+    let options = HashOptions::default();
+
+    let first = hash_path(package.path(), &options).unwrap();
+    let second = hash_path(package.path(), &options).unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(first.entry_count, 3);
+    assert_eq!(first.code_hash_hex().len(), 64);
+}
+
+#[test]
+fn test_content_change_changes_hash() {
+    let package = sample_package();
+    let options = HashOptions::default();
+    let before = hash_path(package.path(), &options).unwrap();
+
+    write_file(package.path(), "lib/util.py", "X = 2\n");
+
+    assert_ne!(before, hash_path(package.path(), &options).unwrap());
+}
+
+#[test]
+fn test_default_and_configured_ignores() {
+    let package = sample_package();
+    let options = HashOptions::default();
+    let baseline = hash_path(package.path(), &options).unwrap();
+
+    write_file(package.path(), ".git/HEAD", "ref: refs/heads/main\n");
+    write_file(package.path(), "lib/.DS_Store", "junk");
+    assert_eq!(baseline, hash_path(package.path(), &options).unwrap());
+
+    write_file(package.path(), "__pycache__/main.pyc", "bytecode");
+    assert_ne!(baseline, hash_path(package.path(), &options).unwrap());
+
+    let mut ignoring = HashOptions::default();
+    ignoring.ignore_patterns.push("__pycache__".to_string());
+    assert_eq!(baseline, hash_path(package.path(), &ignoring).unwrap());
+}
+
+#[test]
+fn test_ignore_file_is_applied() {
+    let package = sample_package();
+    write_file(package.path(), ".kyaignore", "# build output\n*.log\n");
+    let options = HashOptions::default();
+    let baseline = hash_path(package.path(), &options).unwrap();
+
+    write_file(package.path(), "logs/run.log", "noise");
+
+    assert_eq!(baseline, hash_path(package.path(), &options).unwrap());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_permissions_are_normalized() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let package = sample_package();
+    let options = HashOptions::default();
+    let main = package.path().join("main.py");
+    let baseline = hash_path(package.path(), &options).unwrap();
+
+    // Group/other bits do not matter
+    fs::set_permissions(&main, fs::Permissions::from_mode(0o600)).unwrap();
+    assert_eq!(baseline, hash_path(package.path(), &options).unwrap());
+
+    // The executable bit does
+    fs::set_permissions(&main, fs::Permissions::from_mode(0o700)).unwrap();
+    assert_ne!(baseline, hash_path(package.path(), &options).unwrap());
+}
+
+#[test]
+fn test_archives_match_directory() {
+    let package = sample_package();
+    let expected = hash_path(package.path(), &HashOptions::default()).unwrap();
+
+    let (_out, tar_path) = build_tarball(package.path(), ".", false);
+    assert_eq!(expected, hash_path(&tar_path, &HashOptions::default()).unwrap());
+
+    let (_out, tgz_path) = build_tarball(package.path(), "agent-1.0.0", true);
+    let options = HashOptions {
+        strip_components: 1,
+        ..HashOptions::default()
+    };
+    assert_eq!(expected, hash_path(&tgz_path, &options).unwrap());
+}
+
+#[test]
+fn test_archive_bytes_detect_compression() {
+    let package = sample_package();
+    let expected = hash_path(package.path(), &HashOptions::default()).unwrap();
+
+    let (_out, tar_path) = build_tarball(package.path(), ".", false);
+    let (_out_gz, tgz_path) = build_tarball(package.path(), ".", true);
+    for path in [tar_path, tgz_path] {
+        let bytes = fs::read(path).unwrap();
+        assert_eq!(expected, hash_archive_bytes(&bytes, &HashOptions::default()).unwrap());
+    }
+}
+
+fn append_file(builder: &mut tar::Builder<Vec<u8>>, path: &str, contents: &str) {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, path, contents.as_bytes()).unwrap();
+}
+
+fn append_special(builder: &mut tar::Builder<Vec<u8>>, path: &str, entry_type: tar::EntryType, target: &str) {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(entry_type);
+    header.set_size(0);
+    header.set_mode(0o644);
+    if !target.is_empty() {
+        header.set_link_name(target).unwrap();
+    }
+    header.set_cksum();
+    builder.append_data(&mut header, path, io::empty()).unwrap();
+}
+
+#[test]
+fn test_hard_links_hash_as_copies() {
+    let package = sample_package();
+    write_file(package.path(), "bin/main.py", "print('hello')\n");
+    let expected = hash_path(package.path(), &HashOptions::default()).unwrap();
+
+    let mut builder = tar::Builder::new(Vec::new());
+    append_file(&mut builder, "main.py", "print('hello')\n");
+    append_file(&mut builder, "lib/util.py", "X = 1\n");
+    append_file(&mut builder, "README.md", "# Agent\n");
+    append_special(&mut builder, "bin/main.py", tar::EntryType::Link, "main.py");
+    let bytes = builder.into_inner().unwrap();
+
+    assert_eq!(expected, hash_archive_bytes(&bytes, &HashOptions::default()).unwrap());
+}
+
+#[test]
+fn test_unresolvable_and_special_entries_are_rejected() {
+    let mut dangling = tar::Builder::new(Vec::new());
+    append_special(&mut dangling, "bin/main.py", tar::EntryType::Link, "missing.py");
+    let bytes = dangling.into_inner().unwrap();
+    assert!(matches!(
+        hash_archive_bytes(&bytes, &HashOptions::default()),
+        Err(HashError::UnresolvedHardLink { .. })
+    ));
+
+    let mut fifo = tar::Builder::new(Vec::new());
+    append_file(&mut fifo, "main.py", "print('hello')\n");
+    append_special(&mut fifo, "pipe", tar::EntryType::Fifo, "");
+    let bytes = fifo.into_inner().unwrap();
+    assert!(matches!(
+        hash_archive_bytes(&bytes, &HashOptions::default()),
+        Err(HashError::UnsupportedEntry { .. })
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_directory_rejects_fifo() {
+    let package = sample_package();
+    let status = std::process::Command::new("mkfifo")
+        .arg(package.path().join("pipe"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(matches!(
+        hash_directory(package.path(), &HashOptions::default()),
+        Err(HashError::UnsupportedEntry { ref kind, .. }) if kind == "fifo"
+    ));
+}
+
+#[test]
+fn test_unsupported_format() {
+    let package = sample_package();
+    let result = hash_path(&package.path().join("main.py"), &HashOptions::default());
+    assert!(matches!(result, Err(HashError::UnsupportedFormat(_))));
+}
+
+#[test]
+fn test_parse_code_hash() {
+    let hex_hash = "ab".repeat(32);
+    assert_eq!(parse_code_hash(&hex_hash).unwrap(), [0xab; 32]);
+    assert_eq!(parse_code_hash(&format!("0x{hex_hash}")).unwrap(), [0xab; 32]);
+    assert!(parse_code_hash("abcd").is_err());
+    assert!(parse_code_hash("not hex").is_err());
+}
+
+#[test]
+fn test_parse_badge_response() {
+    let response = serde_json::json!({
+        "data": {
+            "getBadge": {
+                "codeHashHex": "11".repeat(32),
+                "storageProvider": "IPFS",
+                "storageCid": "bafy-test",
+            }
+        }
+    });
+    let package = registry::parse_badge_response(&response, "0x01").unwrap();
+    assert_eq!(package.code_hash, [0x11; 32]);
+    assert_eq!(package.storage_cid, "bafy-test");
+
+    let missing = serde_json::json!({ "data": { "getBadge": null } });
+    assert!(matches!(
+        registry::parse_badge_response(&missing, "0x01"),
+        Err(registry::RegistryError::NotFound(_))
+    ));
+}
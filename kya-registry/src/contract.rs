@@ -3,15 +3,19 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 #![allow(unexpected_cfgs)]
 
-use kya_registry::{AgentBadge, KyaRegistryAbi, Message, Operation, Response};
+use std::collections::BTreeMap;
+
+use kya_registry::{
+    AgentBadge, AgentId, AnchorOutcome, AgentNotificationKind, AgentUpdate, AgentStatus, BadgeSnapshot, CodeUpdateNotice, Delegation, DelegationScope, CodeUpdateRecord, KyaRegistryAbi, Message, Operation, RegistryParameters,
+    Rating, RatingSource, Response, TaskReport, VerificationRecord,
+};
 use linera_sdk::{
-    linera_base_types::{AccountOwner, WithContractAbi},
+    linera_base_types::{AccountOwner, ChainId, WithContractAbi},
     views::{RootView, View},
     Contract, ContractRuntime,
 };
 
-mod state;
-use state::KyaRegistryState;
+use kya_registry::state::KyaRegistryState;
 
 linera_sdk::contract!(KyaRegistryContract);
 
@@ -23,7 +27,7 @@ pub struct KyaRegistryContract {
 
 impl Contract for KyaRegistryContract {
     type Message = Message;
-    type Parameters = ();
+    type Parameters = RegistryParameters;
     type InstantiationArgument = ();
     type EventValue = ();
 
@@ -35,8 +39,12 @@ impl Contract for KyaRegistryContract {
     }
 
     async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {
-        // Initialize with default state - nothing special needed
-        // The state views initialize with their default values
+        // The state views initialize with their default values; only the
+        // parameters need checking
+        self.runtime
+            .application_parameters()
+            .validate()
+            .expect("Invalid registry parameters");
     }
 
     async fn execute_operation(&mut self, operation: Operation) -> Response {
@@ -52,9 +60,15 @@ impl Contract for KyaRegistryContract {
                     .authenticated_signer()
                     .expect("Operation must be authenticated");
 
+                let agent_id = match self.state.next_agent_id(&owner).await {
+                    Ok(agent_id) => agent_id,
+                    Err(e) => return Response::Error(e),
+                };
+
                 let timestamp = self.runtime.system_time();
-                let badge = AgentBadge::new(
-                    owner.into(),
+                let mut badge = AgentBadge::new(
+                    agent_id,
+                    owner,
                     code_hash,
                     storage_provider,
                     storage_cid.clone(),
@@ -62,45 +76,75 @@ impl Contract for KyaRegistryContract {
                     timestamp,
                 );
 
-                match self.state.register_agent(badge).await {
+                // Owner -> Contract transfer of the fee and deposit (simulated, as for Stake)
+                // self.runtime.transfer(Some(owner), destination, fee + deposit);
+                let fee_paid = *self.state.registration_fee.get();
+                let deposit = *self.state.registration_deposit.get();
+                badge.registration_fee_paid = fee_paid;
+                badge.deposit = deposit;
+
+                let params = self.runtime.application_parameters();
+                match self.state.register_agent(badge, &params).await {
                     Ok(cid) => Response::AgentRegistered {
-                        agent_id: owner.into(),
+                        agent_id,
                         storage_cid: cid,
+                        fee_paid,
+                        deposit,
                     },
                     Err(e) => Response::Error(e),
                 }
             }
 
             Operation::UpdateAgentCode {
+                agent_id,
                 code_hash,
                 storage_provider,
                 storage_cid,
                 manifest,
+                notify_chains,
             } => {
-                let owner = self
-                    .runtime
-                    .authenticated_signer()
-                    .expect("Operation must be authenticated");
+                if let Err(e) = self.authorize(&agent_id, DelegationScope::UpdateCode).await {
+                    return Response::Error(e);
+                }
 
                 let timestamp = self.runtime.system_time();
+                let params = self.runtime.application_parameters();
+                let before = self.badge_snapshot(&agent_id).await;
+                // Look up who to notify before changing anything, so a failed lookup
+                // cannot leave the update applied but unannounced
+                let recipients = match self
+                    .state
+                    .code_update_recipients(&agent_id, code_hash, timestamp)
+                    .await
+                {
+                    Ok(recipients) => recipients,
+                    Err(e) => return Response::Error(e),
+                };
 
                 match self
                     .state
                     .update_agent_code(
-                        &owner.into(),
+                        &agent_id,
                         code_hash,
                         storage_provider,
                         storage_cid,
                         manifest,
                         timestamp,
+                        &params,
                     )
                     .await
                 {
-                    Ok((version, update_count)) => Response::AgentUpdated {
-                        agent_id: owner.into(),
-                        version,
-                        update_count,
-                    },
+                    Ok((record, pending_reaudit)) => {
+                        self.notify_code_update(agent_id, &record, recipients, notify_chains);
+                        self.notify_agent(agent_id, before, None).await;
+                        Response::AgentUpdated {
+                            agent_id,
+                            version: record.new_version,
+                            update_count: record.update_number,
+                            severity: record.diff.severity,
+                            pending_reaudit,
+                        }
+                    }
                     Err(e) => Response::Error(e),
                 }
             }
@@ -110,9 +154,17 @@ impl Contract for KyaRegistryContract {
                 delta,
                 reason: _,
             } => {
-                // TODO: Add admin/DAO permission check
-                match self.state.update_score(&agent_id, delta).await {
-                    Ok((new_score, new_tier)) => Response::ScoreAdjusted { new_score, new_tier },
+                if let Err(e) = self.require_governance() {
+                    return Response::Error(e);
+                }
+                let params = self.runtime.application_parameters();
+                let now = self.runtime.system_time();
+                let before = self.badge_snapshot(&agent_id).await;
+                match self.state.update_score(&agent_id, delta, &params, now).await {
+                    Ok((new_score, new_tier)) => {
+                        self.notify_agent(agent_id, before, None).await;
+                        Response::ScoreAdjusted { new_score, new_tier }
+                    }
                     Err(e) => Response::Error(e),
                 }
             }
@@ -120,41 +172,154 @@ impl Contract for KyaRegistryContract {
             Operation::FlagSpam {
                 agent_id,
                 evidence: _,
-            } => match self.state.flag_spam(&agent_id).await {
-                Ok(total_flags) => Response::SpamFlagged { total_flags },
-                Err(e) => Response::Error(e),
-            },
+            } => {
+                let params = self.runtime.application_parameters();
+                let now = self.runtime.system_time();
+                let before = self.badge_snapshot(&agent_id).await;
+                match self.state.flag_spam(&agent_id, &params, now).await {
+                    Ok(total_flags) => {
+                        let event = (AgentNotificationKind::SpamFlagged, format!("Spam flag {total_flags} recorded"));
+                        self.notify_agent(agent_id, before, Some(event)).await;
+                        Response::SpamFlagged { total_flags }
+                    }
+                    Err(e) => Response::Error(e),
+                }
+            }
 
             Operation::SubmitAudit {
                 agent_id,
                 passed,
                 auditor_notes: _,
             } => {
+                if let Err(e) = self.require_auditor() {
+                    return Response::Error(e);
+                }
                 // Update score based on audit result
-                let delta = if passed { 100 } else { -50 };
-                match self.state.update_score(&agent_id, delta).await {
-                    Ok(_) => Response::AuditSubmitted { passed },
+                let timestamp = self.runtime.system_time();
+                let params = self.runtime.application_parameters();
+                let before = self.badge_snapshot(&agent_id).await;
+                match self.state.apply_audit(&agent_id, passed, timestamp, &params).await {
+                    Ok(_) => {
+                        self.notify_agent(agent_id, before, Some(audit_event(passed))).await;
+                        Response::AuditSubmitted { passed }
+                    }
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::AttestTask { agent_id, task_hash, success } => {
+                let counterparty = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Attestation must be signed".to_string()),
+                };
+                let params = self.runtime.application_parameters();
+                let now = self.runtime.system_time();
+                let before = self.badge_snapshot(&agent_id).await;
+
+                match self
+                    .state
+                    .attest_task(&agent_id, task_hash, counterparty, success, &params, now)
+                    .await
+                {
+                    Ok(new_score) => {
+                        self.notify_agent(agent_id, before, None).await;
+                        Response::TaskAttested { agent_id, success, new_score }
+                    }
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::RateAgent { agent_id, stars, review_cid, task_hash } => {
+                let rater = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Rating must be signed".to_string()),
+                };
+                let source = if task_hash.is_some() {
+                    RatingSource::Task
+                } else {
+                    RatingSource::Subscription
+                };
+                let rating = Rating {
+                    rater,
+                    stars,
+                    review_cid,
+                    source,
+                    task_hash,
+                    timestamp: self.runtime.system_time(),
+                };
+                let params = self.runtime.application_parameters();
+                let before = self.badge_snapshot(&agent_id).await;
+
+                match self.state.rate_agent(&agent_id, rating, &params).await {
+                    Ok((customer_rating, new_score)) => {
+                        self.notify_agent(agent_id, before, None).await;
+                        Response::AgentRated {
+                            agent_id,
+                            customer_rating,
+                            new_score,
+                        }
+                    }
                     Err(e) => Response::Error(e),
                 }
             }
 
             Operation::VerifyCodeHash {
                 agent_id,
-                expected_hash,
-            } => match self.state.verify_code_hash(&agent_id, expected_hash).await {
-                Ok(matches) => Response::HashVerified { matches },
-                Err(e) => Response::Error(e),
-            },
+                storage_cid,
+                computed_hash,
+            } => {
+                let verifier = match self.runtime.authenticated_signer() {
+                    Some(signer) => signer,
+                    None => return Response::Error("Verification must be signed".to_string()),
+                };
+
+                let badge = match self.state.get_badge(&agent_id).await {
+                    Ok(Some(badge)) => badge,
+                    Ok(None) => return Response::Error("Agent not found".to_string()),
+                    Err(e) => return Response::Error(e),
+                };
+
+                let matches = badge.code_hash == computed_hash;
+                let record = VerificationRecord {
+                    verifier,
+                    storage_cid,
+                    computed_hash,
+                    matches,
+                    timestamp: self.runtime.system_time(),
+                };
+                let params = self.runtime.application_parameters();
+                let before = Some(BadgeSnapshot::from(&badge));
+
+                match self.state.record_verification(&agent_id, record, &params).await {
+                    Ok((hash_mismatches, penalty)) => {
+                        self.notify_agent(agent_id, before, None).await;
+                        Response::HashVerified {
+                            matches,
+                            hash_mismatches,
+                            penalty,
+                        }
+                    }
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::ReadAgentUpdates { agent_id, offset, limit } => {
+                match self.state.get_agent_updates(&agent_id, offset, limit).await {
+                    Ok((updates, next)) => Response::AgentUpdates { agent_id, updates, next },
+                    Err(e) => Response::Error(e),
+                }
+            }
 
             // === Economic Operations ===
             
-            Operation::Stake { amount } => {
-                let owner = self.runtime.authenticated_signer().expect("Authentication required");
-                let agent_id: AccountOwner = owner.into(); // Assuming owner implies agent_id for self-staking
+            Operation::Stake { agent_id, amount } => {
+                if let Err(e) = self.authorize(&agent_id, DelegationScope::ManageStake).await {
+                    return Response::Error(e);
+                }
 
                 // 1. Transfer tokens from User -> Contract
                 let contract_id = self.runtime.application_id().forget_abi(); 
-                let destination: AccountOwner = contract_id.into();
+                let _destination: AccountOwner = contract_id.into();
                 
                 // Note: In a real Linera app, we might need to handle specific chain/account logic
                 // self.runtime.transfer(Some(owner), destination, amount); 
@@ -167,18 +332,29 @@ impl Contract for KyaRegistryContract {
                 // SIMULATION FOR MVP: We just assume the transfer succeeds if we are logic-bound
                 // In production, we'd verify the incoming funds.
                 
-                match self.state.stake_tokens(&agent_id, amount).await {
-                    Ok(new_balance) => Response::Staked { agent_id, amount, new_balance },
+                let params = self.runtime.application_parameters();
+                let now = self.runtime.system_time();
+                let before = self.badge_snapshot(&agent_id).await;
+                match self.state.stake_tokens(&agent_id, amount, &params, now).await {
+                    Ok(new_balance) => {
+                        self.notify_agent(agent_id, before, None).await;
+                        Response::Staked { agent_id, amount, new_balance }
+                    }
                     Err(e) => Response::Error(e),
                 }
             }
 
-            Operation::Unstake { amount } => {
-                let owner = self.runtime.authenticated_signer().expect("Authentication required");
-                let agent_id: AccountOwner = owner.into();
+            Operation::Unstake { agent_id, amount } => {
+                if let Err(e) = self.authorize(&agent_id, DelegationScope::ManageStake).await {
+                    return Response::Error(e);
+                }
 
-                match self.state.unstake_tokens(&agent_id, amount).await {
+                let params = self.runtime.application_parameters();
+                let now = self.runtime.system_time();
+                let before = self.badge_snapshot(&agent_id).await;
+                match self.state.unstake_tokens(&agent_id, amount, &params, now).await {
                     Ok(remaining_balance) => {
+                        self.notify_agent(agent_id, before, None).await;
                         // 2. Transfer tokens Contract -> User
                         // self.runtime.transfer(None, owner, amount);
                         Response::Unstaked { agent_id, amount, remaining_balance }
@@ -187,20 +363,45 @@ impl Contract for KyaRegistryContract {
                 }
             }
 
-            Operation::Slash { agent_id, amount } => {
-                // TODO: Governance Check
-                // let caller = self.runtime.authenticated_signer();
-                // if caller != Some(all_powerful_admin) { return Error }
+            Operation::Slash { agent_id, amount, reporter, harmed_subscriber } => {
+                if let Err(e) = self.require_governance() {
+                    return Response::Error(e);
+                }
 
-                match self.state.unstake_tokens(&agent_id, amount).await {
-                    Ok(_) => Response::Slashed { agent_id, amount },
+                let params = self.runtime.application_parameters();
+                let timestamp = self.runtime.system_time();
+                let before = self.badge_snapshot(&agent_id).await;
+                match self
+                    .state
+                    .slash_stake(&agent_id, amount, reporter, harmed_subscriber, timestamp, &params)
+                    .await
+                {
+                    Ok(distribution) => {
+                        let event = (AgentNotificationKind::Slashed, format!("{amount} tokens slashed"));
+                        self.notify_agent(agent_id, before, Some(event)).await;
+                        Response::Slashed { agent_id, amount, distribution }
+                    }
                     Err(e) => Response::Error(e),
                 }
             }
 
-            Operation::SetSubscriptionCost { cost } => {
-                let owner = self.runtime.authenticated_signer().expect("Authentication required");
-                let agent_id: AccountOwner = owner.into();
+            Operation::ClaimSlashProceeds => {
+                let claimant = self.runtime.authenticated_signer().expect("Authentication required");
+
+                match self.state.take_claim(&claimant).await {
+                    Ok(amount) => {
+                        // Transfer Contract -> Claimant
+                        // self.runtime.transfer(None, claimant, amount);
+                        Response::SlashProceedsClaimed { claimant, amount }
+                    }
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::SetSubscriptionCost { agent_id, cost } => {
+                if let Err(e) = self.authorize(&agent_id, DelegationScope::ManagePricing).await {
+                    return Response::Error(e);
+                }
 
                 match self.state.set_subscription_cost(&agent_id, cost).await {
                     Ok(_) => Response::CostUpdated { agent_id, new_cost: cost },
@@ -208,48 +409,341 @@ impl Contract for KyaRegistryContract {
                 }
             }
 
-            Operation::Subscribe { agent_id, duration: _ } => {
+            // === Ownership Operations ===
+
+            Operation::ProposeOwnershipTransfer { agent_id, new_owner } => {
+                if let Err(e) = self.authorize_owner(&agent_id).await {
+                    return Response::Error(e);
+                }
+                let timestamp = self.runtime.system_time();
+
+                match self.state.propose_transfer(&agent_id, new_owner, timestamp).await {
+                    Ok(()) => Response::OwnershipTransferProposed { agent_id, new_owner },
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::CancelOwnershipTransfer { agent_id } => {
+                if let Err(e) = self.authorize_owner(&agent_id).await {
+                    return Response::Error(e);
+                }
+                let timestamp = self.runtime.system_time();
+
+                match self.state.cancel_transfer(&agent_id, timestamp).await {
+                    Ok(()) => Response::OwnershipTransferCancelled { agent_id },
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::AcceptOwnership { agent_id } => {
+                let new_owner = self.runtime.authenticated_signer().expect("Authentication required");
+                let timestamp = self.runtime.system_time();
+
+                match self.state.accept_transfer(&agent_id, new_owner, timestamp).await {
+                    Ok(old_owner) => Response::OwnershipTransferred {
+                        agent_id,
+                        old_owner,
+                        new_owner,
+                    },
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::SetRecoveryKey { agent_id, recovery_key } => {
+                if let Err(e) = self.authorize_owner(&agent_id).await {
+                    return Response::Error(e);
+                }
+                let timestamp = self.runtime.system_time();
+
+                match self.state.set_recovery_key(&agent_id, recovery_key, timestamp).await {
+                    Ok(()) => Response::RecoveryKeySet { agent_id },
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::RotateKey { agent_id, new_owner } => {
+                let signer = self.runtime.authenticated_signer().expect("Authentication required");
+                let timestamp = self.runtime.system_time();
+
+                match self.state.rotate_key(&agent_id, new_owner, signer, timestamp).await {
+                    Ok(old_owner) => Response::OwnershipTransferred {
+                        agent_id,
+                        old_owner,
+                        new_owner,
+                    },
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::GrantDelegation {
+                agent_id,
+                operator,
+                scopes,
+                duration,
+            } => {
+                if let Err(e) = self.authorize_owner(&agent_id).await {
+                    return Response::Error(e);
+                }
+                let timestamp = self.runtime.system_time();
+                let delegation = Delegation::new(operator, scopes, timestamp, duration);
+                let expires_at = delegation.expires_at;
+
+                match self.state.grant_delegation(&agent_id, delegation).await {
+                    Ok(()) => Response::DelegationGranted {
+                        agent_id,
+                        operator,
+                        expires_at,
+                    },
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::RevokeDelegation { agent_id, operator } => {
+                if let Err(e) = self.authorize_owner(&agent_id).await {
+                    return Response::Error(e);
+                }
+                let timestamp = self.runtime.system_time();
+
+                match self.state.revoke_delegation(&agent_id, &operator, timestamp).await {
+                    Ok(()) => Response::DelegationRevoked { agent_id, operator },
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::RetireAgent { agent_id } => {
+                if let Err(e) = self.authorize_owner(&agent_id).await {
+                    return Response::Error(e);
+                }
+                let timestamp = self.runtime.system_time();
+
+                let params = self.runtime.application_parameters();
+                let before = self.badge_snapshot(&agent_id).await;
+                match self.state.retire_agent(&agent_id, timestamp, &params).await {
+                    Ok((returned_stake, refunded_deposit)) => {
+                        self.notify_agent(agent_id, before, None).await;
+                        // Stake and deposit -> Owner transfer (simulated, as for Unstake)
+                        // self.runtime.transfer(None, owner, returned_stake + refunded_deposit);
+                        Response::AgentRetired { agent_id, returned_stake, refunded_deposit }
+                    }
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            Operation::Subscribe { agent_id, duration, notify_chain } => {
                 let subscriber = self.runtime.authenticated_signer().expect("Authentication required");
-                
-                match self.state.get_subscription_cost(&agent_id).await {
-                    Ok(cost) => {
+                let timestamp = self.runtime.system_time();
+
+                match self
+                    .state
+                    .subscribe(&agent_id, subscriber, notify_chain, timestamp, duration)
+                    .await
+                {
+                    Ok((cost, expires_at)) => {
                          // 3. User -> Agent Transfer
                          // self.runtime.transfer(None, agent_id, cost);
                          
-                         Response::Subscribed { agent_id, subscriber: subscriber.into(), cost }
+                         Response::Subscribed { agent_id, subscriber, cost, expires_at }
                     },
                     Err(e) => Response::Error(e),
                 }
-    }
             }
+
+            Operation::PinCodeHash { agent_id, code_hash } => {
+                let subscriber = self.runtime.authenticated_signer().expect("Authentication required");
+                let timestamp = self.runtime.system_time();
+
+                match self.state.pin_code_hash(&agent_id, &subscriber, code_hash, timestamp).await {
+                    Ok(matches_current) => Response::CodeHashPinned { agent_id, matches_current },
+                    Err(e) => Response::Error(e),
+                }
+            }
+
+            // === Governance Operations ===
+
+            Operation::SuspendAgent { agent_id, reason } => {
+                self.change_status(agent_id, AgentStatus::Suspended, reason).await
+            }
+
+            Operation::ReinstateAgent { agent_id, reason } => {
+                self.change_status(agent_id, AgentStatus::Active, reason).await
+            }
+
+            Operation::BanAgent { agent_id, reason } => {
+                self.change_status(agent_id, AgentStatus::Banned, reason).await
+            }
+
+            Operation::SetRegistrationFees { fee, deposit } => {
+                if let Err(e) = self.require_governance() {
+                    return Response::Error(e);
+                }
+
+                self.state.registration_fee.set(fee);
+                self.state.registration_deposit.set(deposit);
+                Response::RegistrationFeesSet { fee, deposit }
+            }
+
+            Operation::WithdrawTreasury { amount, recipient } => {
+                if let Err(e) = self.require_governance() {
+                    return Response::Error(e);
+                }
+
+                match self.state.withdraw_treasury(amount) {
+                    Ok(remaining_balance) => {
+                        // Transfer Contract -> Recipient
+                        // self.runtime.transfer(None, recipient, amount);
+                        Response::TreasuryWithdrawn { recipient, amount, remaining_balance }
+                    }
+                    Err(e) => Response::Error(e),
+                }
+            }
+        }
     }
 
     async fn execute_message(&mut self, message: Message) {
         match message {
             Message::ActivityLog {
                 agent_id,
-                task_hash: _,
+                task_hash,
+                capability,
                 success,
-                timestamp: _,
+                counterparty,
+                timestamp,
+                details,
             } => {
+                // Only the owner or a delegated operator may report activity.
+                // Rejected activity panics so the agent chain gets it back as a bounce.
+                if let Err(e) = self.authorize(&agent_id, DelegationScope::LogTasks).await {
+                    panic!("Rejected activity log: {e}");
+                }
                 // Record the task result and update reputation
-                let _ = self.state.record_task(&agent_id, success).await;
+                let params = self.runtime.application_parameters();
+                let now = self.runtime.system_time();
+                let report = TaskReport {
+                    task_hash,
+                    capability,
+                    success,
+                    counterparty,
+                    timestamp,
+                    details,
+                };
+                let before = self.badge_snapshot(&agent_id).await;
+                if let Err(e) = self.state.record_tasks(&agent_id, vec![report], &params, now).await {
+                    panic!("Rejected activity log: {e}");
+                }
+                self.activity_recorded(agent_id, before, None).await;
+            }
+
+            Message::ActivityBatch { agent_id, tasks } => {
+                if let Err(e) = self.authorize(&agent_id, DelegationScope::LogTasks).await {
+                    panic!("Rejected activity batch: {e}");
+                }
+                let params = self.runtime.application_parameters();
+                let now = self.runtime.system_time();
+                let before = self.badge_snapshot(&agent_id).await;
+                if let Err(e) = self.state.record_tasks(&agent_id, tasks, &params, now).await {
+                    panic!("Rejected activity batch: {e}");
+                }
+                self.activity_recorded(agent_id, before, None).await;
+            }
+
+            Message::TaskRoot {
+                agent_id,
+                anchor,
+                attested_tasks,
+            } => {
+                let signer = match self.authorize(&agent_id, DelegationScope::LogTasks).await {
+                    Ok(signer) => signer,
+                    Err(e) => panic!("Rejected task root: {e}"),
+                };
+                let params = self.runtime.application_parameters();
+                let now = self.runtime.system_time();
+                let before = self.badge_snapshot(&agent_id).await;
+                let epoch = anchor.epoch;
+                // Out-of-order roots bounce and are resent; invalid ones use up their
+                // epoch instead, since resending them would fail forever
+                let event = match self
+                    .state
+                    .record_anchor(&agent_id, &signer, anchor, attested_tasks, &params, now)
+                    .await
+                {
+                    Ok(AnchorOutcome::Recorded { .. }) => None,
+                    Ok(AnchorOutcome::Rejected { reason }) => Some((
+                        AgentNotificationKind::TaskRootRejected,
+                        format!("Task root for epoch {} rejected: {}", epoch, reason),
+                    )),
+                    Err(e) => panic!("Rejected task root: {e}"),
+                };
+                self.activity_recorded(agent_id, before, event).await;
+            }
+
+            Message::AuditRequest { agent_id, timestamp, .. } => {
+                // Only the owner may request an audit; rejected requests bounce back
+                let Some(signer) = self.runtime.authenticated_signer() else {
+                    panic!("Rejected audit request: not authenticated");
+                };
+                if let Err(e) = self.state.request_audit(&agent_id, &signer, timestamp).await {
+                    panic!("Rejected audit request: {e}");
+                }
             }
 
             Message::ProofOfAudit {
                 agent_id,
-                auditor: _,
+                auditor,
                 passed,
                 timestamp: _,
             } => {
-                // Apply audit result to reputation
-                let delta = if passed { 100 } else { -50 };
-                let _ = self.state.update_score(&agent_id, delta).await;
+                let params = self.runtime.application_parameters();
+                if self.runtime.authenticated_signer() != Some(auditor) || !params.is_auditor(&auditor) {
+                    panic!("Rejected proof of audit: not signed by a registered auditor");
+                }
+                // Apply audit result to reputation, dated by this chain's clock rather
+                // than the auditor's so an audit cannot be back- or forward-dated
+                let now = self.runtime.system_time();
+                let before = self.badge_snapshot(&agent_id).await;
+                if self.state.apply_audit(&agent_id, passed, now, &params).await.is_ok() {
+                    self.notify_agent(agent_id, before, Some(audit_event(passed))).await;
+                }
             }
 
-            Message::ScoreResponse { .. } | Message::CodeUpdated { .. } => {
-                // These message types are sent OUT, not received
-                // No action needed here
+            Message::CodeUpdated {
+                agent_id,
+                old_code_hash,
+                new_code_hash,
+                new_version,
+                broken_pins,
+                timestamp,
+            } => {
+                // A notification that bounced back to us is not worth keeping
+                if self.runtime.message_is_bouncing() == Some(true) {
+                    return;
+                }
+                let _ = self
+                    .state
+                    .record_code_update_notice(CodeUpdateNotice {
+                        agent_id,
+                        old_code_hash,
+                        new_code_hash,
+                        new_version,
+                        broken_pins,
+                        timestamp,
+                    })
+                    .await;
+            }
+
+            Message::AgentUpdate(update) => {
+                if self.runtime.message_is_bouncing() == Some(true) {
+                    return;
+                }
+                // Kept on the agent's home chain for its client to read; only the
+                // registry's own chain may send them
+                if self.runtime.message_origin_chain_id() != Some(self.runtime.application_creator_chain_id()) {
+                    panic!("Rejected agent update: not sent from the registry chain");
+                }
+                let _ = self.state.record_agent_update(update).await;
+            }
+
+            Message::ScoreResponse { .. } => {
+                // Sent OUT to the oracle bridge, not received
             }
         }
     }
@@ -259,6 +753,173 @@ impl Contract for KyaRegistryContract {
     }
 }
 
+impl KyaRegistryContract {
+    /// Check that the operation is signed by the agent's owner, returning the signer
+    async fn authorize_owner(&mut self, agent_id: &AgentId) -> Result<AccountOwner, String> {
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .ok_or("Operation must be authenticated")?;
+        self.state.owned_badge(agent_id, &signer).await?;
+        Ok(signer)
+    }
+
+    /// Check that the operation is signed by the governance account, returning it
+    fn require_governance(&mut self) -> Result<AccountOwner, String> {
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .ok_or("Operation must be authenticated")?;
+        if self.runtime.application_parameters().governance != Some(signer) {
+            return Err("Only governance can do this".to_string());
+        }
+        Ok(signer)
+    }
+
+    /// Check that the operation is signed by a registered auditor or governance, returning it
+    fn require_auditor(&mut self) -> Result<AccountOwner, String> {
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .ok_or("Operation must be authenticated")?;
+        if !self.runtime.application_parameters().is_auditor(&signer) {
+            return Err("Only registered auditors can submit audits".to_string());
+        }
+        Ok(signer)
+    }
+
+    /// Apply a governance status change (suspend, reinstate or ban)
+    async fn change_status(&mut self, agent_id: AgentId, status: AgentStatus, reason: String) -> Response {
+        let governance = match self.require_governance() {
+            Ok(governance) => governance,
+            Err(e) => return Response::Error(e),
+        };
+        let timestamp = self.runtime.system_time();
+        let params = self.runtime.application_parameters();
+
+        let before = self.badge_snapshot(&agent_id).await;
+        let event = (AgentNotificationKind::StatusChanged, format!("{status:?}: {reason}"));
+        match self
+            .state
+            .change_status(&agent_id, status, governance, reason, timestamp, &params)
+            .await
+        {
+            Ok(()) => {
+                self.notify_agent(agent_id, before, Some(event)).await;
+                Response::StatusChanged { agent_id, status }
+            }
+            Err(e) => Response::Error(e),
+        }
+    }
+
+    /// Snapshot an agent's badge before a change, to compare against afterwards
+    async fn badge_snapshot(&mut self, agent_id: &AgentId) -> Option<BadgeSnapshot> {
+        let badge = self.state.get_badge(agent_id).await.ok().flatten()?;
+        Some(BadgeSnapshot::from(&badge))
+    }
+
+    /// Remember the chain activity came from as the agent's home chain, then
+    /// notify it of `event` and any score or tier change
+    async fn activity_recorded(
+        &mut self,
+        agent_id: AgentId,
+        before: Option<BadgeSnapshot>,
+        event: Option<(AgentNotificationKind, String)>,
+    ) {
+        if let Some(origin) = self.runtime.message_origin_chain_id() {
+            let _ = self.state.set_home_chain(&agent_id, origin);
+        }
+        self.notify_agent(agent_id, before, event).await;
+    }
+
+    /// Send `event` and any score, tier, status or capability change since
+    /// `before` to the agent's home chain, where this application keeps them for
+    /// the agent client to read. Agents that have never reported activity have
+    /// no home chain yet.
+    async fn notify_agent(
+        &mut self,
+        agent_id: AgentId,
+        before: Option<BadgeSnapshot>,
+        event: Option<(AgentNotificationKind, String)>,
+    ) {
+        let Ok(Some(chain_id)) = self.state.get_home_chain(&agent_id).await else {
+            return;
+        };
+        let Some(after) = self.badge_snapshot(&agent_id).await else {
+            return;
+        };
+
+        let mut updates = before.map_or_else(Vec::new, |before| after.changes_since(&before));
+        // An explicit event carries more detail than the matching change
+        if let Some((kind, detail)) = event {
+            updates.retain(|(change, _)| *change != kind);
+            updates.push((kind, detail));
+        }
+
+        let timestamp = self.runtime.system_time();
+        for (kind, detail) in updates {
+            let message = Message::AgentUpdate(AgentUpdate {
+                agent_id,
+                kind,
+                detail,
+                badge: after.clone(),
+                timestamp,
+            });
+            self.runtime.prepare_message(message).send_to(chain_id);
+        }
+    }
+
+    /// Check that the operation is signed by the agent's owner or by an operator
+    /// currently delegated `scope`, returning the signer
+    async fn authorize(&mut self, agent_id: &AgentId, scope: DelegationScope) -> Result<AccountOwner, String> {
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .ok_or("Operation must be authenticated")?;
+        let now = self.runtime.system_time();
+        self.state.authorized_badge(agent_id, &signer, scope, now).await?;
+        Ok(signer)
+    }
+
+    /// Send a `CodeUpdated` notification to the subscribers' chains in `recipients`
+    /// and to extra watcher chains
+    fn notify_code_update(
+        &mut self,
+        agent_id: AgentId,
+        record: &CodeUpdateRecord,
+        mut recipients: BTreeMap<ChainId, Vec<[u8; 32]>>,
+        notify_chains: Vec<ChainId>,
+    ) {
+        for chain_id in notify_chains {
+            recipients.entry(chain_id).or_default();
+        }
+
+        for (chain_id, broken_pins) in recipients {
+            let message = Message::CodeUpdated {
+                agent_id,
+                old_code_hash: record.old_code_hash,
+                new_code_hash: record.new_code_hash,
+                new_version: record.new_version.clone(),
+                broken_pins,
+                timestamp: record.timestamp,
+            };
+            self.runtime
+                .prepare_message(message)
+                .with_tracking()
+                .send_to(chain_id);
+        }
+    }
+}
+
+/// Notification for an audit outcome
+fn audit_event(passed: bool) -> (AgentNotificationKind, String) {
+    if passed {
+        (AgentNotificationKind::AuditPassed, "Audit passed".to_string())
+    } else {
+        (AgentNotificationKind::AuditFailed, "Audit failed".to_string())
+    }
+}
+
 impl WithContractAbi for KyaRegistryContract {
     type Abi = KyaRegistryAbi;
 }
@@ -2,52 +2,737 @@
 //!
 //! This library defines the core types and ABIs for the KYA Registry Linera application.
 
+pub mod merkle;
+pub mod state;
 #[cfg(test)]
 mod tests;
 
-use linera_sdk::linera_base_types::{AccountOwner, Timestamp, Amount};
+use linera_sdk::linera_base_types::{
+    AccountOwner, Amount, BcsHashable, ChainId, CryptoHash, TimeDelta, Timestamp,
+};
 use serde::{Deserialize, Serialize};
 
 /// The Application Binary Interface for KYA Registry
 pub struct KyaRegistryAbi;
 
+// ============================================================================
+// Parameters
+// ============================================================================
+
+/// Registry-wide configuration, fixed when the application is created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegistryParameters {
+    /// Number of distinct verifiers that must report a hash mismatch against the
+    /// current code version before the penalty applies
+    pub mismatch_threshold: u32,
+    /// Score penalty applied once when the mismatch threshold is reached
+    pub mismatch_penalty: u16,
+    /// Score penalty for code updates that change capabilities, tools or resources
+    pub functional_update_penalty: u16,
+    /// Account allowed to run governance operations (suspend, ban, reinstate,
+    /// slash and treasury withdrawals)
+    pub governance: Option<AccountOwner>,
+    /// Accounts whose code-hash attestations are accepted, besides `governance`
+    pub verifiers: Vec<AccountOwner>,
+    /// Accounts allowed to submit audit results, besides `governance`
+    pub auditors: Vec<AccountOwner>,
+    /// How slashed stake is divided between the treasury, the reporter and a harmed subscriber
+    pub slash_split: SlashSplit,
+    /// Seconds an agent must stay registered before retiring refunds its deposit
+    pub deposit_lock_secs: u64,
+    /// Score changes for task outcomes, by whether a counterparty attested them
+    pub task_weights: TaskWeights,
+    /// Score change per star above or below a 3-star rating
+    pub rating_weight: u16,
+    /// Tier table, ordered by ascending `min_score`; the first tier starts at 0
+    /// and is also assigned to inactive agents and agents awaiting re-audit
+    pub tiers: Vec<TierDefinition>,
+}
+
+impl Default for RegistryParameters {
+    fn default() -> Self {
+        Self {
+            mismatch_threshold: 3,
+            mismatch_penalty: 100,
+            functional_update_penalty: 50,
+            governance: None,
+            verifiers: Vec::new(),
+            auditors: Vec::new(),
+            slash_split: SlashSplit::default(),
+            deposit_lock_secs: 30 * 24 * 60 * 60,
+            task_weights: TaskWeights::default(),
+            rating_weight: 2,
+            tiers: TierDefinition::default_table(),
+        }
+    }
+}
+
+impl RegistryParameters {
+    /// Check that the tier table is usable
+    pub fn validate(&self) -> Result<(), String> {
+        let first = self.tiers.first().ok_or("At least one tier is required")?;
+        if first.min_score != 0 {
+            return Err("The first tier must start at score 0".to_string());
+        }
+        if self
+            .tiers
+            .windows(2)
+            .any(|pair| pair[0].min_score >= pair[1].min_score)
+        {
+            return Err("Tiers must be ordered by strictly increasing min_score".to_string());
+        }
+        self.slash_split.validate()
+    }
+
+    /// Whether `account` may attest code hashes
+    pub fn is_verifier(&self, account: &AccountOwner) -> bool {
+        self.governance.as_ref() == Some(account) || self.verifiers.contains(account)
+    }
+
+    /// Whether `account` may submit audit results
+    pub fn is_auditor(&self, account: &AccountOwner) -> bool {
+        self.governance.as_ref() == Some(account) || self.auditors.contains(account)
+    }
+
+    /// The lowest tier
+    pub fn floor_tier(&self) -> &TierDefinition {
+        &self.tiers[0]
+    }
+
+    /// Look up a tier by name
+    pub fn tier(&self, name: &str) -> Option<&TierDefinition> {
+        self.tiers.iter().find(|tier| tier.name == name)
+    }
+}
+
 // ============================================================================
 // Data Types
 // ============================================================================
 
-/// Reputation tiers for agents - determines rate limits and trust level
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, async_graphql::Enum)]
-pub enum ReputationTier {
-    #[default]
-    Unverified, // Rate Limit: 0 req/sec
-    Verified,   // Rate Limit: 10 req/sec
-    Gold,       // Rate Limit: 100 req/sec
-    Platinum,   // Rate Limit: Unlimited
+/// z-score of the two-sided 95% confidence interval used for reliability bounds
+const RELIABILITY_Z: f64 = 1.96;
+
+/// Unique identifier of a registered agent, stable across ownership changes
+pub type AgentId = CryptoHash;
+
+/// Input hashed to derive an agent ID at registration
+#[derive(Serialize, Deserialize)]
+struct AgentIdSeed {
+    owner: AccountOwner,
+    nonce: u64,
 }
 
-impl ReputationTier {
-    /// Get the rate limit for this tier (requests per second)
-    pub fn rate_limit(&self) -> Option<u32> {
-        match self {
-            ReputationTier::Unverified => Some(0),
-            ReputationTier::Verified => Some(10),
-            ReputationTier::Gold => Some(100),
-            ReputationTier::Platinum => None, // Unlimited
+impl BcsHashable<'_> for AgentIdSeed {}
+
+/// Derive the ID of the `nonce`-th agent registered by `owner`
+pub fn derive_agent_id(owner: AccountOwner, nonce: u64) -> AgentId {
+    CryptoHash::new(&AgentIdSeed { owner, nonce })
+}
+
+/// Parse a 32-byte hash from hex, with or without a `0x` prefix
+pub fn parse_hash(hash: &str) -> Option<[u8; 32]> {
+    hex::decode(hash.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+}
+
+/// Lower bound of the 95% Wilson score interval on a success rate, in basis
+/// points. Unlike the plain rate it stays low until there are enough outcomes
+/// to be confident, so thin track records cannot clear it.
+pub fn wilson_lower_bound_bps(successes: u64, failures: u64) -> u16 {
+    let total = successes + failures;
+    if total == 0 {
+        return 0;
+    }
+    let n = total as f64;
+    let p = successes as f64 / n;
+    let z2 = RELIABILITY_Z * RELIABILITY_Z;
+    let centre = p + z2 / (2.0 * n);
+    let margin = RELIABILITY_Z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    let bound = (centre - margin) / (1.0 + z2 / n);
+    (bound.max(0.0) * 10_000.0).floor() as u16
+}
+
+/// Percentages of a slash paid to each party; they must add up to 100
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct SlashSplit {
+    /// Share kept by the protocol treasury
+    pub treasury_percent: u8,
+    /// Share paid to the account that reported the misbehaviour
+    pub reporter_percent: u8,
+    /// Share paid to a harmed subscriber as compensation
+    pub compensation_percent: u8,
+}
+
+impl Default for SlashSplit {
+    fn default() -> Self {
+        Self {
+            treasury_percent: 50,
+            reporter_percent: 25,
+            compensation_percent: 25,
+        }
+    }
+}
+
+impl SlashSplit {
+    /// Check that the percentages add up to 100
+    pub fn validate(&self) -> Result<(), String> {
+        let total = u16::from(self.treasury_percent)
+            + u16::from(self.reporter_percent)
+            + u16::from(self.compensation_percent);
+        if total != 100 {
+            return Err(format!("Slash split must add up to 100 percent, not {}", total));
+        }
+        Ok(())
+    }
+
+    /// Divide a slashed `amount`. Shares without a recipient, and any rounding
+    /// remainder, go to the treasury.
+    pub fn distribute(&self, amount: Amount, has_reporter: bool, has_claimant: bool) -> SlashDistribution {
+        let share = |percent: u8, present: bool| {
+            if !present {
+                return Amount::ZERO;
+            }
+            let attos = amount.to_attos();
+            let percent = u128::from(percent);
+            Amount::from_attos(attos / 100 * percent + attos % 100 * percent / 100)
+        };
+        let reporter = share(self.reporter_percent, has_reporter);
+        let compensation = share(self.compensation_percent, has_claimant);
+        SlashDistribution {
+            treasury: amount.saturating_sub(reporter).saturating_sub(compensation),
+            reporter,
+            compensation,
+        }
+    }
+}
+
+/// Score changes applied for task outcomes. Self-reported outcomes are cheap to
+/// fake, so by default only failures count unless the counterparty attests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct TaskWeights {
+    /// Score gained for a success attested by the counterparty
+    pub attested_success: u16,
+    /// Score lost for a failure attested by the counterparty
+    pub attested_failure: u16,
+    /// Score gained for a self-reported success
+    pub unattested_success: u16,
+    /// Score lost for a self-reported failure
+    pub unattested_failure: u16,
+}
+
+impl Default for TaskWeights {
+    fn default() -> Self {
+        Self {
+            attested_success: 5,
+            attested_failure: 10,
+            unattested_success: 0,
+            unattested_failure: 2,
+        }
+    }
+}
+
+/// Where the proceeds of one slash went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct SlashDistribution {
+    /// Added to the treasury
+    pub treasury: Amount,
+    /// Credited to the reporter
+    pub reporter: Amount,
+    /// Credited to the harmed subscriber
+    pub compensation: Amount,
+}
+
+/// A reputation tier - determines rate limits and trust level
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct TierDefinition {
+    /// Tier name shown on badges (e.g. "Gold")
+    pub name: String,
+    /// Minimum reputation score
+    pub min_score: u16,
+    /// Requests per second allowed; `None` means unlimited
+    pub rate_limit: Option<u32>,
+    /// Minimum stake balance
+    #[serde(default)]
+    pub min_stake: Amount,
+    /// Whether the agent must have passed an audit submitted by a registered
+    /// auditor (see `RegistryParameters::auditors`)
+    #[serde(default)]
+    pub requires_audit: bool,
+    /// How long a passed audit counts towards this tier, in seconds;
+    /// `None` accepts any past audit
+    #[serde(default)]
+    pub audit_max_age_secs: Option<u64>,
+    /// Minimum lower confidence bound on attested task reliability, in basis
+    /// points (8000 = the agent succeeds at least 80% of the time with 95% confidence)
+    #[serde(default)]
+    pub min_reliability_bps: u16,
+}
+
+impl TierDefinition {
+    /// A tier that only depends on score
+    pub fn new(name: &str, min_score: u16, rate_limit: Option<u32>) -> Self {
+        Self {
+            name: name.to_string(),
+            min_score,
+            rate_limit,
+            min_stake: Amount::ZERO,
+            requires_audit: false,
+            audit_max_age_secs: None,
+            min_reliability_bps: 0,
+        }
+    }
+
+    /// Require a minimum stake balance
+    pub fn with_min_stake(mut self, min_stake: Amount) -> Self {
+        self.min_stake = min_stake;
+        self
+    }
+
+    /// Require a minimum reliability lower bound, in basis points
+    pub fn with_min_reliability(mut self, min_reliability_bps: u16) -> Self {
+        self.min_reliability_bps = min_reliability_bps;
+        self
+    }
+
+    /// Require a passed audit, optionally no older than `max_age_secs`
+    pub fn with_audit(mut self, max_age_secs: Option<u64>) -> Self {
+        self.requires_audit = true;
+        self.audit_max_age_secs = max_age_secs;
+        self
+    }
+
+    /// The default Unverified/Verified/Gold/Platinum table
+    pub fn default_table() -> Vec<TierDefinition> {
+        vec![
+            TierDefinition::new("Unverified", 0, Some(0)),
+            TierDefinition::new("Verified", 250, Some(10)).with_min_stake(Amount::from_tokens(10)),
+            TierDefinition::new("Gold", 500, Some(100))
+                .with_min_stake(Amount::from_tokens(100))
+                .with_min_reliability(8000),
+            TierDefinition::new("Platinum", 750, None)
+                .with_min_stake(Amount::from_tokens(1000))
+                .with_min_reliability(9000),
+        ]
+    }
+}
+
+/// A tier requirement that an agent does not meet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum TierRequirement {
+    /// Reputation score is below the tier minimum
+    Score,
+    /// Stake balance is below the tier minimum
+    Stake,
+    /// No passed audit from a registered auditor, or the last one is too old
+    Audit,
+    /// Too few or too unreliable tasks to be confident in the agent
+    Reliability,
+    /// A permission escalation is awaiting re-audit
+    Reaudit,
+    /// The agent is not active
+    Status,
+}
+
+/// Reputation earned in one declared capability
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct CapabilityScore {
+    /// Capability tag from the agent's manifest (e.g. "trading")
+    pub capability: String,
+    /// Reputation score for tasks in this capability (0-1000)
+    pub score: u16,
+    /// Successful tasks in this capability
+    pub tasks_completed: u64,
+    /// Failed tasks in this capability
+    pub tasks_failed: u64,
+}
+
+impl CapabilityScore {
+    /// A fresh sub-score, starting from the same base as the global score
+    pub fn new(capability: &str) -> Self {
+        Self {
+            capability: capability.to_string(),
+            score: 100,
+            tasks_completed: 0,
+            tasks_failed: 0,
+        }
+    }
+}
+
+/// A task outcome as reported by the agent's chain
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
+pub struct TaskReport {
+    /// SHA-256 hash identifying the task; the registry ignores hashes it has seen
+    #[graphql(skip)]
+    pub task_hash: [u8; 32],
+    /// Capability the task exercised; must be declared in the agent's manifest
+    pub capability: String,
+    /// Outcome claimed by the agent
+    pub success: bool,
+    /// Account that received the work, if the agent wants it attested
+    pub counterparty: Option<AccountOwner>,
+    /// When the task was logged on the agent's chain
+    pub timestamp: Timestamp,
+    /// Optional timing, cost and failure details
+    pub details: TaskDetails,
+}
+
+#[async_graphql::ComplexObject]
+impl TaskReport {
+    /// Task hash as a hex string
+    async fn task_hash_hex(&self) -> String {
+        hex::encode(self.task_hash)
+    }
+}
+
+/// Why a task failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum FailureReason {
+    /// The task ran out of time
+    Timeout,
+    /// The agent declined the task
+    Refusal,
+    /// The task finished with wrong output
+    WrongOutput,
+    /// A service the agent depends on failed
+    ExternalDependency,
+    /// The agent crashed
+    Crash,
+}
+
+/// Optional structured details of a task, reported by the agent
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
+pub struct TaskDetails {
+    /// When work on the task started
+    pub started_at: Option<Timestamp>,
+    /// When work on the task ended
+    pub ended_at: Option<Timestamp>,
+    /// Tokens or compute units spent on the task
+    pub cost: Option<u64>,
+    /// SHA-256 hash of the task's input
+    #[graphql(skip)]
+    pub input_hash: Option<[u8; 32]>,
+    /// SHA-256 hash of the task's output
+    #[graphql(skip)]
+    pub output_hash: Option<[u8; 32]>,
+    /// Why the task failed (failed tasks only)
+    pub failure_reason: Option<FailureReason>,
+}
+
+impl TaskDetails {
+    /// Time from start to end in microseconds, if both were reported
+    pub fn latency_micros(&self) -> Option<u64> {
+        self.started_at
+            .zip(self.ended_at)
+            .map(|(started, ended)| ended.delta_since(started).as_micros())
+    }
+
+    /// Check the details are consistent with the task's outcome
+    pub fn validate(&self, success: bool) -> Result<(), String> {
+        if let (Some(started), Some(ended)) = (self.started_at, self.ended_at) {
+            if ended < started {
+                return Err("Task cannot end before it starts".to_string());
+            }
         }
+        if success && self.failure_reason.is_some() {
+            return Err("A successful task cannot have a failure reason".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[async_graphql::ComplexObject]
+impl TaskDetails {
+    /// Time from start to end in milliseconds
+    async fn latency_ms(&self) -> Option<f64> {
+        self.latency_micros().map(|micros| micros as f64 / 1000.0)
+    }
+
+    /// Input hash as a hex string
+    async fn input_hash_hex(&self) -> Option<String> {
+        self.input_hash.map(hex::encode)
+    }
+
+    /// Output hash as a hex string
+    async fn output_hash_hex(&self) -> Option<String> {
+        self.output_hash.map(hex::encode)
+    }
+}
+
+/// Number of failures with one reason
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct FailureModeCount {
+    /// Failure reason
+    pub reason: FailureReason,
+    /// Failures reported with this reason
+    pub count: u64,
+}
+
+/// Latency, cost and failure-mode totals over reported task details
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
+pub struct TaskPerformance {
+    /// Tasks that reported both start and end times
+    pub timed_tasks: u64,
+    /// Sum of their latencies in microseconds
+    pub total_latency_micros: u64,
+    /// Longest reported latency in microseconds
+    pub max_latency_micros: u64,
+    /// Tasks that reported a cost
+    pub costed_tasks: u64,
+    /// Sum of reported costs
+    pub total_cost: u64,
+    /// Failures by reported reason
+    pub failure_modes: Vec<FailureModeCount>,
+    /// Failures reported without a reason
+    pub unclassified_failures: u64,
+}
+
+impl TaskPerformance {
+    /// Add one task's details
+    pub fn record(&mut self, success: bool, details: &TaskDetails) {
+        if let Some(latency) = details.latency_micros() {
+            self.timed_tasks += 1;
+            self.total_latency_micros = self.total_latency_micros.saturating_add(latency);
+            self.max_latency_micros = self.max_latency_micros.max(latency);
+        }
+        if let Some(cost) = details.cost {
+            self.costed_tasks += 1;
+            self.total_cost = self.total_cost.saturating_add(cost);
+        }
+        if !success {
+            match details.failure_reason {
+                Some(reason) => self.add_failures(reason, 1),
+                None => self.unclassified_failures += 1,
+            }
+        }
+    }
+
+    /// Add totals gathered elsewhere (for example over an anchored batch)
+    pub fn merge(&mut self, other: &TaskPerformance) {
+        self.timed_tasks += other.timed_tasks;
+        self.total_latency_micros = self.total_latency_micros.saturating_add(other.total_latency_micros);
+        self.max_latency_micros = self.max_latency_micros.max(other.max_latency_micros);
+        self.costed_tasks += other.costed_tasks;
+        self.total_cost = self.total_cost.saturating_add(other.total_cost);
+        for mode in &other.failure_modes {
+            self.add_failures(mode.reason, mode.count);
+        }
+        self.unclassified_failures += other.unclassified_failures;
+    }
+
+    /// Number of failures reported with `reason`
+    pub fn failures_with(&self, reason: FailureReason) -> u64 {
+        self.failure_modes
+            .iter()
+            .find(|mode| mode.reason == reason)
+            .map_or(0, |mode| mode.count)
+    }
+
+    /// Number of failures covered, with or without a reason
+    pub fn failure_count(&self) -> u64 {
+        self.failure_modes.iter().map(|mode| mode.count).sum::<u64>() + self.unclassified_failures
     }
 
-    /// Calculate tier from reputation score
-    pub fn from_score(score: u16) -> Self {
-        match score {
-            0..=249 => ReputationTier::Unverified,
-            250..=499 => ReputationTier::Verified,
-            500..=749 => ReputationTier::Gold,
-            750..=1000 => ReputationTier::Platinum,
-            _ => ReputationTier::Platinum,
+    fn add_failures(&mut self, reason: FailureReason, count: u64) {
+        match self.failure_modes.iter_mut().find(|mode| mode.reason == reason) {
+            Some(mode) => mode.count += count,
+            None => self.failure_modes.push(FailureModeCount { reason, count }),
         }
     }
 }
 
+#[async_graphql::ComplexObject]
+impl TaskPerformance {
+    /// Average reported latency in milliseconds
+    async fn average_latency_ms(&self) -> Option<f64> {
+        (self.timed_tasks > 0).then(|| self.total_latency_micros as f64 / self.timed_tasks as f64 / 1000.0)
+    }
+
+    /// Average reported cost per task
+    async fn average_cost(&self) -> Option<f64> {
+        (self.costed_tasks > 0).then(|| self.total_cost as f64 / self.costed_tasks as f64)
+    }
+}
+
+/// Self-reported outcomes in one capability, reported as counts instead of entries
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct CapabilityTally {
+    /// Capability tag
+    pub capability: String,
+    /// Successful tasks
+    pub successes: u64,
+    /// Failed tasks
+    pub failures: u64,
+}
+
+/// Merkle root over one batch of an agent chain's task log. Anyone can fetch an
+/// inclusion proof for a task from the agent chain and check it against this root.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
+pub struct TaskAnchor {
+    /// Sequence number of the batch, starting at 0
+    pub epoch: u64,
+    /// Merkle root over the batch's task log entries
+    #[graphql(skip)]
+    pub root: [u8; 32],
+    /// Index of the batch's first entry in the agent's task log
+    pub first_task: u64,
+    /// Number of entries in the batch
+    pub task_count: u64,
+    /// Counts of the batch's tasks that name no counterparty, per capability
+    pub tallies: Vec<CapabilityTally>,
+    /// Totals over the details of the tasks counted in `tallies`
+    pub performance: TaskPerformance,
+    /// When the agent chain sent the batch
+    pub timestamp: Timestamp,
+}
+
+impl TaskAnchor {
+    /// Check the root's counts against the agent's declared `capabilities` and
+    /// the number of attested tasks sent with it, returning how many tasks its
+    /// tallies cover
+    pub fn check(&self, capabilities: &[String], attested_tasks: usize) -> Result<u64, String> {
+        if let Some(tally) = self
+            .tallies
+            .iter()
+            .find(|tally| !capabilities.contains(&tally.capability))
+        {
+            return Err(format!("Capability {} is not declared in the manifest", tally.capability));
+        }
+        let tallied = self
+            .tallies
+            .iter()
+            .map(|tally| tally.successes.saturating_add(tally.failures))
+            .fold(0u64, u64::saturating_add);
+        if tallied.saturating_add(attested_tasks as u64) != self.task_count {
+            return Err("Task root counts do not add up".to_string());
+        }
+        let tallied_failures = self.tallies.iter().map(|tally| tally.failures).fold(0u64, u64::saturating_add);
+        let performance = &self.performance;
+        if performance.timed_tasks > tallied
+            || performance.costed_tasks > tallied
+            || performance.failure_count() != tallied_failures
+        {
+            return Err("Task root performance totals do not match its counts".to_string());
+        }
+        Ok(tallied)
+    }
+}
+
+/// What the registry did with a task root that arrived in epoch order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnchorOutcome {
+    /// The root was stored and its counts applied (none if it was a resend)
+    Recorded { tasks: u64 },
+    /// The root was invalid; its epoch is used up so later roots are not held
+    /// back, and none of its tasks count
+    Rejected { reason: String },
+}
+
+#[async_graphql::ComplexObject]
+impl TaskAnchor {
+    /// Merkle root as a hex string
+    async fn root_hex(&self) -> String {
+        hex::encode(self.root)
+    }
+}
+
+/// A task reported by an agent that names a counterparty and awaits its attestation
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
+pub struct PendingTask {
+    /// SHA-256 hash of the task description
+    #[graphql(skip)]
+    pub task_hash: [u8; 32],
+    /// Account that received the work and must attest the outcome
+    pub counterparty: AccountOwner,
+    /// Capability the task exercised
+    pub capability: String,
+    /// Outcome claimed by the agent
+    pub claimed_success: bool,
+    /// Timestamp of the agent's report
+    pub reported_at: Timestamp,
+}
+
+#[async_graphql::ComplexObject]
+impl PendingTask {
+    /// Task hash as a hex string
+    async fn task_hash_hex(&self) -> String {
+        hex::encode(self.task_hash)
+    }
+}
+
+/// A task outcome co-signed by the counterparty that received the work
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
+pub struct TaskAttestation {
+    /// SHA-256 hash of the task description
+    #[graphql(skip)]
+    pub task_hash: [u8; 32],
+    /// Account that signed the attestation
+    pub counterparty: AccountOwner,
+    /// Capability the task exercised
+    pub capability: String,
+    /// Outcome claimed by the agent
+    pub claimed_success: bool,
+    /// Outcome confirmed by the counterparty (this is what counts)
+    pub success: bool,
+    /// Timestamp of the agent's report
+    pub reported_at: Timestamp,
+    /// Timestamp of the attestation
+    pub attested_at: Timestamp,
+    /// Whether the counterparty has rated the agent for this task
+    pub rated: bool,
+}
+
+#[async_graphql::ComplexObject]
+impl TaskAttestation {
+    /// Task hash as a hex string
+    async fn task_hash_hex(&self) -> String {
+        hex::encode(self.task_hash)
+    }
+}
+
+/// The interaction a rating was left for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum RatingSource {
+    /// A subscription payment
+    Subscription,
+    /// A counterparty-attested task
+    Task,
+}
+
+/// A 1-5 star rating left by a subscriber or task counterparty
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
+pub struct Rating {
+    /// Account that left the rating
+    pub rater: AccountOwner,
+    /// Number of stars, 1 to 5
+    pub stars: u8,
+    /// Storage CID of a written review, if any
+    pub review_cid: Option<String>,
+    /// Kind of interaction rated
+    pub source: RatingSource,
+    /// The rated task, for task ratings
+    #[graphql(skip)]
+    pub task_hash: Option<[u8; 32]>,
+    /// Timestamp of the rating
+    pub timestamp: Timestamp,
+}
+
+#[async_graphql::ComplexObject]
+impl Rating {
+    /// Hash of the rated task as a hex string, for task ratings
+    async fn task_hash_hex(&self) -> Option<String> {
+        self.task_hash.map(hex::encode)
+    }
+}
+
 /// Storage provider for agent code packages
 #[derive(Debug, Clone, Serialize, Deserialize, Default, async_graphql::Enum, PartialEq, Eq, Copy)]
 pub enum StorageProvider {
@@ -70,8 +755,24 @@ pub struct ToolDefinition {
     pub input_schema: String,
 }
 
+/// An independent attestation of an agent's off-chain package against its code hash
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct VerificationRecord {
+    /// Account that fetched the package and signed the attestation
+    pub verifier: AccountOwner,
+    /// Storage CID the package was fetched from
+    pub storage_cid: String,
+    /// SHA-256 hash computed by the verifier
+    #[graphql(skip)]
+    pub computed_hash: [u8; 32],
+    /// Whether the computed hash matched the registered code hash
+    pub matches: bool,
+    /// Timestamp of the attestation
+    pub timestamp: Timestamp,
+}
+
 /// Resource requirements for running the agent
-#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject, Default, PartialEq, Eq)]
 pub struct ResourceRequirements {
     /// Minimum memory in MB
     pub min_memory_mb: u32,
@@ -131,12 +832,545 @@ impl Default for AgentManifest {
     }
 }
 
+/// How much a code update changes what the agent can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default, async_graphql::Enum)]
+pub enum ChangeSeverity {
+    /// Metadata only, or capabilities/permissions removed - no penalty
+    #[default]
+    Cosmetic,
+    /// Capabilities, tools or resource needs changed - small penalty
+    Functional,
+    /// New permissions or newly required resources - agent must be re-audited
+    Escalation,
+}
+
+/// Structured difference between the manifests of two code versions
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq, async_graphql::SimpleObject)]
+pub struct ManifestDiff {
+    /// Capabilities declared by the new version only
+    pub added_capabilities: Vec<String>,
+    /// Capabilities dropped by the new version
+    pub removed_capabilities: Vec<String>,
+    /// Tools provided by the new version only
+    pub added_tools: Vec<String>,
+    /// Tools dropped by the new version
+    pub removed_tools: Vec<String>,
+    /// Tools whose description or input schema changed
+    pub changed_tools: Vec<String>,
+    /// Permissions required by the new version only
+    pub added_permissions: Vec<String>,
+    /// Permissions no longer required
+    pub removed_permissions: Vec<String>,
+    /// Resource flags newly required (e.g. "requires_filesystem")
+    pub resource_escalations: Vec<String>,
+    /// Whether any resource requirement changed
+    pub resources_changed: bool,
+    /// Overall classification of the update
+    pub severity: ChangeSeverity,
+}
+
+impl ManifestDiff {
+    /// Compare two manifests and classify the change
+    pub fn between(old: &AgentManifest, new: &AgentManifest) -> Self {
+        let (added_capabilities, removed_capabilities) =
+            list_changes(&old.capabilities, &new.capabilities);
+        let (added_permissions, removed_permissions) =
+            list_changes(&old.required_permissions, &new.required_permissions);
+
+        let old_tools: Vec<String> = old.tools.iter().map(|tool| tool.name.clone()).collect();
+        let new_tools: Vec<String> = new.tools.iter().map(|tool| tool.name.clone()).collect();
+        let (added_tools, removed_tools) = list_changes(&old_tools, &new_tools);
+        let changed_tools = new
+            .tools
+            .iter()
+            .filter(|tool| {
+                old.tools.iter().any(|previous| {
+                    previous.name == tool.name
+                        && (previous.description != tool.description
+                            || previous.input_schema != tool.input_schema)
+                })
+            })
+            .map(|tool| tool.name.clone())
+            .collect::<Vec<_>>();
+
+        let (old_res, new_res) = (&old.resources, &new.resources);
+        let resource_escalations = [
+            ("requires_gpu", old_res.requires_gpu, new_res.requires_gpu),
+            ("requires_network", old_res.requires_network, new_res.requires_network),
+            ("requires_filesystem", old_res.requires_filesystem, new_res.requires_filesystem),
+        ]
+        .into_iter()
+        .filter(|(_, before, after)| !before && *after)
+        .map(|(name, _, _)| name.to_string())
+        .collect::<Vec<_>>();
+        let resources_changed = old_res != new_res;
+
+        let severity = if !added_permissions.is_empty() || !resource_escalations.is_empty() {
+            ChangeSeverity::Escalation
+        } else if !added_capabilities.is_empty()
+            || !removed_capabilities.is_empty()
+            || !added_tools.is_empty()
+            || !removed_tools.is_empty()
+            || !changed_tools.is_empty()
+            || resources_changed
+        {
+            ChangeSeverity::Functional
+        } else {
+            ChangeSeverity::Cosmetic
+        };
+
+        Self {
+            added_capabilities,
+            removed_capabilities,
+            added_tools,
+            removed_tools,
+            changed_tools,
+            added_permissions,
+            removed_permissions,
+            resource_escalations,
+            resources_changed,
+            severity,
+        }
+    }
+}
+
+/// Entries only in `new`, and entries only in `old`
+fn list_changes(old: &[String], new: &[String]) -> (Vec<String>, Vec<String>) {
+    let added = new.iter().filter(|item| !old.contains(item)).cloned().collect();
+    let removed = old.iter().filter(|item| !new.contains(item)).cloned().collect();
+    (added, removed)
+}
+
+/// A recorded code update with its manifest diff
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct CodeUpdateRecord {
+    /// Update number (matches the badge's `update_count` after the update)
+    pub update_number: u32,
+    /// Code hash before the update
+    #[graphql(skip)]
+    pub old_code_hash: [u8; 32],
+    /// Code hash after the update
+    #[graphql(skip)]
+    pub new_code_hash: [u8; 32],
+    /// Manifest version before the update
+    pub old_version: String,
+    /// Manifest version after the update
+    pub new_version: String,
+    /// Classified manifest changes
+    pub diff: ManifestDiff,
+    /// Score penalty applied
+    pub penalty: u16,
+    /// Timestamp of the update
+    pub timestamp: Timestamp,
+}
+
+/// Subscription length used when `Subscribe` is called with a zero duration (30 days)
+pub const DEFAULT_SUBSCRIPTION_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// A paid subscription to an agent
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Subscription {
+    /// The subscribing account
+    pub subscriber: AccountOwner,
+    /// Chain that receives code update notifications, if any
+    pub notify_chain: Option<ChainId>,
+    /// Start of the current paid period
+    pub subscribed_at: Timestamp,
+    /// End of the paid period
+    pub expires_at: Timestamp,
+    /// Total paid for the current period, renewals included
+    pub paid: Amount,
+    /// Code hash the subscriber expects the agent to keep running
+    #[graphql(skip)]
+    pub pinned_code_hash: Option<[u8; 32]>,
+    /// Whether the subscriber has rated the agent during the current period
+    pub rated: bool,
+}
+
+impl Subscription {
+    /// Create a subscription starting at `timestamp` and lasting `duration_secs`
+    pub fn new(
+        subscriber: AccountOwner,
+        notify_chain: Option<ChainId>,
+        timestamp: Timestamp,
+        duration_secs: u64,
+    ) -> Self {
+        let duration_secs = if duration_secs == 0 {
+            DEFAULT_SUBSCRIPTION_SECS
+        } else {
+            duration_secs
+        };
+        Self {
+            subscriber,
+            notify_chain,
+            subscribed_at: timestamp,
+            expires_at: timestamp
+                .saturating_add(TimeDelta::from_micros(duration_secs.saturating_mul(1_000_000))),
+            paid: Amount::ZERO,
+            pinned_code_hash: None,
+            rated: false,
+        }
+    }
+
+    /// Record the amount paid for the period
+    pub fn with_payment(mut self, paid: Amount) -> Self {
+        self.paid = paid;
+        self
+    }
+
+    /// Whether the paid period covers `now`
+    pub fn is_active(&self, now: Timestamp) -> bool {
+        now < self.expires_at
+    }
+
+    /// Pay for `duration_secs` more (0 means 30 days). While the period is still
+    /// running it is extended and keeps its rating; otherwise a new period starts.
+    pub fn renew(&mut self, notify_chain: Option<ChainId>, timestamp: Timestamp, duration_secs: u64, paid: Amount) {
+        let mut next = Subscription::new(self.subscriber, notify_chain, timestamp, duration_secs).with_payment(paid);
+        next.pinned_code_hash = self.pinned_code_hash;
+        if self.is_active(timestamp) {
+            next.subscribed_at = self.subscribed_at;
+            next.expires_at = self
+                .expires_at
+                .saturating_add(next.expires_at.delta_since(timestamp));
+            next.paid = self.paid.saturating_add(paid);
+            next.rated = self.rated;
+        }
+        *self = next;
+    }
+
+    /// Check the subscriber may rate the agent at `now`: once per paid period,
+    /// while the period is running
+    pub fn check_can_rate(&self, now: Timestamp) -> Result<(), String> {
+        if !self.is_active(now) {
+            return Err("Subscription has expired".to_string());
+        }
+        if self.paid == Amount::ZERO {
+            return Err("Only paid subscriptions can rate".to_string());
+        }
+        if self.rated {
+            return Err("Already rated this subscription period".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A code update notification received from a registry chain
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
+pub struct CodeUpdateNotice {
+    /// The updated agent
+    pub agent_id: AgentId,
+    /// Code hash before the update
+    #[graphql(skip)]
+    pub old_code_hash: [u8; 32],
+    /// Code hash after the update
+    #[graphql(skip)]
+    pub new_code_hash: [u8; 32],
+    /// Manifest version after the update
+    pub new_version: String,
+    /// Code hashes pinned by this chain's subscribers that the agent no longer matches
+    #[graphql(skip)]
+    pub broken_pins: Vec<[u8; 32]>,
+    /// Timestamp of the update
+    pub timestamp: Timestamp,
+}
+
+#[async_graphql::ComplexObject]
+impl CodeUpdateNotice {
+    /// Code hash before the update as hex string
+    async fn old_code_hash_hex(&self) -> String {
+        hex::encode(self.old_code_hash)
+    }
+
+    /// Code hash after the update as hex string
+    async fn new_code_hash_hex(&self) -> String {
+        hex::encode(self.new_code_hash)
+    }
+
+    /// Whether the agent no longer matches a code hash pinned by a subscriber
+    async fn pin_warning(&self) -> bool {
+        !self.broken_pins.is_empty()
+    }
+
+    /// Broken pins as hex strings
+    async fn broken_pins_hex(&self) -> Vec<String> {
+        self.broken_pins.iter().map(hex::encode).collect()
+    }
+}
+
+impl CodeUpdateNotice {
+    /// Keep the pins `previous` reported as broken that this update does not
+    /// restore, so a later notice cannot hide an earlier pin warning
+    pub fn carry_broken_pins(&mut self, previous: &CodeUpdateNotice) {
+        for pin in &previous.broken_pins {
+            if *pin != self.new_code_hash && !self.broken_pins.contains(pin) {
+                self.broken_pins.push(*pin);
+            }
+        }
+    }
+}
+
+/// Kinds of lifecycle events recorded in an agent's history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum AgentEventKind {
+    /// Agent registered
+    Registered,
+    /// Ownership transfer proposed by the owner
+    TransferProposed,
+    /// Pending ownership transfer cancelled by the owner
+    TransferCancelled,
+    /// Ownership accepted by the new owner
+    OwnershipTransferred,
+    /// Recovery key set or cleared by the owner
+    RecoveryKeyChanged,
+    /// Owner key replaced using the recovery key
+    KeyRotated,
+    /// Operator key granted (or re-granted) scoped permissions
+    DelegationGranted,
+    /// Operator key's permissions revoked by the owner
+    DelegationRevoked,
+    /// Agent suspended by governance
+    Suspended,
+    /// Suspension lifted by governance
+    Reinstated,
+    /// Agent banned by governance
+    Banned,
+    /// Agent retired by its owner
+    Retired,
+    /// Stake slashed by governance
+    Slashed,
+    /// Audit requested by the owner from the agent's chain
+    AuditRequested,
+    /// Task root rejected; its epoch was skipped
+    TaskRootRejected,
+}
+
+/// What a notification pushed to an agent's home chain is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum AgentNotificationKind {
+    /// Reputation score changed
+    ScoreChanged,
+    /// Tier changed
+    TierChanged,
+    /// An audit passed
+    AuditPassed,
+    /// An audit failed
+    AuditFailed,
+    /// Flagged for spam
+    SpamFlagged,
+    /// Stake slashed
+    Slashed,
+    /// Suspended, reinstated, banned or retired
+    StatusChanged,
+    /// A task root was rejected and its epoch skipped
+    TaskRootRejected,
+    /// Capabilities declared in the manifest changed
+    CapabilitiesChanged,
+}
+
+/// The parts of a badge pushed to an agent's home chain for it to cache
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct BadgeSnapshot {
+    /// Reputation score (0-1000)
+    pub reputation_score: u16,
+    /// Current tier
+    pub tier: String,
+    /// Lifecycle status
+    pub status: AgentStatus,
+    /// Staked tokens
+    pub stake_balance: Amount,
+    /// Number of spam flags
+    pub spam_flags: u8,
+    /// Timestamp of the last passed audit, if any
+    pub last_passed_audit: Option<Timestamp>,
+    /// Average rating in hundredths of a star
+    pub customer_rating: Option<u16>,
+    /// Capabilities declared in the manifest; task roots using others are rejected
+    pub capabilities: Vec<String>,
+}
+
+impl From<&AgentBadge> for BadgeSnapshot {
+    fn from(badge: &AgentBadge) -> Self {
+        Self {
+            reputation_score: badge.reputation_score,
+            tier: badge.tier.clone(),
+            status: badge.status,
+            stake_balance: badge.stake_balance,
+            spam_flags: badge.spam_flags,
+            last_passed_audit: badge.last_passed_audit,
+            customer_rating: badge.customer_rating,
+            capabilities: badge.manifest.capabilities.clone(),
+        }
+    }
+}
+
+impl BadgeSnapshot {
+    /// Notifications for the score, tier, status and capability changes since
+    /// `before`
+    pub fn changes_since(&self, before: &BadgeSnapshot) -> Vec<(AgentNotificationKind, String)> {
+        let mut changes = Vec::new();
+        if before.reputation_score != self.reputation_score {
+            changes.push((
+                AgentNotificationKind::ScoreChanged,
+                format!("Score {} -> {}", before.reputation_score, self.reputation_score),
+            ));
+        }
+        if before.tier != self.tier {
+            changes.push((AgentNotificationKind::TierChanged, format!("Tier {} -> {}", before.tier, self.tier)));
+        }
+        if before.status != self.status {
+            changes.push((
+                AgentNotificationKind::StatusChanged,
+                format!("Status {:?} -> {:?}", before.status, self.status),
+            ));
+        }
+        if before.capabilities != self.capabilities {
+            changes.push((
+                AgentNotificationKind::CapabilitiesChanged,
+                format!("Capabilities: {}", self.capabilities.join(", ")),
+            ));
+        }
+        changes
+    }
+}
+
+/// A change to an agent's standing, sent to its home chain. The registry
+/// application there keeps these for the agent client to read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct AgentUpdate {
+    /// The agent the update is about
+    pub agent_id: AgentId,
+    /// What changed
+    pub kind: AgentNotificationKind,
+    /// Human-readable description of the change
+    pub detail: String,
+    /// The badge after the change
+    pub badge: BadgeSnapshot,
+    /// When the registry sent it
+    pub timestamp: Timestamp,
+}
+
+/// An entry in an agent's event history
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct AgentEvent {
+    /// What happened
+    pub kind: AgentEventKind,
+    /// Account that signed the operation
+    pub actor: AccountOwner,
+    /// Human-readable details
+    pub details: String,
+    /// Timestamp of the event
+    pub timestamp: Timestamp,
+}
+
+/// Lifecycle status of a registered agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, async_graphql::Enum)]
+pub enum AgentStatus {
+    /// Operating normally
+    #[default]
+    Active,
+    /// Temporarily disabled by governance; stake is locked
+    Suspended,
+    /// Permanently disabled by governance; stake stays locked
+    Banned,
+    /// Withdrawn by its owner; stake has been returned
+    Retired,
+}
+
+impl AgentStatus {
+    /// Whether an agent may move from this status to `next`
+    pub fn can_transition_to(self, next: AgentStatus) -> bool {
+        matches!(
+            (self, next),
+            (AgentStatus::Active, AgentStatus::Suspended)
+                | (AgentStatus::Active, AgentStatus::Banned)
+                | (AgentStatus::Active, AgentStatus::Retired)
+                | (AgentStatus::Suspended, AgentStatus::Active)
+                | (AgentStatus::Suspended, AgentStatus::Banned)
+        )
+    }
+}
+
+/// Owner-scoped permissions that can be delegated to an operator key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum DelegationScope {
+    /// Report task activity for the agent
+    LogTasks,
+    /// Change the agent's subscription price
+    ManagePricing,
+    /// Stake and unstake on the agent's behalf
+    ManageStake,
+    /// Publish new code versions
+    UpdateCode,
+}
+
+/// Permissions granted by an agent's owner to an operator key
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Delegation {
+    /// The operator key
+    pub operator: AccountOwner,
+    /// What the operator may do
+    pub scopes: Vec<DelegationScope>,
+    /// Timestamp of the grant
+    pub granted_at: Timestamp,
+    /// End of the delegation; `None` lasts until revoked
+    pub expires_at: Option<Timestamp>,
+}
+
+impl Delegation {
+    /// Create a delegation starting at `timestamp`, lasting `duration_secs` if given
+    pub fn new(
+        operator: AccountOwner,
+        scopes: Vec<DelegationScope>,
+        timestamp: Timestamp,
+        duration_secs: Option<u64>,
+    ) -> Self {
+        Self {
+            operator,
+            scopes,
+            granted_at: timestamp,
+            expires_at: duration_secs.map(|secs| {
+                timestamp.saturating_add(TimeDelta::from_micros(secs.saturating_mul(1_000_000)))
+            }),
+        }
+    }
+
+    /// Whether the delegation has not expired at `now`
+    pub fn is_active(&self, now: Timestamp) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+
+    /// Whether the delegation grants `scope` at `now`
+    pub fn allows(&self, scope: DelegationScope, now: Timestamp) -> bool {
+        self.is_active(now) && self.scopes.contains(&scope)
+    }
+}
+
+/// Whether `key` is the agent's `owner` or holds one of its active `delegations`
+/// at `now`; such keys are not a second party to the agent's work
+pub fn controls_agent(owner: &AccountOwner, delegations: &[Delegation], key: &AccountOwner, now: Timestamp) -> bool {
+    key == owner
+        || delegations
+            .iter()
+            .any(|delegation| delegation.operator == *key && delegation.is_active(now))
+}
+
 /// The Dynamic Reputation Badge - a Soulbound NFT for AI agents
 #[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
 pub struct AgentBadge {
     // === Identity ===
+    /// Unique agent identifier, assigned at registration
+    pub agent_id: AgentId,
     /// The agent's wallet owner
     pub owner: AccountOwner,
+    /// Account proposed as the new owner, pending its acceptance
+    pub pending_owner: Option<AccountOwner>,
+    /// Pre-registered key allowed to rotate the owner key in an emergency
+    pub recovery_key: Option<AccountOwner>,
+    /// Lifecycle status; only active agents earn tiers or appear in discovery
+    pub status: AgentStatus,
     /// SHA-256 hash of the full code package
     #[graphql(skip)]
     pub code_hash: [u8; 32],
@@ -154,28 +1388,75 @@ pub struct AgentBadge {
     // === Reputation ===
     /// Reputation score (0-1000)
     pub reputation_score: u16,
-    /// Current tier based on score
-    pub tier: ReputationTier,
+    /// Name of the current tier (see `RegistryParameters::tiers`)
+    pub tier: String,
+    /// Rate limit of the current tier in requests per second; `None` means unlimited
+    pub rate_limit: Option<u32>,
+    /// The tier above the current one, if any
+    pub next_tier: Option<String>,
+    /// Requirements of `next_tier` the agent does not meet yet
+    pub promotion_blockers: Vec<TierRequirement>,
     /// Count of reported spam/abuse incidents
     pub spam_flags: u8,
+    /// Set when a code update escalated permissions; the agent stays in the lowest
+    /// tier until it passes a new audit
+    pub pending_reaudit: bool,
     
     // === Economics ===
     /// Total amount staked by the agent (Native Token)
     pub stake_balance: Amount,
     /// Cost to subscribe to this agent for 30 days (Native Token)
     pub subscription_cost: Amount,
+    /// Non-refundable fee paid into the treasury at registration
+    pub registration_fee_paid: Amount,
+    /// Refundable deposit still held for the agent (zero once refunded or forfeited)
+    pub deposit: Amount,
     
     // === Audit & Activity ===
     /// Timestamp of last audit
     pub last_audit_timestamp: Timestamp,
+    /// Timestamp of the last passed audit, if any
+    pub last_passed_audit: Option<Timestamp>,
     /// Number of successful tasks completed
     pub tasks_completed: u64,
     /// Number of failed tasks
     pub tasks_failed: u64,
+    /// Successful tasks confirmed by a counterparty (included in `tasks_completed`)
+    pub attested_successes: u64,
+    /// Failed tasks confirmed by a counterparty (included in `tasks_failed`)
+    pub attested_failures: u64,
+    /// Number of distinct accounts that have attested tasks
+    pub unique_counterparties: u32,
+    /// Sub-scores for each capability the agent has logged tasks in
+    pub capability_scores: Vec<CapabilityScore>,
+    /// Number of task log roots anchored (the next expected epoch)
+    pub anchored_epochs: u64,
+    /// Latency, cost and failure-mode totals over the details reported with tasks
+    pub performance: TaskPerformance,
+    
+    // === Customer Rating ===
+    /// Average rating in hundredths of a star (450 = 4.5 stars); `None` until rated
+    pub customer_rating: Option<u16>,
+    /// Number of ratings received
+    pub rating_count: u32,
+    /// Sum of all stars received
+    pub rating_stars_total: u64,
+    
+    // === Integrity ===
+    /// Timestamp of the last independent verification that matched the code hash
+    pub last_verified_at: Option<Timestamp>,
+    /// Verifier of the last matching verification
+    pub last_verified_by: Option<AccountOwner>,
+    /// Total number of verifications submitted for this agent
+    pub verification_count: u32,
+    /// Number of distinct verifiers that reported a mismatch against the current code version
+    pub hash_mismatches: u32,
     
     // === Versioning ===
     /// Number of code updates (version changes)
     pub update_count: u32,
+    /// Number of lifecycle and ownership events in the agent's history
+    pub event_count: u32,
     /// Timestamp of registration
     pub registered_at: Timestamp,
     /// Timestamp of last code update
@@ -185,6 +1466,7 @@ pub struct AgentBadge {
 impl AgentBadge {
     /// Create a new badge for a freshly registered agent
     pub fn new(
+        agent_id: AgentId,
         owner: AccountOwner,
         code_hash: [u8; 32],
         storage_provider: StorageProvider,
@@ -193,31 +1475,137 @@ impl AgentBadge {
         timestamp: Timestamp,
     ) -> Self {
         Self {
+            agent_id,
             owner,
+            pending_owner: None,
+            recovery_key: None,
+            status: AgentStatus::Active,
             code_hash,
             storage_provider,
             storage_cid,
             manifest,
             reputation_score: 100, // Start with base score
-            tier: ReputationTier::Unverified,
+            // Assigned by `update_tier` once the registry's tier table is known
+            tier: String::new(),
+            rate_limit: Some(0),
+            next_tier: None,
+            promotion_blockers: Vec::new(),
             spam_flags: 0,
+            pending_reaudit: false,
             stake_balance: Amount::ZERO,
             subscription_cost: Amount::ZERO,
+            registration_fee_paid: Amount::ZERO,
+            deposit: Amount::ZERO,
             last_audit_timestamp: timestamp,
+            last_passed_audit: None,
             tasks_completed: 0,
             tasks_failed: 0,
+            attested_successes: 0,
+            attested_failures: 0,
+            unique_counterparties: 0,
+            capability_scores: Vec::new(),
+            anchored_epochs: 0,
+            performance: TaskPerformance::default(),
+            customer_rating: None,
+            rating_count: 0,
+            rating_stars_total: 0,
+            last_verified_at: None,
+            last_verified_by: None,
+            verification_count: 0,
+            hash_mismatches: 0,
             update_count: 0,
+            event_count: 0,
             registered_at: timestamp,
             last_updated_at: timestamp,
         }
     }
 
-    /// Recalculate tier based on current score
-    pub fn update_tier(&mut self) {
-        self.tier = ReputationTier::from_score(self.reputation_score);
+    /// Whether the agent has been registered long enough for retirement to refund its deposit
+    pub fn deposit_refundable(&self, params: &RegistryParameters, now: Timestamp) -> bool {
+        now.delta_since(self.registered_at) >= TimeDelta::from_secs(params.deposit_lock_secs)
+    }
+
+    /// Whether the agent is operating normally
+    pub fn is_active(&self) -> bool {
+        self.status == AgentStatus::Active
+    }
+
+    /// Move the agent to a new lifecycle status
+    pub fn set_status(
+        &mut self,
+        status: AgentStatus,
+        params: &RegistryParameters,
+        now: Timestamp,
+    ) -> Result<(), String> {
+        if !self.status.can_transition_to(status) {
+            return Err(format!("Cannot move a {:?} agent to {:?}", self.status, status));
+        }
+        self.status = status;
+        self.update_tier(params, now);
+        Ok(())
+    }
+
+    /// The score, stake and audit requirements of `tier` the agent does not meet at `now`
+    pub fn unmet_requirements(&self, tier: &TierDefinition, now: Timestamp) -> Vec<TierRequirement> {
+        let mut unmet = Vec::new();
+        if self.reputation_score < tier.min_score {
+            unmet.push(TierRequirement::Score);
+        }
+        if self.stake_balance < tier.min_stake {
+            unmet.push(TierRequirement::Stake);
+        }
+        if self.attested_reliability_lower_bound_bps() < tier.min_reliability_bps {
+            unmet.push(TierRequirement::Reliability);
+        }
+        if tier.requires_audit {
+            let audit_is_recent = self.last_passed_audit.is_some_and(|passed_at| {
+                tier.audit_max_age_secs.is_none_or(|max_age| {
+                    now.delta_since(passed_at) <= TimeDelta::from_micros(max_age.saturating_mul(1_000_000))
+                })
+            });
+            if !audit_is_recent {
+                unmet.push(TierRequirement::Audit);
+            }
+        }
+        unmet
+    }
+
+    /// Recalculate the tier as of `now`: the highest tier in the table whose
+    /// requirements are all met, plus what blocks promotion to the tier above
+    pub fn update_tier(&mut self, params: &RegistryParameters, now: Timestamp) {
+        let index = if self.pending_reaudit || !self.is_active() {
+            0
+        } else {
+            params
+                .tiers
+                .iter()
+                .rposition(|tier| self.unmet_requirements(tier, now).is_empty())
+                .unwrap_or(0)
+        };
+        let tier = &params.tiers[index];
+        self.tier = tier.name.clone();
+        self.rate_limit = tier.rate_limit;
+
+        match params.tiers.get(index + 1) {
+            Some(next) => {
+                let mut blockers = self.unmet_requirements(next, now);
+                if self.pending_reaudit {
+                    blockers.push(TierRequirement::Reaudit);
+                }
+                if !self.is_active() {
+                    blockers.push(TierRequirement::Status);
+                }
+                self.next_tier = Some(next.name.clone());
+                self.promotion_blockers = blockers;
+            }
+            None => {
+                self.next_tier = None;
+                self.promotion_blockers = Vec::new();
+            }
+        }
     }
 
-    /// Update the agent's code (new version)
+    /// Update the agent's code (new version), returning the record of the change
     pub fn update_code(
         &mut self,
         code_hash: [u8; 32],
@@ -225,20 +1613,222 @@ impl AgentBadge {
         storage_cid: String,
         manifest: AgentManifest,
         timestamp: Timestamp,
-    ) {
+        params: &RegistryParameters,
+    ) -> CodeUpdateRecord {
+        let diff = ManifestDiff::between(&self.manifest, &manifest);
+        let old_code_hash = self.code_hash;
+        let old_version = self.manifest.version.clone();
+
         self.code_hash = code_hash;
         self.storage_provider = storage_provider;
         self.storage_cid = storage_cid;
         self.manifest = manifest;
         self.update_count += 1;
         self.last_updated_at = timestamp;
-        // Code updates require re-verification, reset to Unverified tier
-        // but preserve the score
-        if self.tier != ReputationTier::Unverified {
-            // Small penalty for updating code without re-audit
+        // The new package has not been independently verified yet
+        self.last_verified_at = None;
+        self.last_verified_by = None;
+        self.hash_mismatches = 0;
+
+        let before = self.reputation_score;
+        match diff.severity {
+            ChangeSeverity::Cosmetic => {}
+            ChangeSeverity::Functional => {
+                // Small penalty for changing behavior without re-audit
+                self.reputation_score = self
+                    .reputation_score
+                    .saturating_sub(params.functional_update_penalty);
+            }
+            ChangeSeverity::Escalation => {
+                // Score is preserved, but trust is withheld until re-audited
+                self.pending_reaudit = true;
+            }
+        }
+        self.update_tier(params, timestamp);
+
+        CodeUpdateRecord {
+            update_number: self.update_count,
+            old_code_hash,
+            new_code_hash: code_hash,
+            old_version,
+            new_version: self.manifest.version.clone(),
+            diff,
+            penalty: before - self.reputation_score,
+            timestamp,
+        }
+    }
+
+    /// Apply an audit result; a passed audit clears any pending re-audit
+    pub fn apply_audit(&mut self, passed: bool, timestamp: Timestamp, params: &RegistryParameters) {
+        self.last_audit_timestamp = timestamp;
+        if passed {
+            self.pending_reaudit = false;
+            self.last_passed_audit = Some(timestamp);
+            self.reputation_score = self.reputation_score.saturating_add(100).min(1000);
+        } else {
             self.reputation_score = self.reputation_score.saturating_sub(50);
-            self.update_tier();
         }
+        self.update_tier(params, timestamp);
+    }
+
+    /// Apply an independent verification result, returning the score penalty applied.
+    /// `new_reporter` is whether this is the verifier's first mismatch against the
+    /// current code update; repeats from the same verifier are recorded but not counted.
+    pub fn record_verification(
+        &mut self,
+        record: &VerificationRecord,
+        new_reporter: bool,
+        params: &RegistryParameters,
+    ) -> u16 {
+        self.verification_count = self.verification_count.saturating_add(1);
+
+        if record.matches {
+            self.last_verified_at = Some(record.timestamp);
+            self.last_verified_by = Some(record.verifier);
+            return 0;
+        }
+        if !new_reporter {
+            return 0;
+        }
+
+        self.hash_mismatches = self.hash_mismatches.saturating_add(1);
+        // The penalty applies once, when the threshold is crossed
+        if self.hash_mismatches != params.mismatch_threshold {
+            return 0;
+        }
+
+        // Repeated mismatches suggest the registered package is not what is being served
+        let before = self.reputation_score;
+        self.reputation_score = self.reputation_score.saturating_sub(params.mismatch_penalty);
+        self.update_tier(params, record.timestamp);
+        before - self.reputation_score
+    }
+
+    /// Number of counterparty-attested task outcomes
+    pub fn attested_task_count(&self) -> u64 {
+        self.attested_successes + self.attested_failures
+    }
+
+    /// Share of successful tasks in basis points; `None` before the first task
+    pub fn reliability_bps(&self) -> Option<u16> {
+        let total = self.tasks_completed + self.tasks_failed;
+        if total == 0 {
+            return None;
+        }
+        Some((self.tasks_completed as f64 / total as f64 * 10_000.0).round() as u16)
+    }
+
+    /// Lower bound of the 95% Wilson score interval on the success rate over
+    /// all tasks, self-reported ones included, in basis points
+    pub fn reliability_lower_bound_bps(&self) -> u16 {
+        wilson_lower_bound_bps(self.tasks_completed, self.tasks_failed)
+    }
+
+    /// Lower bound of the 95% Wilson score interval on the success rate over
+    /// counterparty-attested tasks, in basis points. Tiers are gated on this
+    /// bound, since the owner can self-report any number of successes.
+    pub fn attested_reliability_lower_bound_bps(&self) -> u16 {
+        wilson_lower_bound_bps(self.attested_successes, self.attested_failures)
+    }
+
+    /// The sub-score for a capability, if the agent has logged tasks in it
+    pub fn capability_score(&self, capability: &str) -> Option<&CapabilityScore> {
+        self.capability_scores
+            .iter()
+            .find(|entry| entry.capability == capability)
+    }
+
+    /// Apply a task outcome to the global score and the capability's sub-score,
+    /// weighted by whether a counterparty attested it
+    pub fn record_task_outcome(
+        &mut self,
+        capability: &str,
+        success: bool,
+        attested: bool,
+        params: &RegistryParameters,
+        now: Timestamp,
+    ) {
+        let (successes, failures) = if success { (1, 0) } else { (0, 1) };
+        self.apply_task_outcomes(capability, successes, failures, attested, params);
+        self.update_tier(params, now);
+    }
+
+    /// Apply a batch's self-reported outcomes for one capability
+    pub fn record_task_tally(&mut self, tally: &CapabilityTally, params: &RegistryParameters, now: Timestamp) {
+        self.apply_task_outcomes(&tally.capability, tally.successes, tally.failures, false, params);
+        self.update_tier(params, now);
+    }
+
+    /// Add task counts and their score changes (successes first, then failures)
+    fn apply_task_outcomes(
+        &mut self,
+        capability: &str,
+        successes: u64,
+        failures: u64,
+        attested: bool,
+        params: &RegistryParameters,
+    ) {
+        let weights = &params.task_weights;
+        let (gain, loss) = if attested {
+            self.attested_successes += successes;
+            self.attested_failures += failures;
+            (weights.attested_success, weights.attested_failure)
+        } else {
+            (weights.unattested_success, weights.unattested_failure)
+        };
+        // Scores are capped at 1000, so anything beyond that saturates
+        let total_gain = u64::from(gain).saturating_mul(successes).min(1000) as u16;
+        let total_loss = u64::from(loss).saturating_mul(failures).min(1000) as u16;
+
+        let index = match self
+            .capability_scores
+            .iter()
+            .position(|entry| entry.capability == capability)
+        {
+            Some(index) => index,
+            None => {
+                self.capability_scores.push(CapabilityScore::new(capability));
+                self.capability_scores.len() - 1
+            }
+        };
+        let sub_score = &mut self.capability_scores[index];
+        sub_score.tasks_completed += successes;
+        sub_score.tasks_failed += failures;
+        sub_score.score = sub_score.score.saturating_add(total_gain).min(1000).saturating_sub(total_loss);
+
+        self.tasks_completed += successes;
+        self.tasks_failed += failures;
+        self.reputation_score = self
+            .reputation_score
+            .saturating_add(total_gain)
+            .min(1000)
+            .saturating_sub(total_loss);
+    }
+
+    /// Add a 1-5 star rating to the customer rating and move the score by
+    /// `rating_weight` per star above or below 3
+    pub fn record_rating(
+        &mut self,
+        stars: u8,
+        params: &RegistryParameters,
+        now: Timestamp,
+    ) -> Result<(), String> {
+        if !(1..=5).contains(&stars) {
+            return Err("Ratings must be between 1 and 5 stars".to_string());
+        }
+        self.rating_count = self.rating_count.saturating_add(1);
+        self.rating_stars_total = self.rating_stars_total.saturating_add(u64::from(stars));
+        self.customer_rating =
+            Some((self.rating_stars_total * 100 / u64::from(self.rating_count)) as u16);
+
+        let change = params.rating_weight.saturating_mul(u16::from(stars.abs_diff(3)));
+        self.reputation_score = if stars >= 3 {
+            self.reputation_score.saturating_add(change).min(1000)
+        } else {
+            self.reputation_score.saturating_sub(change)
+        };
+        self.update_tier(params, now);
+        Ok(())
     }
 
     /// Get the code hash as hex string
@@ -247,6 +1837,35 @@ impl AgentBadge {
     }
 }
 
+#[async_graphql::ComplexObject]
+impl AgentBadge {
+    /// SHA-256 hash of the full code package as hex string
+    /// (see the `kya-package-hash` crate for how it is computed)
+    #[graphql(name = "codeHashHex")]
+    async fn graphql_code_hash_hex(&self) -> String {
+        self.code_hash_hex()
+    }
+
+    /// Share of successful tasks in basis points; null before the first task
+    #[graphql(name = "reliabilityBps")]
+    async fn graphql_reliability_bps(&self) -> Option<u16> {
+        self.reliability_bps()
+    }
+
+    /// 95% lower confidence bound on the success rate, in basis points
+    #[graphql(name = "reliabilityLowerBoundBps")]
+    async fn graphql_reliability_lower_bound_bps(&self) -> u16 {
+        self.reliability_lower_bound_bps()
+    }
+
+    /// 95% lower confidence bound on the attested success rate, in basis
+    /// points; tiers are gated on this one
+    #[graphql(name = "attestedReliabilityLowerBoundBps")]
+    async fn graphql_attested_reliability_lower_bound_bps(&self) -> u16 {
+        self.attested_reliability_lower_bound_bps()
+    }
+}
+
 // ============================================================================
 // Operations (User-initiated actions)
 // ============================================================================
@@ -254,7 +1873,8 @@ impl AgentBadge {
 /// Operations that can be performed on the registry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Operation {
-    /// Register a new AI agent with full manifest
+    /// Register a new AI agent with full manifest, paying the current registration
+    /// fee and deposit
     RegisterAgent {
         /// SHA-256 hash of the full code package
         code_hash: [u8; 32],
@@ -265,8 +1885,9 @@ pub enum Operation {
         /// Agent manifest with metadata
         manifest: AgentManifest,
     },
-    /// Update an existing agent's code (new version)
+    /// Update an existing agent's code (new version) and notify subscribers
     UpdateAgentCode {
+        agent_id: AgentId,
         /// New code hash
         code_hash: [u8; 32],
         /// New storage provider
@@ -275,52 +1896,170 @@ pub enum Operation {
         storage_cid: String,
         /// Updated manifest
         manifest: AgentManifest,
+        /// Extra watcher chains to notify besides active subscribers
+        notify_chains: Vec<ChainId>,
     },
-    /// Manually adjust an agent's score (admin/DAO only)
+    /// Manually adjust an agent's score (governance only)
     AdjustScore {
-        agent_id: AccountOwner,
+        agent_id: AgentId,
         delta: i16,
         reason: String,
     },
     /// Flag an agent for spam/abuse
     FlagSpam {
-        agent_id: AccountOwner,
+        agent_id: AgentId,
         evidence: String,
     },
-    /// Submit an audit result for an agent
+    /// Confirm the outcome of a task that names the signer as its counterparty
+    AttestTask {
+        agent_id: AgentId,
+        task_hash: [u8; 32],
+        /// Outcome as seen by the counterparty; overrides the agent's claim
+        success: bool,
+    },
+    /// Rate an active agent 1-5 stars, once per paid subscription period (while it
+    /// runs) or attested task
+    RateAgent {
+        agent_id: AgentId,
+        stars: u8,
+        /// Storage CID of a written review
+        review_cid: Option<String>,
+        /// Attested task being rated; `None` rates the signer's latest subscription
+        task_hash: Option<[u8; 32]>,
+    },
+    /// Submit an audit result for an agent (registered auditors only)
     SubmitAudit {
-        agent_id: AccountOwner,
+        agent_id: AgentId,
         passed: bool,
         auditor_notes: String,
     },
-    /// Verify that a code hash matches the stored CID (anyone can verify)
+    /// Attest to the hash of the package fetched from the agent's storage CID
+    /// (registered verifiers only, and never the agent's owner)
     VerifyCodeHash {
-        agent_id: AccountOwner,
-        expected_hash: [u8; 32],
+        agent_id: AgentId,
+        /// Storage CID the package was fetched from
+        storage_cid: String,
+        /// SHA-256 hash computed over the fetched package
+        computed_hash: [u8; 32],
+    },
+    /// Read standing updates received on this chain for an agent, oldest first.
+    /// Called by the agent client on the agent's home chain.
+    ReadAgentUpdates {
+        agent_id: AgentId,
+        offset: u64,
+        limit: u64,
     },
     
     // === Economic Operations ===
     /// Lock tokens to increase security/trust (Native Token)
     Stake {
+        agent_id: AgentId,
         amount: Amount,
     },
     /// Withdraw staked tokens
     Unstake {
+        agent_id: AgentId,
         amount: Amount,
     },
-    /// Slash an agent's stake (Governance only)
+    /// Slash an agent's stake (Governance only); proceeds are divided by the
+    /// registry's slash split
     Slash {
-        agent_id: AccountOwner,
+        agent_id: AgentId,
         amount: Amount,
+        /// Account that raised the upheld report, rewarded with the reporter share
+        reporter: Option<AccountOwner>,
+        /// Subscriber harmed by the agent, credited with the compensation share
+        harmed_subscriber: Option<AccountOwner>,
     },
-    /// Set the subscription cost for this agent
+    /// Withdraw reporter rewards and compensation credited to the signer
+    ClaimSlashProceeds,
+    /// Set the subscription cost for an agent
     SetSubscriptionCost {
+        agent_id: AgentId,
         cost: Amount,
     },
+    // === Ownership Operations ===
+    /// Propose a new owner for an agent (step one of a transfer)
+    ProposeOwnershipTransfer {
+        agent_id: AgentId,
+        new_owner: AccountOwner,
+    },
+    /// Cancel an agent's pending ownership transfer
+    CancelOwnershipTransfer {
+        agent_id: AgentId,
+    },
+    /// Accept a proposed transfer (signed by the new owner)
+    AcceptOwnership {
+        agent_id: AgentId,
+    },
+    /// Set or clear the recovery key for an agent
+    SetRecoveryKey {
+        agent_id: AgentId,
+        recovery_key: Option<AccountOwner>,
+    },
+    /// Move an agent to a new owner key (signed by its recovery key)
+    RotateKey {
+        agent_id: AgentId,
+        new_owner: AccountOwner,
+    },
+    /// Grant an operator key scoped permissions, replacing any earlier grant to it
+    GrantDelegation {
+        agent_id: AgentId,
+        operator: AccountOwner,
+        scopes: Vec<DelegationScope>,
+        /// Delegation length in seconds; `None` lasts until revoked
+        duration: Option<u64>,
+    },
+    /// Revoke all permissions of an operator key
+    RevokeDelegation {
+        agent_id: AgentId,
+        operator: AccountOwner,
+    },
+    /// Retire an agent and return its stake, plus its deposit once the lock period
+    /// has passed (owner only)
+    RetireAgent {
+        agent_id: AgentId,
+    },
+
     /// User pays the agent for a subscription
     Subscribe {
-        agent_id: AccountOwner,
-        duration: u64, // Not used in logic yet (just payment), but good for indexing
+        agent_id: AgentId,
+        /// Subscription length in seconds (0 means 30 days)
+        duration: u64,
+        /// Chain to receive code update notifications on
+        notify_chain: Option<ChainId>,
+    },
+    /// Pin the code hash a subscriber expects; `None` removes the pin
+    PinCodeHash {
+        agent_id: AgentId,
+        code_hash: Option<[u8; 32]>,
+    },
+
+    // === Governance Operations ===
+    /// Temporarily disable an agent
+    SuspendAgent {
+        agent_id: AgentId,
+        reason: String,
+    },
+    /// Lift a suspension
+    ReinstateAgent {
+        agent_id: AgentId,
+        reason: String,
+    },
+    /// Permanently disable an agent
+    BanAgent {
+        agent_id: AgentId,
+        reason: String,
+    },
+    /// Set the fee and refundable deposit charged for new registrations
+    SetRegistrationFees {
+        fee: Amount,
+        deposit: Amount,
+    },
+    /// Pay out part of the treasury balance
+    WithdrawTreasury {
+        amount: Amount,
+        recipient: AccountOwner,
     },
 }
 
@@ -329,35 +2068,74 @@ pub enum Operation {
 pub enum Response {
     /// Agent successfully registered
     AgentRegistered { 
-        agent_id: AccountOwner,
+        agent_id: AgentId,
         storage_cid: String,
+        fee_paid: Amount,
+        deposit: Amount,
     },
     /// Agent code updated
     AgentUpdated {
-        agent_id: AccountOwner,
+        agent_id: AgentId,
         version: String,
         update_count: u32,
+        severity: ChangeSeverity,
+        pending_reaudit: bool,
     },
     /// Score was adjusted
-    ScoreAdjusted { new_score: u16, new_tier: ReputationTier },
+    ScoreAdjusted { new_score: u16, new_tier: String },
     /// Spam flag recorded
     SpamFlagged { total_flags: u8 },
     /// Audit submitted
     AuditSubmitted { passed: bool },
-    /// Hash verification result
-    HashVerified { matches: bool },
+    /// Task outcome attested by its counterparty
+    TaskAttested { agent_id: AgentId, success: bool, new_score: u16 },
+    /// Rating recorded; `customer_rating` is in hundredths of a star
+    AgentRated { agent_id: AgentId, customer_rating: Option<u16>, new_score: u16 },
+    /// Hash verification recorded
+    HashVerified { matches: bool, hash_mismatches: u32, penalty: u16 },
+    /// Standing updates received for an agent; `next` is the offset to read from next
+    AgentUpdates { agent_id: AgentId, updates: Vec<AgentUpdate>, next: u64 },
     
     // === Economic Responses ===
     /// Staked successfully
-    Staked { agent_id: AccountOwner, amount: Amount, new_balance: Amount },
+    Staked { agent_id: AgentId, amount: Amount, new_balance: Amount },
     /// Unstaked successfully
-    Unstaked { agent_id: AccountOwner, amount: Amount, remaining_balance: Amount },
+    Unstaked { agent_id: AgentId, amount: Amount, remaining_balance: Amount },
     /// Slashed successfully
-    Slashed { agent_id: AccountOwner, amount: Amount },
+    Slashed { agent_id: AgentId, amount: Amount, distribution: SlashDistribution },
+    /// Credited slash proceeds paid out
+    SlashProceedsClaimed { claimant: AccountOwner, amount: Amount },
+    /// Treasury paid out
+    TreasuryWithdrawn { recipient: AccountOwner, amount: Amount, remaining_balance: Amount },
     /// Subscription success
-    Subscribed { agent_id: AccountOwner, subscriber: AccountOwner, cost: Amount },
+    Subscribed { agent_id: AgentId, subscriber: AccountOwner, cost: Amount, expires_at: Timestamp },
+    /// Pinned code hash updated; `matches_current` tells whether the agent runs it now
+    CodeHashPinned { agent_id: AgentId, matches_current: bool },
     /// Cost updated
-    CostUpdated { agent_id: AccountOwner, new_cost: Amount },
+    CostUpdated { agent_id: AgentId, new_cost: Amount },
+    
+    // === Ownership Responses ===
+    /// Transfer proposed
+    OwnershipTransferProposed { agent_id: AgentId, new_owner: AccountOwner },
+    /// Pending transfer cancelled
+    OwnershipTransferCancelled { agent_id: AgentId },
+    /// Agent moved to a new owner (after acceptance or key rotation)
+    OwnershipTransferred { agent_id: AgentId, old_owner: AccountOwner, new_owner: AccountOwner },
+    /// Recovery key updated
+    RecoveryKeySet { agent_id: AgentId },
+    /// Operator key delegated
+    DelegationGranted { agent_id: AgentId, operator: AccountOwner, expires_at: Option<Timestamp> },
+    /// Operator key revoked
+    DelegationRevoked { agent_id: AgentId, operator: AccountOwner },
+    
+    // === Lifecycle Responses ===
+    /// Agent suspended, reinstated or banned
+    StatusChanged { agent_id: AgentId, status: AgentStatus },
+    /// Agent retired and its stake (plus its deposit, once the lock period has passed)
+    /// returned to the owner
+    AgentRetired { agent_id: AgentId, returned_stake: Amount, refunded_deposit: Amount },
+    /// Registration fee and deposit changed
+    RegistrationFeesSet { fee: Amount, deposit: Amount },
     
     /// Error occurred
     Error(String),
@@ -370,34 +2148,108 @@ pub enum Response {
 /// Messages sent between chains
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Message {
-    /// Activity log from an agent's chain
+    /// Activity log from an agent's chain. Activity messages the registry cannot
+    /// record are rejected, so tracked ones bounce back to the agent chain.
     ActivityLog {
-        agent_id: AccountOwner,
+        agent_id: AgentId,
         task_hash: [u8; 32],
+        /// Capability the task exercised; must be declared in the agent's manifest
+        capability: String,
         success: bool,
+        /// Account that received the work; when set, the outcome only counts
+        /// once that account attests it
+        counterparty: Option<AccountOwner>,
+        timestamp: Timestamp,
+        /// Optional timing, cost and failure details
+        details: TaskDetails,
+    },
+    /// Several activity logs from an agent's chain, recorded all-or-nothing
+    ActivityBatch {
+        agent_id: AgentId,
+        tasks: Vec<TaskReport>,
+    },
+    /// Merkle root and counts for a batch of an agent chain's task log, plus the
+    /// tasks that await counterparty attestation
+    TaskRoot {
+        agent_id: AgentId,
+        anchor: TaskAnchor,
+        attested_tasks: Vec<TaskReport>,
+    },
+    /// Audit request from an agent's chain; must be signed by the agent's owner
+    AuditRequest {
+        agent_id: AgentId,
+        /// Sequence number of the request on the agent's chain
+        request_id: u64,
         timestamp: Timestamp,
     },
-    /// Proof of audit from an auditor
+    /// Proof of audit from an auditor's chain; must be signed by `auditor`,
+    /// which must be a registered auditor. `timestamp` is when it was sent; the
+    /// registry dates the audit by when it processes the message
     ProofOfAudit {
-        agent_id: AccountOwner,
+        agent_id: AgentId,
         auditor: AccountOwner,
         passed: bool,
         timestamp: Timestamp,
     },
     /// Score query response (for oracle bridge)
     ScoreResponse {
-        agent_id: AccountOwner,
+        agent_id: AgentId,
         score: u16,
-        tier: ReputationTier,
+        tier: String,
         timestamp: Timestamp,
     },
-    /// Code update notification
+    /// Code update notification sent to subscribers and watcher chains
     CodeUpdated {
-        agent_id: AccountOwner,
+        agent_id: AgentId,
+        old_code_hash: [u8; 32],
         new_code_hash: [u8; 32],
         new_version: String,
+        /// Code hashes pinned by subscribers on the recipient chain that the
+        /// agent no longer matches
+        broken_pins: Vec<[u8; 32]>,
         timestamp: Timestamp,
     },
+    /// Change to an agent's standing, sent to the chain it reports activity from;
+    /// only kept there when it comes from the registry's own chain
+    AgentUpdate(AgentUpdate),
+}
+
+impl Message {
+    /// Name of the message type
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Message::ActivityLog { .. } => "ActivityLog",
+            Message::ActivityBatch { .. } => "ActivityBatch",
+            Message::TaskRoot { .. } => "TaskRoot",
+            Message::AuditRequest { .. } => "AuditRequest",
+            Message::ProofOfAudit { .. } => "ProofOfAudit",
+            Message::ScoreResponse { .. } => "ScoreResponse",
+            Message::CodeUpdated { .. } => "CodeUpdated",
+            Message::AgentUpdate(_) => "AgentUpdate",
+        }
+    }
+
+    /// Hashes of the tasks the message carries in full
+    pub fn task_hashes(&self) -> Vec<[u8; 32]> {
+        match self {
+            Message::ActivityLog { task_hash, .. } => vec![*task_hash],
+            Message::ActivityBatch { tasks, .. } => tasks.iter().map(|task| task.task_hash).collect(),
+            Message::TaskRoot { attested_tasks, .. } => {
+                attested_tasks.iter().map(|task| task.task_hash).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Number of tasks the message reports (all tasks under an anchored root)
+    pub fn task_count(&self) -> u64 {
+        match self {
+            Message::ActivityLog { .. } => 1,
+            Message::ActivityBatch { tasks, .. } => tasks.len() as u64,
+            Message::TaskRoot { anchor, .. } => anchor.task_count,
+            _ => 0,
+        }
+    }
 }
 
 // ============================================================================
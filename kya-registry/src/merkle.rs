@@ -0,0 +1,157 @@
+//! Merkle trees over anchored task log batches
+//!
+//! Agent chains commit to each batch of their task log with the root of a
+//! binary Merkle tree. The agent client builds roots and proofs and the registry
+//! checks them, both with this module. Leaves and inner nodes are SHA-256 hashes
+//! with distinct prefixes, and a node without a sibling moves up a level
+//! unchanged. A proof lists the sibling hashes from the leaf up; which side each
+//! sibling sits on follows from the leaf's index and the number of leaves, so a
+//! proof only checks out for the position it was built for.
+
+use linera_sdk::linera_base_types::{AccountOwner, Timestamp};
+use sha2::{Digest, Sha256};
+
+use crate::{FailureReason, TaskDetails};
+
+/// Hash of a task log entry's committed fields: task hash, success flag,
+/// timestamp, capability, description, counterparty and details
+pub fn task_leaf_hash(
+    task_hash: &[u8; 32],
+    capability: &str,
+    success: bool,
+    timestamp: Timestamp,
+    description: &str,
+    counterparty: Option<AccountOwner>,
+    details: &TaskDetails,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0u8]);
+    hasher.update(task_hash);
+    hasher.update([u8::from(success)]);
+    hasher.update(timestamp.micros().to_be_bytes());
+    // Strings are length-prefixed so adjacent fields cannot be shifted into each other
+    for text in [capability, description] {
+        hasher.update((text.len() as u64).to_be_bytes());
+        hasher.update(text.as_bytes());
+    }
+    update_optional(&mut hasher, counterparty.map(owner_bytes));
+    update_optional(&mut hasher, details.started_at.map(|at| at.micros().to_be_bytes()));
+    update_optional(&mut hasher, details.ended_at.map(|at| at.micros().to_be_bytes()));
+    update_optional(&mut hasher, details.cost.map(u64::to_be_bytes));
+    update_optional(&mut hasher, details.input_hash);
+    update_optional(&mut hasher, details.output_hash);
+    update_optional(&mut hasher, details.failure_reason.map(|reason| [failure_reason_tag(reason)]));
+    hasher.finalize().into()
+}
+
+/// Hash of an inner node
+pub fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([1u8]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Root of the tree over `leaves`; all zeros for an empty tree
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    if level.is_empty() {
+        return [0; 32];
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Sibling hashes needed to rebuild the root from the leaf at `index`, bottom up
+pub fn merkle_proof(leaves: &[[u8; 32]], mut index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut siblings = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            siblings.push(*sibling);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(siblings)
+}
+
+/// Whether `siblings` lead from `leaf`, at `index` in a tree of `leaf_count`
+/// leaves, to `root`
+pub fn verify_proof(leaf: [u8; 32], index: u64, leaf_count: u64, siblings: &[[u8; 32]], root: [u8; 32]) -> bool {
+    if index >= leaf_count {
+        return false;
+    }
+    let (mut node, mut index, mut width) = (leaf, index, leaf_count);
+    let mut siblings = siblings.iter();
+    while width > 1 {
+        if index % 2 == 1 {
+            let Some(sibling) = siblings.next() else {
+                return false;
+            };
+            node = node_hash(sibling, &node);
+        } else if index + 1 < width {
+            let Some(sibling) = siblings.next() else {
+                return false;
+            };
+            node = node_hash(&node, sibling);
+        }
+        index /= 2;
+        width = width.div_ceil(2);
+    }
+    siblings.next().is_none() && node == root
+}
+
+fn update_optional<const N: usize>(hasher: &mut Sha256, value: Option<[u8; N]>) {
+    match value {
+        Some(bytes) => {
+            hasher.update([1u8]);
+            hasher.update(bytes);
+        }
+        None => hasher.update([0u8]),
+    }
+}
+
+/// A variant tag followed by the address, zero-padded to the longest variant
+fn owner_bytes(owner: AccountOwner) -> [u8; 33] {
+    let mut bytes = [0u8; 33];
+    match owner {
+        AccountOwner::Reserved(id) => bytes[1] = id,
+        AccountOwner::Address32(hash) => {
+            bytes[0] = 1;
+            bytes[1..].copy_from_slice(&<[u8; 32]>::from(hash));
+        }
+        AccountOwner::Address20(address) => {
+            bytes[0] = 2;
+            bytes[1..21].copy_from_slice(&address);
+        }
+    }
+    bytes
+}
+
+fn failure_reason_tag(reason: FailureReason) -> u8 {
+    match reason {
+        FailureReason::Timeout => 0,
+        FailureReason::Refusal => 1,
+        FailureReason::WrongOutput => 2,
+        FailureReason::ExternalDependency => 3,
+        FailureReason::Crash => 4,
+    }
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
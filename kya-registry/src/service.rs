@@ -5,28 +5,29 @@
 
 use std::sync::Arc;
 
-use async_graphql::{EmptySubscription, Object, Request, Response, Schema, Context};
-use kya_registry::{AgentBadge, KyaRegistryAbi};
+use async_graphql::{EmptySubscription, InputObject, Object, Request, Response, Schema};
+use kya_registry::{
+    merkle, parse_hash, AgentBadge, AgentEvent, AgentId, AgentUpdate, CapabilityScore, Delegation, FailureReason, PendingTask, Rating, SlashSplit, TaskAnchor, TaskAttestation, TaskWeights, TierDefinition, CodeUpdateNotice, CodeUpdateRecord, KyaRegistryAbi, Subscription, Operation, RegistryParameters,
+    TaskDetails, VerificationRecord,
+};
 use linera_sdk::{
-    linera_base_types::{AccountOwner, WithServiceAbi},
+    linera_base_types::{AccountOwner, Amount, Timestamp, WithServiceAbi},
     views::View,
     Service, ServiceRuntime,
 };
 
-mod state;
-use state::KyaRegistryState;
+use kya_registry::state::KyaRegistryState;
 
 linera_sdk::service!(KyaRegistryService);
 
 /// The KYA Registry service
 pub struct KyaRegistryService {
     state: Arc<KyaRegistryState>,
-    #[allow(dead_code)]
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
 impl Service for KyaRegistryService {
-    type Parameters = ();
+    type Parameters = RegistryParameters;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = KyaRegistryState::load(runtime.root_view_storage_context())
@@ -42,14 +43,22 @@ impl Service for KyaRegistryService {
         let total_registered = *self.state.total_registered.get();
         let total_logs_processed = *self.state.total_logs_processed.get();
         let state = self.state.clone();
+        let now = self.runtime.system_time();
+        let params = self.runtime.application_parameters();
         
         let schema = Schema::build(
             QueryRoot {
                 total_registered,
                 total_logs_processed,
                 state,
+                now,
+                tiers: params.tiers,
+                slash_split: params.slash_split,
+                task_weights: params.task_weights,
+            },
+            MutationRoot {
+                runtime: self.runtime.clone(),
             },
-            MutationRoot,
             EmptySubscription,
         )
         .finish();
@@ -70,6 +79,10 @@ struct QueryRoot {
     total_registered: u64,
     total_logs_processed: u64,
     state: Arc<KyaRegistryState>,
+    now: Timestamp,
+    tiers: Vec<TierDefinition>,
+    slash_split: SlashSplit,
+    task_weights: TaskWeights,
 }
 
 #[Object]
@@ -84,37 +97,351 @@ impl QueryRoot {
         self.total_logs_processed
     }
 
-    /// Get an agent's badge by their AccountOwner ID
+    /// The registry's tier table, lowest tier first
+    async fn tiers(&self) -> &Vec<TierDefinition> {
+        &self.tiers
+    }
+
+    /// Balance of the protocol treasury
+    async fn treasury_balance(&self) -> Amount {
+        *self.state.treasury.get()
+    }
+
+    /// Fee currently charged for a registration
+    async fn registration_fee(&self) -> Amount {
+        *self.state.registration_fee.get()
+    }
+
+    /// Refundable deposit currently required for a registration
+    async fn registration_deposit(&self) -> Amount {
+        *self.state.registration_deposit.get()
+    }
+
+    /// Total registration fees paid into the treasury
+    async fn total_fees_collected(&self) -> Amount {
+        *self.state.total_fees_collected.get()
+    }
+
+    /// Total deposits currently held for registered agents
+    async fn total_deposits_held(&self) -> Amount {
+        *self.state.total_deposits_held.get()
+    }
+
+    /// Total deposits returned to owners on retirement
+    async fn total_deposits_refunded(&self) -> Amount {
+        *self.state.total_deposits_refunded.get()
+    }
+
+    /// Total deposits forfeited to the treasury (bans and early retirements)
+    async fn total_deposits_forfeited(&self) -> Amount {
+        *self.state.total_deposits_forfeited.get()
+    }
+
+    /// How slashed stake is divided between the treasury, reporters and harmed subscribers
+    async fn slash_split(&self) -> SlashSplit {
+        self.slash_split
+    }
+
+    /// Score changes for attested and self-reported task outcomes
+    async fn task_weights(&self) -> TaskWeights {
+        self.task_weights
+    }
+
+    /// Slash proceeds an account can claim with `ClaimSlashProceeds`
+    async fn claimable(&self, owner: String) -> Amount {
+        let Ok(owner) = owner.parse::<AccountOwner>() else {
+            return Amount::ZERO;
+        };
+        self.state.get_claimable(&owner).await.unwrap_or_default()
+    }
+
+    /// List registered badges, paginated (default limit 100); suspended, banned
+    /// and retired agents are only listed with `includeInactive: true`
+    async fn agents(
+        &self,
+        offset: Option<u32>,
+        limit: Option<u32>,
+        include_inactive: Option<bool>,
+    ) -> Vec<AgentBadge> {
+        let offset = offset.unwrap_or(0) as usize;
+        let limit = limit.unwrap_or(100) as usize;
+        self.state
+            .list_badges(offset, limit, include_inactive.unwrap_or(false))
+            .await
+            .unwrap_or_default()
+    }
+
+    /// List the badges of every agent owned by an account
+    async fn agents_by_owner(&self, owner: String) -> Vec<AgentBadge> {
+        let Ok(owner) = owner.parse::<AccountOwner>() else {
+            return Vec::new();
+        };
+        let mut badges = Vec::new();
+        for agent_id in self.state.agents_of(&owner).await.unwrap_or_default() {
+            if let Ok(Some(badge)) = self.state.get_badge(&agent_id).await {
+                badges.push(badge);
+            }
+        }
+        badges
+    }
+
+    /// Get an agent's badge by its agent ID
     async fn get_badge(&self, agent_id: String) -> Option<AgentBadge> {
-        // Parse the hex string to AccountOwner
-        // AccountOwner is typically a 32-byte hex address
-        let bytes: Vec<u8> = match hex::decode(agent_id.trim_start_matches("0x")) {
-            Ok(b) => b,
-            Err(_) => return None,
+        let agent_id = parse_agent_id(&agent_id)?;
+        self.state.get_badge(&agent_id).await.ok().flatten()
+    }
+
+    /// Get an agent's sub-score for one capability (e.g. "trading"); `None` until
+    /// the agent logs a task in it
+    async fn capability_score(&self, agent_id: String, capability: String) -> Option<CapabilityScore> {
+        let agent_id = parse_agent_id(&agent_id)?;
+        let badge = self.state.get_badge(&agent_id).await.ok().flatten()?;
+        badge.capability_score(&capability).cloned()
+    }
+
+    /// Get the code hash verification attestations for an agent, oldest first, paginated (default limit 100)
+    async fn verifications(&self, agent_id: String, offset: Option<u32>, limit: Option<u32>) -> Vec<VerificationRecord> {
+        let Some(agent_id) = parse_agent_id(&agent_id) else {
+            return Vec::new();
         };
-        
-        if bytes.len() != 32 {
-            return None;
+        self.state
+            .get_verifications(&agent_id, u64::from(offset.unwrap_or(0)), u64::from(limit.unwrap_or(100)))
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Get the code update history (with manifest diffs) for an agent, oldest first, paginated (default limit 100)
+    async fn code_updates(&self, agent_id: String, offset: Option<u32>, limit: Option<u32>) -> Vec<CodeUpdateRecord> {
+        let Some(agent_id) = parse_agent_id(&agent_id) else {
+            return Vec::new();
+        };
+        self.state
+            .get_code_updates(&agent_id, u64::from(offset.unwrap_or(0)), u64::from(limit.unwrap_or(100)))
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Get an agent's lifecycle and ownership event history, oldest first, paginated (default limit 100)
+    async fn history(&self, agent_id: String, offset: Option<u32>, limit: Option<u32>) -> Vec<AgentEvent> {
+        let Some(agent_id) = parse_agent_id(&agent_id) else {
+            return Vec::new();
+        };
+        self.state
+            .get_history(&agent_id, u64::from(offset.unwrap_or(0)), u64::from(limit.unwrap_or(100)))
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Get the standing updates received on this chain for an agent, oldest first, paginated (default limit 100)
+    async fn agent_updates(&self, agent_id: String, offset: Option<u32>, limit: Option<u32>) -> Vec<AgentUpdate> {
+        let Some(agent_id) = parse_agent_id(&agent_id) else {
+            return Vec::new();
+        };
+        self.state
+            .get_agent_updates(&agent_id, u64::from(offset.unwrap_or(0)), u64::from(limit.unwrap_or(100)))
+            .await
+            .map(|(updates, _)| updates)
+            .unwrap_or_default()
+    }
+
+    /// Get the tasks an agent reported that still await their counterparty's attestation, paginated (default limit 100)
+    async fn pending_tasks(&self, agent_id: String, offset: Option<u32>, limit: Option<u32>) -> Vec<PendingTask> {
+        let Some(agent_id) = parse_agent_id(&agent_id) else {
+            return Vec::new();
+        };
+        self.state
+            .get_pending_tasks(&agent_id, u64::from(offset.unwrap_or(0)), u64::from(limit.unwrap_or(100)))
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Get the counterparty-attested task outcomes for an agent, oldest first, paginated (default limit 100)
+    async fn task_attestations(&self, agent_id: String, offset: Option<u32>, limit: Option<u32>) -> Vec<TaskAttestation> {
+        let Some(agent_id) = parse_agent_id(&agent_id) else {
+            return Vec::new();
+        };
+        self.state
+            .get_task_attestations(&agent_id, u64::from(offset.unwrap_or(0)), u64::from(limit.unwrap_or(100)))
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Get an agent's ratings and reviews, newest first, paginated (default limit 20)
+    async fn reviews(&self, agent_id: String, offset: Option<u32>, limit: Option<u32>) -> Vec<Rating> {
+        let Some(agent_id) = parse_agent_id(&agent_id) else {
+            return Vec::new();
+        };
+        self.state
+            .get_ratings(&agent_id, u64::from(offset.unwrap_or(0)), u64::from(limit.unwrap_or(20)))
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Get the task log roots an agent chain anchored, oldest first, paginated (default limit 100)
+    async fn task_anchors(&self, agent_id: String, offset: Option<u32>, limit: Option<u32>) -> Vec<TaskAnchor> {
+        let Some(agent_id) = parse_agent_id(&agent_id) else {
+            return Vec::new();
+        };
+        let Ok(Some(badge)) = self.state.get_badge(&agent_id).await else {
+            return Vec::new();
+        };
+        let start = u64::from(offset.unwrap_or(0));
+        let end = badge
+            .anchored_epochs
+            .min(start.saturating_add(u64::from(limit.unwrap_or(100))));
+        let mut anchors = Vec::new();
+        for epoch in start..end {
+            if let Ok(Some(anchor)) = self.state.get_task_anchor(&agent_id, epoch).await {
+                anchors.push(anchor);
+            }
         }
-        
-        let mut arr = [0u8; 32];
-        arr.copy_from_slice(&bytes);
-        
-        // Note: This is a simplified conversion. In production, you'd need
-        // proper AccountOwner construction from the address bytes.
-        // For now, we return None as a placeholder since AccountOwner
-        // construction requires the full Owner type.
-        None
+        anchors
+    }
+
+    /// Check an inclusion proof (from the agent chain's `taskProof` query) for a
+    /// task against the root the agent anchored for `epoch`
+    async fn verify_task_inclusion(
+        &self,
+        agent_id: String,
+        epoch: u64,
+        leaf_index: u64,
+        task: TaskLeafInput,
+        siblings_hex: Vec<String>,
+    ) -> async_graphql::Result<bool> {
+        let agent_id = parse_agent_id(&agent_id).ok_or("Invalid agent ID")?;
+        let anchor = self
+            .state
+            .get_task_anchor(&agent_id, epoch)
+            .await?
+            .ok_or("No task root anchored for this epoch")?;
+        let task_hash = parse_hash(&task.task_hash_hex).ok_or("Task hash must be 32 hex-encoded bytes")?;
+        let details = task.details.unwrap_or_default().into_details()?;
+        let leaf = merkle::task_leaf_hash(
+            &task_hash,
+            &task.capability,
+            task.success,
+            task.timestamp,
+            &task.description,
+            task.counterparty,
+            &details,
+        );
+        let siblings = siblings_hex
+            .iter()
+            .map(|sibling| parse_hash(sibling).ok_or("Sibling hashes must be 32 hex-encoded bytes"))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(merkle::verify_proof(leaf, leaf_index, anchor.task_count, &siblings, anchor.root))
     }
+
+    /// Get the subscriptions (active and expired) for an agent
+    async fn subscriptions(&self, agent_id: String) -> Vec<Subscription> {
+        let Some(agent_id) = parse_agent_id(&agent_id) else {
+            return Vec::new();
+        };
+        self.state.get_subscriptions(&agent_id).await.unwrap_or_default()
+    }
+
+    /// Get the operator keys currently delegated by an agent's owner (expired grants are omitted)
+    async fn delegations(&self, agent_id: String) -> Vec<Delegation> {
+        let Some(agent_id) = parse_agent_id(&agent_id) else {
+            return Vec::new();
+        };
+        let mut delegations = self.state.get_delegations(&agent_id).await.unwrap_or_default();
+        delegations.retain(|delegation| delegation.is_active(self.now));
+        delegations
+    }
+
+    /// Get the latest code update notification this chain received for an agent
+    async fn code_update_notice(&self, agent_id: String) -> Option<CodeUpdateNotice> {
+        let agent_id = parse_agent_id(&agent_id)?;
+        self.state.get_code_update_notice(&agent_id).await.ok().flatten()
+    }
+}
+
+/// Parse an agent ID from its 64-character hex form
+fn parse_agent_id(agent_id: &str) -> Option<AgentId> {
+    agent_id.parse().ok()
 }
 
-/// GraphQL mutation root (operations via GraphQL)
-struct MutationRoot;
+/// The committed fields of a task log entry
+#[derive(InputObject)]
+struct TaskLeafInput {
+    task_hash_hex: String,
+    capability: String,
+    success: bool,
+    timestamp: Timestamp,
+    description: String,
+    counterparty: Option<AccountOwner>,
+    details: Option<TaskDetailsInput>,
+}
+
+/// Timing, cost and failure details of a task log entry
+#[derive(InputObject, Default)]
+struct TaskDetailsInput {
+    started_at: Option<Timestamp>,
+    ended_at: Option<Timestamp>,
+    cost: Option<u64>,
+    input_hash_hex: Option<String>,
+    output_hash_hex: Option<String>,
+    failure_reason: Option<FailureReason>,
+}
+
+impl TaskDetailsInput {
+    fn into_details(self) -> async_graphql::Result<TaskDetails> {
+        let parse = |hash: Option<String>| {
+            hash.map(|hash| parse_hash(&hash).ok_or("Input and output hashes must be 32 hex-encoded bytes"))
+                .transpose()
+        };
+        Ok(TaskDetails {
+            started_at: self.started_at,
+            ended_at: self.ended_at,
+            cost: self.cost,
+            input_hash: parse(self.input_hash_hex)?,
+            output_hash: parse(self.output_hash_hex)?,
+            failure_reason: self.failure_reason,
+        })
+    }
+}
+
+/// GraphQL mutation root (schedules operations on the chain this service runs on)
+struct MutationRoot {
+    runtime: Arc<ServiceRuntime<KyaRegistryService>>,
+}
 
 #[Object]
 impl MutationRoot {
-    /// Placeholder - operations should be submitted via Linera CLI
-    async fn placeholder(&self) -> bool {
-        true
+    /// Attest to the hash of the package fetched from an agent's storage CID
+    async fn verify_code_hash(
+        &self,
+        agent_id: String,
+        storage_cid: String,
+        computed_hash: String,
+    ) -> async_graphql::Result<bool> {
+        let agent_id = parse_agent_id(&agent_id).ok_or("Invalid agent ID")?;
+        let computed_hash = parse_hash(&computed_hash).ok_or("Computed hash must be 32 hex-encoded bytes")?;
+
+        self.runtime.schedule_operation(&Operation::VerifyCodeHash {
+            agent_id,
+            storage_cid,
+            computed_hash,
+        });
+        Ok(true)
+    }
+
+    /// Confirm the outcome of a task that names this chain's signer as its counterparty
+    async fn attest_task(
+        &self,
+        agent_id: String,
+        task_hash: String,
+        success: bool,
+    ) -> async_graphql::Result<bool> {
+        let agent_id = parse_agent_id(&agent_id).ok_or("Invalid agent ID")?;
+        let task_hash = parse_hash(&task_hash).ok_or("Task hash must be 32 hex-encoded bytes")?;
+
+        self.runtime.schedule_operation(&Operation::AttestTask {
+            agent_id,
+            task_hash,
+            success,
+        });
+        Ok(true)
     }
 }
@@ -1,57 +1,278 @@
 //! KYA Registry State - Persistent storage using linera-views
 
-use kya_registry::{AgentBadge, AgentManifest, ReputationTier, StorageProvider};
-use linera_sdk::linera_base_types::{AccountOwner, Timestamp, Amount};
-use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext};
+use std::collections::BTreeMap;
+
+use crate::{
+    controls_agent, derive_agent_id, AgentBadge, AnchorOutcome, AgentEvent, AgentEventKind, AgentId, AgentUpdate, AgentManifest, AgentStatus, CodeUpdateNotice,
+    CodeUpdateRecord, Delegation, DelegationScope, PendingTask, Rating, RatingSource, RegistryParameters, SlashDistribution, StorageProvider, Subscription, TaskAnchor, TaskAttestation, TaskReport, VerificationRecord,
+};
+use linera_sdk::linera_base_types::{AccountOwner, ChainId, Timestamp, Amount};
+use linera_sdk::views::{
+    linera_views, CollectionView, MapView, RegisterView, RootView, SetView, ViewStorageContext,
+};
+use serde::{de::DeserializeOwned, Serialize};
 
 /// The root state of the KYA Registry application
 #[derive(RootView, async_graphql::SimpleObject)]
 #[view(context = ViewStorageContext)]
 pub struct KyaRegistryState {
     /// Map of agent IDs to their reputation badges
-    pub badges: MapView<AccountOwner, AgentBadge>,
+    pub badges: MapView<AgentId, AgentBadge>,
     /// Total number of registered agents
     pub total_registered: RegisterView<u64>,
     /// Total activity logs processed
     pub total_logs_processed: RegisterView<u64>,
     /// Total code updates across all agents
     pub total_code_updates: RegisterView<u64>,
+    /// Code hash verification attestations, keyed by agent and position
+    /// (`AgentBadge::verification_count` entries per agent)
+    #[graphql(skip)]
+    pub verifications: MapView<(AgentId, u64), VerificationRecord>,
+    /// Verifiers that reported a mismatch, keyed by agent and the code update they
+    /// checked (`AgentBadge::update_count`), so reverting to an earlier hash starts afresh
+    #[graphql(skip)]
+    pub mismatch_reporters: SetView<(AgentId, u32, AccountOwner)>,
+    /// Code update history with manifest diffs, keyed by agent and position
+    /// (`AgentBadge::update_count` entries per agent)
+    #[graphql(skip)]
+    pub code_updates: MapView<(AgentId, u64), CodeUpdateRecord>,
+    /// Subscriptions per agent (one entry per subscriber)
+    pub subscriptions: MapView<AgentId, Vec<Subscription>>,
+    /// Latest code update notification received on this chain, per agent
+    pub code_update_notices: MapView<AgentId, CodeUpdateNotice>,
+    /// Lifecycle and ownership events, keyed by agent and position
+    /// (`AgentBadge::event_count` entries per agent)
+    #[graphql(skip)]
+    pub history: MapView<(AgentId, u64), AgentEvent>,
+    /// IDs of the agents owned by each account, in registration/transfer order
+    pub owner_agents: MapView<AccountOwner, Vec<AgentId>>,
+    /// Number of agents ever registered by each account (seeds new agent IDs)
+    pub owner_nonces: MapView<AccountOwner, u64>,
+    /// Operator keys delegated by each agent's owner (one entry per operator)
+    pub delegations: MapView<AgentId, Vec<Delegation>>,
+    /// Reported tasks waiting for their counterparty's attestation, per agent and task hash
+    #[graphql(skip)]
+    pub pending_tasks: CollectionView<AgentId, MapView<[u8; 32], PendingTask>>,
+    /// Tasks already recorded, keyed by agent and task hash, so resent reports are ignored
+    #[graphql(skip)]
+    pub processed_tasks: SetView<(AgentId, [u8; 32])>,
+    /// Anchored task log roots, keyed by agent and epoch
+    #[graphql(skip)]
+    pub task_anchors: MapView<(AgentId, u64), TaskAnchor>,
+    /// Chain each agent last reported activity from, where its notifications go
+    pub home_chains: MapView<AgentId, ChainId>,
+    /// Standing updates received on this chain (an agent's home chain), keyed by
+    /// agent and position (`agent_update_counts` entries per agent)
+    #[graphql(skip)]
+    pub agent_updates: MapView<(AgentId, u64), AgentUpdate>,
+    /// Number of standing updates received on this chain per agent
+    pub agent_update_counts: MapView<AgentId, u64>,
+    /// Counterparty-attested task outcomes, keyed by agent and position
+    /// (`AgentBadge::attested_task_count` entries per agent)
+    #[graphql(skip)]
+    pub task_attestations: MapView<(AgentId, u64), TaskAttestation>,
+    /// Position of each attested task in `task_attestations`, keyed by agent and task hash
+    #[graphql(skip)]
+    pub attestation_positions: MapView<(AgentId, [u8; 32]), u64>,
+    /// Accounts that have attested at least one of each agent's tasks
+    #[graphql(skip)]
+    pub attesting_counterparties: SetView<(AgentId, AccountOwner)>,
+    /// Ratings, keyed by agent and position (`AgentBadge::rating_count` entries per agent)
+    #[graphql(skip)]
+    pub ratings: MapView<(AgentId, u64), Rating>,
+    /// Protocol treasury funded by slashing
+    pub treasury: RegisterView<Amount>,
+    /// Unclaimed reporter rewards and compensation per account
+    pub slash_claims: MapView<AccountOwner, Amount>,
+    /// Fee currently charged for a registration (set by governance)
+    pub registration_fee: RegisterView<Amount>,
+    /// Refundable deposit currently required for a registration (set by governance)
+    pub registration_deposit: RegisterView<Amount>,
+    /// Total registration fees paid into the treasury
+    pub total_fees_collected: RegisterView<Amount>,
+    /// Total deposits currently held for registered agents
+    pub total_deposits_held: RegisterView<Amount>,
+    /// Total deposits returned to owners on retirement
+    pub total_deposits_refunded: RegisterView<Amount>,
+    /// Total deposits forfeited to the treasury
+    pub total_deposits_forfeited: RegisterView<Amount>,
 }
 
 impl KyaRegistryState {
-    /// Register a new agent badge
-    pub async fn register_agent(&mut self, badge: AgentBadge) -> Result<String, String> {
+    /// Allocate the ID for the next agent registered by `owner`
+    pub async fn next_agent_id(&mut self, owner: &AccountOwner) -> Result<AgentId, String> {
+        let nonce = self
+            .owner_nonces
+            .get(owner)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default();
+        self.owner_nonces
+            .insert(owner, nonce + 1)
+            .map_err(|e| e.to_string())?;
+        Ok(derive_agent_id(*owner, nonce))
+    }
+
+    /// Register a new agent badge, collecting the fee and deposit recorded on it
+    pub async fn register_agent(
+        &mut self,
+        mut badge: AgentBadge,
+        params: &RegistryParameters,
+    ) -> Result<String, String> {
+        badge.update_tier(params, badge.registered_at);
+        let agent_id = badge.agent_id;
         let owner = badge.owner;
         let storage_cid = badge.storage_cid.clone();
         
         // Check if already registered
-        if self.badges.contains_key(&owner).await.map_err(|e| e.to_string())? {
+        if self.badges.contains_key(&agent_id).await.map_err(|e| e.to_string())? {
             return Err("Agent already registered".to_string());
         }
         
-        self.badges.insert(&owner, badge).map_err(|e| e.to_string())?;
+        let timestamp = badge.registered_at;
+        let fee = badge.registration_fee_paid;
+        let deposit = badge.deposit;
+        self.badges.insert(&agent_id, badge).map_err(|e| e.to_string())?;
+        self.add_to_owner(&owner, agent_id).await?;
+        self.record_event(
+            &agent_id,
+            AgentEvent {
+                kind: AgentEventKind::Registered,
+                actor: owner,
+                details: String::new(),
+                timestamp,
+            },
+        )
+        .await?;
         
         let current = self.total_registered.get();
         self.total_registered.set(current + 1);
+
+        let treasury = self.treasury.get().saturating_add(fee);
+        self.treasury.set(treasury);
+        let collected = self.total_fees_collected.get().saturating_add(fee);
+        self.total_fees_collected.set(collected);
+        let held = self.total_deposits_held.get().saturating_add(deposit);
+        self.total_deposits_held.set(held);
         
         Ok(storage_cid)
     }
 
     /// Get an agent's badge
-    pub async fn get_badge(&self, agent_id: &AccountOwner) -> Result<Option<AgentBadge>, String> {
+    pub async fn get_badge(&self, agent_id: &AgentId) -> Result<Option<AgentBadge>, String> {
         self.badges.get(agent_id).await.map_err(|e| e.to_string())
     }
 
-    /// Update an agent's code (new version)
+    /// Get an agent's badge, failing unless `signer` owns it
+    pub async fn owned_badge(&self, agent_id: &AgentId, signer: &AccountOwner) -> Result<AgentBadge, String> {
+        let badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if badge.owner != *signer {
+            return Err("Signer does not own this agent".to_string());
+        }
+        Ok(badge)
+    }
+
+    /// Get an agent's badge, failing unless `signer` is its owner or an operator
+    /// currently delegated `scope`
+    pub async fn authorized_badge(
+        &self,
+        agent_id: &AgentId,
+        signer: &AccountOwner,
+        scope: DelegationScope,
+        now: Timestamp,
+    ) -> Result<AgentBadge, String> {
+        let badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if !badge.is_active() {
+            return Err(format!("Agent is {:?}", badge.status));
+        }
+        if badge.owner == *signer {
+            return Ok(badge);
+        }
+        let delegated = self
+            .get_delegations(agent_id)
+            .await?
+            .iter()
+            .any(|delegation| delegation.operator == *signer && delegation.allows(scope, now));
+        if !delegated {
+            return Err(format!("Signer is neither the owner nor delegated {scope:?}"));
+        }
+        Ok(badge)
+    }
+
+    /// Get the IDs of the agents owned by an account
+    pub async fn agents_of(&self, owner: &AccountOwner) -> Result<Vec<AgentId>, String> {
+        Ok(self
+            .owner_agents
+            .get(owner)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default())
+    }
+
+    /// Append an event to an agent's history
+    pub async fn record_event(&mut self, agent_id: &AgentId, event: AgentEvent) -> Result<(), String> {
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        let position = u64::from(badge.event_count);
+        badge.event_count += 1;
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+        self.history
+            .insert(&(*agent_id, position), event)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Get a page of an agent's event history, oldest first
+    pub async fn get_history(
+        &self,
+        agent_id: &AgentId,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<AgentEvent>, String> {
+        let count = self.get_badge(agent_id).await?.map_or(0, |badge| u64::from(badge.event_count));
+        read_log(&self.history, agent_id, offset, limit, count).await
+    }
+
+    /// List badges in index order, skipping `offset` and returning at most `limit`;
+    /// agents that are not active are left out unless `include_inactive` is set
+    pub async fn list_badges(
+        &self,
+        offset: usize,
+        limit: usize,
+        include_inactive: bool,
+    ) -> Result<Vec<AgentBadge>, String> {
+        let agent_ids = self.badges.indices().await.map_err(|e| e.to_string())?;
+        let mut badges = Vec::new();
+        let mut skipped = 0;
+        for agent_id in agent_ids {
+            if badges.len() >= limit {
+                break;
+            }
+            let Some(badge) = self.badges.get(&agent_id).await.map_err(|e| e.to_string())? else {
+                continue;
+            };
+            if !include_inactive && !badge.is_active() {
+                continue;
+            }
+            if skipped < offset {
+                skipped += 1;
+                continue;
+            }
+            badges.push(badge);
+        }
+        Ok(badges)
+    }
+
+    /// Update an agent's code (new version) and record the manifest diff
+    #[allow(clippy::too_many_arguments)]
     pub async fn update_agent_code(
         &mut self,
-        agent_id: &AccountOwner,
+        agent_id: &AgentId,
         code_hash: [u8; 32],
         storage_provider: StorageProvider,
         storage_cid: String,
         manifest: AgentManifest,
         timestamp: Timestamp,
-    ) -> Result<(String, u32), String> {
+        params: &RegistryParameters,
+    ) -> Result<(CodeUpdateRecord, bool), String> {
         let mut badge = self
             .badges
             .get(agent_id)
@@ -60,41 +281,106 @@ impl KyaRegistryState {
             .ok_or("Agent not found")?;
         
         // Update the code
-        badge.update_code(code_hash, storage_provider, storage_cid, manifest.clone(), timestamp);
-        
-        let version = badge.manifest.version.clone();
-        let update_count = badge.update_count;
+        let record = badge.update_code(
+            code_hash,
+            storage_provider,
+            storage_cid,
+            manifest,
+            timestamp,
+            params,
+        );
+        let pending_reaudit = badge.pending_reaudit;
         
         self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+        self.code_updates
+            .insert(&(*agent_id, u64::from(record.update_number) - 1), record.clone())
+            .map_err(|e| e.to_string())?;
         
         let current = self.total_code_updates.get();
         self.total_code_updates.set(current + 1);
         
-        Ok((version, update_count))
+        Ok((record, pending_reaudit))
     }
 
-    /// Verify that a code hash matches the stored value
-    pub async fn verify_code_hash(
+    /// Get a page of the code update history for an agent, oldest first
+    pub async fn get_code_updates(
         &self,
-        agent_id: &AccountOwner,
-        expected_hash: [u8; 32],
-    ) -> Result<bool, String> {
-        let badge = self
+        agent_id: &AgentId,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<CodeUpdateRecord>, String> {
+        let count = self.get_badge(agent_id).await?.map_or(0, |badge| u64::from(badge.update_count));
+        read_log(&self.code_updates, agent_id, offset, limit, count).await
+    }
+
+    /// Record a code hash verification attestation and apply any mismatch penalty
+    pub async fn record_verification(
+        &mut self,
+        agent_id: &AgentId,
+        record: VerificationRecord,
+        params: &RegistryParameters,
+    ) -> Result<(u32, u16), String> {
+        let mut badge = self
             .badges
             .get(agent_id)
             .await
             .map_err(|e| e.to_string())?
             .ok_or("Agent not found")?;
-        
-        Ok(badge.code_hash == expected_hash)
+
+        if !params.is_verifier(&record.verifier) {
+            return Err("Only registered verifiers can attest code hashes".to_string());
+        }
+        if badge.owner == record.verifier {
+            return Err("Agents cannot verify their own code".to_string());
+        }
+        if badge.storage_cid != record.storage_cid {
+            return Err("Attestation is for a stale storage CID".to_string());
+        }
+
+        let reporter = (*agent_id, badge.update_count, record.verifier);
+        let new_reporter = !record.matches
+            && !self
+                .mismatch_reporters
+                .contains(&reporter)
+                .await
+                .map_err(|e| e.to_string())?;
+        if new_reporter {
+            self.mismatch_reporters.insert(&reporter).map_err(|e| e.to_string())?;
+        }
+
+        let position = u64::from(badge.verification_count);
+        let penalty = badge.record_verification(&record, new_reporter, params);
+        let mismatches = badge.hash_mismatches;
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+        self.verifications
+            .insert(&(*agent_id, position), record)
+            .map_err(|e| e.to_string())?;
+
+        Ok((mismatches, penalty))
+    }
+
+    /// Get a page of the verification attestations for an agent, oldest first
+    pub async fn get_verifications(
+        &self,
+        agent_id: &AgentId,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<VerificationRecord>, String> {
+        let count = self
+            .get_badge(agent_id)
+            .await?
+            .map_or(0, |badge| u64::from(badge.verification_count));
+        read_log(&self.verifications, agent_id, offset, limit, count).await
     }
 
     /// Update an agent's reputation score
     pub async fn update_score(
         &mut self,
-        agent_id: &AccountOwner,
+        agent_id: &AgentId,
         delta: i16,
-    ) -> Result<(u16, ReputationTier), String> {
+        params: &RegistryParameters,
+        now: Timestamp,
+    ) -> Result<(u16, String), String> {
         let mut badge = self
             .badges
             .get(agent_id)
@@ -110,48 +396,456 @@ impl KyaRegistryState {
         };
         
         badge.reputation_score = new_score;
-        badge.update_tier();
+        badge.update_tier(params, now);
         
-        let new_tier = badge.tier;
+        let new_tier = badge.tier.clone();
         self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
         
         Ok((new_score, new_tier))
     }
 
-    /// Record a task result for an agent
-    pub async fn record_task(
+    /// Apply an audit result to an agent
+    pub async fn apply_audit(
         &mut self,
-        agent_id: &AccountOwner,
-        success: bool,
-    ) -> Result<(), String> {
+        agent_id: &AgentId,
+        passed: bool,
+        timestamp: Timestamp,
+        params: &RegistryParameters,
+    ) -> Result<(u16, String), String> {
         let mut badge = self
             .badges
             .get(agent_id)
             .await
             .map_err(|e| e.to_string())?
             .ok_or("Agent not found")?;
-        
-        if success {
-            badge.tasks_completed += 1;
-            // Small reputation boost for successful tasks
-            badge.reputation_score = badge.reputation_score.saturating_add(1).min(1000);
-        } else {
-            badge.tasks_failed += 1;
-            // Small reputation penalty for failed tasks
-            badge.reputation_score = badge.reputation_score.saturating_sub(2);
+
+        badge.apply_audit(passed, timestamp, params);
+
+        let result = (badge.reputation_score, badge.tier.clone());
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+
+        Ok(result)
+    }
+
+    /// Record a batch of tasks reported by the agent, returning how many were new.
+    /// Tasks naming a counterparty are held until it attests them; the rest count
+    /// immediately at the unattested weight. Tasks already processed are skipped,
+    /// and if any new task is invalid the whole batch is rejected.
+    pub async fn record_tasks(
+        &mut self,
+        agent_id: &AgentId,
+        reports: Vec<TaskReport>,
+        params: &RegistryParameters,
+        now: Timestamp,
+    ) -> Result<u64, String> {
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        let mut pending = Vec::new();
+        let mut accepted: Vec<[u8; 32]> = Vec::new();
+
+        for report in reports {
+            let key = (*agent_id, report.task_hash);
+            if accepted.contains(&report.task_hash)
+                || self.processed_tasks.contains(&key).await.map_err(|e| e.to_string())?
+            {
+                continue;
+            }
+            if !badge.manifest.capabilities.contains(&report.capability) {
+                return Err(format!("Capability {} is not declared in the manifest", report.capability));
+            }
+            report.details.validate(report.success)?;
+            accepted.push(report.task_hash);
+            // Timing and failure details are the agent's own telemetry, so they
+            // count whether or not the outcome awaits attestation
+            badge.performance.record(report.success, &report.details);
+
+            match report.counterparty {
+                Some(counterparty) => {
+                    if counterparty == badge.owner {
+                        return Err("An agent's owner cannot be its counterparty".to_string());
+                    }
+                    pending.push(PendingTask {
+                        task_hash: report.task_hash,
+                        counterparty,
+                        capability: report.capability,
+                        claimed_success: report.success,
+                        reported_at: report.timestamp,
+                    });
+                }
+                None => badge.record_task_outcome(&report.capability, report.success, false, params, now),
+            }
+        }
+
+        // Nothing is written until every task in the batch has been checked
+        for task_hash in &accepted {
+            self.processed_tasks
+                .insert(&(*agent_id, *task_hash))
+                .map_err(|e| e.to_string())?;
+        }
+        if !pending.is_empty() {
+            let agent_pending = self
+                .pending_tasks
+                .load_entry_mut(agent_id)
+                .await
+                .map_err(|e| e.to_string())?;
+            for task in pending {
+                let task_hash = task.task_hash;
+                agent_pending
+                    .insert(&task_hash, task)
+                    .map_err(|e| e.to_string())?;
+            }
         }
-        
-        badge.update_tier();
         self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
-        
+
+        let count = accepted.len() as u64;
         let current = self.total_logs_processed.get();
-        self.total_logs_processed.set(current + 1);
-        
-        Ok(())
+        self.total_logs_processed.set(current + count);
+
+        Ok(count)
+    }
+
+    /// Store an anchored task log root and apply its counts. Roots must arrive
+    /// in epoch order and out-of-order ones are an error, so they bounce and are
+    /// resent; resent ones are ignored. An invalid root in order is rejected:
+    /// its epoch is skipped and the rejection logged, so it cannot hold back
+    /// later roots.
+    pub async fn record_anchor(
+        &mut self,
+        agent_id: &AgentId,
+        signer: &AccountOwner,
+        anchor: TaskAnchor,
+        attested_tasks: Vec<TaskReport>,
+        params: &RegistryParameters,
+        now: Timestamp,
+    ) -> Result<AnchorOutcome, String> {
+        let badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if anchor.epoch < badge.anchored_epochs {
+            return Ok(AnchorOutcome::Recorded { tasks: 0 });
+        }
+        if anchor.epoch > badge.anchored_epochs {
+            return Err(format!("Expected task root for epoch {}", badge.anchored_epochs));
+        }
+        let tallied = match anchor.check(&badge.manifest.capabilities, attested_tasks.len()) {
+            Ok(tallied) => tallied,
+            Err(reason) => return self.reject_anchor(agent_id, signer, anchor.epoch, reason, now).await,
+        };
+
+        // Checks the attested tasks and writes nothing if any is invalid
+        if let Err(reason) = self.record_tasks(agent_id, attested_tasks, params, now).await {
+            return self.reject_anchor(agent_id, signer, anchor.epoch, reason, now).await;
+        }
+
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        for tally in &anchor.tallies {
+            badge.record_task_tally(tally, params, now);
+        }
+        badge.performance.merge(&anchor.performance);
+        badge.anchored_epochs += 1;
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+
+        let current = self.total_logs_processed.get();
+        self.total_logs_processed.set(current + tallied);
+
+        self.task_anchors
+            .insert(&(*agent_id, anchor.epoch), anchor)
+            .map_err(|e| e.to_string())?;
+        Ok(AnchorOutcome::Recorded { tasks: tallied })
+    }
+
+    /// Skip the agent's next epoch because its root was invalid, logging why
+    async fn reject_anchor(
+        &mut self,
+        agent_id: &AgentId,
+        signer: &AccountOwner,
+        epoch: u64,
+        reason: String,
+        now: Timestamp,
+    ) -> Result<AnchorOutcome, String> {
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        badge.anchored_epochs += 1;
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind: AgentEventKind::TaskRootRejected,
+                actor: *signer,
+                details: format!("Epoch {}: {}", epoch, reason),
+                timestamp: now,
+            },
+        )
+        .await?;
+        Ok(AnchorOutcome::Rejected { reason })
+    }
+
+    /// Record the chain an agent reports activity from
+    pub fn set_home_chain(&mut self, agent_id: &AgentId, chain_id: ChainId) -> Result<(), String> {
+        self.home_chains.insert(agent_id, chain_id).map_err(|e| e.to_string())
+    }
+
+    /// Get the chain an agent's notifications are sent to, if it has reported activity
+    pub async fn get_home_chain(&self, agent_id: &AgentId) -> Result<Option<ChainId>, String> {
+        self.home_chains.get(agent_id).await.map_err(|e| e.to_string())
+    }
+
+    /// Record an audit request from the agent's owner in its history
+    pub async fn request_audit(
+        &mut self,
+        agent_id: &AgentId,
+        signer: &AccountOwner,
+        timestamp: Timestamp,
+    ) -> Result<(), String> {
+        self.owned_badge(agent_id, signer).await?;
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind: AgentEventKind::AuditRequested,
+                actor: *signer,
+                details: String::new(),
+                timestamp,
+            },
+        )
+        .await
+    }
+
+    /// Store a standing update received from the registry chain
+    pub async fn record_agent_update(&mut self, update: AgentUpdate) -> Result<(), String> {
+        let agent_id = update.agent_id;
+        let position = self
+            .agent_update_counts
+            .get(&agent_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default();
+        self.agent_update_counts
+            .insert(&agent_id, position + 1)
+            .map_err(|e| e.to_string())?;
+        self.agent_updates
+            .insert(&(agent_id, position), update)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Get a page of the standing updates received for an agent, oldest first,
+    /// and the offset to continue from
+    pub async fn get_agent_updates(
+        &self,
+        agent_id: &AgentId,
+        offset: u64,
+        limit: u64,
+    ) -> Result<(Vec<AgentUpdate>, u64), String> {
+        let count = self
+            .agent_update_counts
+            .get(agent_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default();
+        let updates = read_log(&self.agent_updates, agent_id, offset, limit, count).await?;
+        let next = offset.max(count.min(offset.saturating_add(limit)));
+        Ok((updates, next))
+    }
+
+    /// Get the task log root an agent anchored for `epoch`
+    pub async fn get_task_anchor(&self, agent_id: &AgentId, epoch: u64) -> Result<Option<TaskAnchor>, String> {
+        self.task_anchors
+            .get(&(*agent_id, epoch))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Apply a counterparty's attestation of a pending task, returning the new score
+    pub async fn attest_task(
+        &mut self,
+        agent_id: &AgentId,
+        task_hash: [u8; 32],
+        counterparty: AccountOwner,
+        success: bool,
+        params: &RegistryParameters,
+        now: Timestamp,
+    ) -> Result<u16, String> {
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if !badge.is_active() {
+            return Err(format!("Agent is {:?}", badge.status));
+        }
+        // Keys the owner controls are not a second party
+        let delegations = self.get_delegations(agent_id).await?;
+        if controls_agent(&badge.owner, &delegations, &counterparty, now) {
+            return Err("The agent's owner and operators cannot attest its tasks".to_string());
+        }
+
+        let agent_pending = self
+            .pending_tasks
+            .load_entry_mut(agent_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        let task = agent_pending
+            .get(&task_hash)
+            .await
+            .map_err(|e| e.to_string())?
+            .filter(|task| task.counterparty == counterparty)
+            .ok_or("No pending task names this counterparty")?;
+        agent_pending.remove(&task_hash).map_err(|e| e.to_string())?;
+
+        let counterparty_key = (*agent_id, counterparty);
+        if !self
+            .attesting_counterparties
+            .contains(&counterparty_key)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            self.attesting_counterparties
+                .insert(&counterparty_key)
+                .map_err(|e| e.to_string())?;
+            badge.unique_counterparties = badge.unique_counterparties.saturating_add(1);
+        }
+        let position = badge.attested_task_count();
+        badge.record_task_outcome(&task.capability, success, true, params, now);
+        self.task_attestations
+            .insert(
+                &(*agent_id, position),
+                TaskAttestation {
+                    task_hash,
+                    counterparty,
+                    capability: task.capability,
+                    claimed_success: task.claimed_success,
+                    success,
+                    reported_at: task.reported_at,
+                    attested_at: now,
+                    rated: false,
+                },
+            )
+            .map_err(|e| e.to_string())?;
+        self.attestation_positions
+            .insert(&(*agent_id, task_hash), position)
+            .map_err(|e| e.to_string())?;
+
+        let new_score = badge.reputation_score;
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+        Ok(new_score)
+    }
+
+    /// Get a page of the tasks awaiting counterparty attestation for an agent,
+    /// in task hash order
+    pub async fn get_pending_tasks(
+        &self,
+        agent_id: &AgentId,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<PendingTask>, String> {
+        let Some(agent_pending) = self
+            .pending_tasks
+            .try_load_entry(agent_id)
+            .await
+            .map_err(|e| e.to_string())?
+        else {
+            return Ok(Vec::new());
+        };
+        let task_hashes = agent_pending.indices().await.map_err(|e| e.to_string())?;
+        let mut tasks = Vec::new();
+        for task_hash in task_hashes.iter().skip(offset as usize).take(limit as usize) {
+            if let Some(task) = agent_pending.get(task_hash).await.map_err(|e| e.to_string())? {
+                tasks.push(task);
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Get a page of the counterparty-attested task outcomes for an agent, oldest first
+    pub async fn get_task_attestations(
+        &self,
+        agent_id: &AgentId,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<TaskAttestation>, String> {
+        let count = self
+            .get_badge(agent_id)
+            .await?
+            .map_or(0, |badge| badge.attested_task_count());
+        read_log(&self.task_attestations, agent_id, offset, limit, count).await
+    }
+
+    /// Record a rating, returning the agent's updated customer rating and score.
+    /// Each subscription payment and each attested task allows one rating.
+    pub async fn rate_agent(
+        &mut self,
+        agent_id: &AgentId,
+        rating: Rating,
+        params: &RegistryParameters,
+    ) -> Result<(Option<u16>, u16), String> {
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if !badge.is_active() {
+            return Err(format!("Agent is {:?}", badge.status));
+        }
+        let delegations = self.get_delegations(agent_id).await?;
+        if controls_agent(&badge.owner, &delegations, &rating.rater, rating.timestamp) {
+            return Err("The agent's owner and operators cannot rate it".to_string());
+        }
+        badge.record_rating(rating.stars, params, rating.timestamp)?;
+
+        match rating.source {
+            RatingSource::Subscription => {
+                let mut subscriptions = self.get_subscriptions(agent_id).await?;
+                let subscription = subscriptions
+                    .iter_mut()
+                    .find(|existing| existing.subscriber == rating.rater)
+                    .ok_or("Signer never subscribed to this agent")?;
+                subscription.check_can_rate(rating.timestamp)?;
+                subscription.rated = true;
+                self.subscriptions
+                    .insert(agent_id, subscriptions)
+                    .map_err(|e| e.to_string())?;
+            }
+            RatingSource::Task => {
+                let task_hash = rating.task_hash.ok_or("Task ratings must name the task")?;
+                let position = self
+                    .attestation_positions
+                    .get(&(*agent_id, task_hash))
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .ok_or("Signer did not attest this task")?;
+                let key = (*agent_id, position);
+                let mut attestation = self
+                    .task_attestations
+                    .get(&key)
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .ok_or("Signer did not attest this task")?;
+                if attestation.counterparty != rating.rater {
+                    return Err("Signer did not attest this task".to_string());
+                }
+                if attestation.rated {
+                    return Err("Already rated this task".to_string());
+                }
+                attestation.rated = true;
+                self.task_attestations
+                    .insert(&key, attestation)
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+
+        let result = (badge.customer_rating, badge.reputation_score);
+        let position = u64::from(badge.rating_count) - 1;
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+        self.ratings
+            .insert(&(*agent_id, position), rating)
+            .map_err(|e| e.to_string())?;
+        Ok(result)
+    }
+
+    /// Get a page of an agent's ratings, newest first
+    pub async fn get_ratings(&self, agent_id: &AgentId, offset: u64, limit: u64) -> Result<Vec<Rating>, String> {
+        let count = self.get_badge(agent_id).await?.map_or(0, |badge| u64::from(badge.rating_count));
+        let end = count.saturating_sub(offset);
+        let start = end.saturating_sub(limit);
+        let mut ratings = read_log(&self.ratings, agent_id, start, end - start, end).await?;
+        ratings.reverse();
+        Ok(ratings)
     }
 
     /// Flag an agent for spam
-    pub async fn flag_spam(&mut self, agent_id: &AccountOwner) -> Result<u8, String> {
+    pub async fn flag_spam(
+        &mut self,
+        agent_id: &AgentId,
+        params: &RegistryParameters,
+        now: Timestamp,
+    ) -> Result<u8, String> {
         let mut badge = self
             .badges
             .get(agent_id)
@@ -163,7 +857,7 @@ impl KyaRegistryState {
         
         // Heavy reputation penalty for spam flags
         badge.reputation_score = badge.reputation_score.saturating_sub(50);
-        badge.update_tier();
+        badge.update_tier(params, now);
         
         let flags = badge.spam_flags;
         self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
@@ -173,11 +867,18 @@ impl KyaRegistryState {
 
     // === Economic State Methods ===
 
-    /// Increase an agent's stake balance
-    pub async fn stake_tokens(&mut self, agent_id: &AccountOwner, amount: Amount) -> Result<Amount, String> {
+    /// Increase an agent's stake balance and re-evaluate its tier
+    pub async fn stake_tokens(
+        &mut self,
+        agent_id: &AgentId,
+        amount: Amount,
+        params: &RegistryParameters,
+        now: Timestamp,
+    ) -> Result<Amount, String> {
         let mut badge = self.badges.get(agent_id).await.map_err(|e| e.to_string())?.ok_or("Agent not found")?;
         
         badge.stake_balance = badge.stake_balance.saturating_add(amount);
+        badge.update_tier(params, now);
         
         let new_balance = badge.stake_balance;
         self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
@@ -185,8 +886,14 @@ impl KyaRegistryState {
         Ok(new_balance)
     }
 
-    /// Decrease an agent's stake balance (Unstake or Slash)
-    pub async fn unstake_tokens(&mut self, agent_id: &AccountOwner, amount: Amount) -> Result<Amount, String> {
+    /// Decrease an agent's stake balance and re-evaluate its tier
+    pub async fn unstake_tokens(
+        &mut self,
+        agent_id: &AgentId,
+        amount: Amount,
+        params: &RegistryParameters,
+        now: Timestamp,
+    ) -> Result<Amount, String> {
         let mut badge = self.badges.get(agent_id).await.map_err(|e| e.to_string())?.ok_or("Agent not found")?;
         
         if badge.stake_balance < amount {
@@ -194,6 +901,7 @@ impl KyaRegistryState {
         }
         
         badge.stake_balance = badge.stake_balance.saturating_sub(amount);
+        badge.update_tier(params, now);
         
         let remaining = badge.stake_balance;
         self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
@@ -201,8 +909,99 @@ impl KyaRegistryState {
         Ok(remaining)
     }
 
+    /// Slash an agent's stake, paying the proceeds into the treasury and the
+    /// claim balances of the reporter and harmed subscriber
+    pub async fn slash_stake(
+        &mut self,
+        agent_id: &AgentId,
+        amount: Amount,
+        reporter: Option<AccountOwner>,
+        harmed_subscriber: Option<AccountOwner>,
+        timestamp: Timestamp,
+        params: &RegistryParameters,
+    ) -> Result<SlashDistribution, String> {
+        let governance = params.governance.ok_or("No governance account configured")?;
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if badge.stake_balance < amount {
+            return Err("Insufficient stake balance".to_string());
+        }
+        if reporter == Some(badge.owner) {
+            return Err("The agent's owner cannot be rewarded for reporting it".to_string());
+        }
+        if let Some(subscriber) = harmed_subscriber {
+            let subscriptions = self.get_subscriptions(agent_id).await?;
+            if !subscriptions.iter().any(|existing| existing.subscriber == subscriber) {
+                return Err("Harmed account never subscribed to this agent".to_string());
+            }
+        }
+
+        let distribution =
+            params
+                .slash_split
+                .distribute(amount, reporter.is_some(), harmed_subscriber.is_some());
+
+        badge.stake_balance = badge.stake_balance.saturating_sub(amount);
+        badge.update_tier(params, timestamp);
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+
+        let treasury = self.treasury.get().saturating_add(distribution.treasury);
+        self.treasury.set(treasury);
+        if let Some(reporter) = reporter {
+            self.credit_claim(&reporter, distribution.reporter).await?;
+        }
+        if let Some(subscriber) = harmed_subscriber {
+            self.credit_claim(&subscriber, distribution.compensation).await?;
+        }
+
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind: AgentEventKind::Slashed,
+                actor: governance,
+                details: format!(
+                    "slashed {amount}: treasury {}, reporter {}, compensation {}",
+                    distribution.treasury, distribution.reporter, distribution.compensation
+                ),
+                timestamp,
+            },
+        )
+        .await?;
+        Ok(distribution)
+    }
+
+    /// Get the slash proceeds waiting to be claimed by an account
+    pub async fn get_claimable(&self, claimant: &AccountOwner) -> Result<Amount, String> {
+        Ok(self
+            .slash_claims
+            .get(claimant)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default())
+    }
+
+    /// Empty an account's claim balance, returning the amount to pay out
+    pub async fn take_claim(&mut self, claimant: &AccountOwner) -> Result<Amount, String> {
+        let amount = self.get_claimable(claimant).await?;
+        if amount == Amount::ZERO {
+            return Err("Nothing to claim".to_string());
+        }
+        self.slash_claims.remove(claimant).map_err(|e| e.to_string())?;
+        Ok(amount)
+    }
+
+    /// Take `amount` out of the treasury, returning the remaining balance
+    pub fn withdraw_treasury(&mut self, amount: Amount) -> Result<Amount, String> {
+        let remaining = self
+            .treasury
+            .get()
+            .try_sub(amount)
+            .map_err(|_| "Insufficient treasury balance".to_string())?;
+        self.treasury.set(remaining);
+        Ok(remaining)
+    }
+
     /// Set subscription cost for an agent
-    pub async fn set_subscription_cost(&mut self, agent_id: &AccountOwner, cost: Amount) -> Result<(), String> {
+    pub async fn set_subscription_cost(&mut self, agent_id: &AgentId, cost: Amount) -> Result<(), String> {
         let mut badge = self.badges.get(agent_id).await.map_err(|e| e.to_string())?.ok_or("Agent not found")?;
         
         badge.subscription_cost = cost;
@@ -211,9 +1010,513 @@ impl KyaRegistryState {
         Ok(())
     }
 
+    /// Pay for a subscription, or renew the subscriber's existing one, returning
+    /// the cost charged and the new end of the paid period
+    pub async fn subscribe(
+        &mut self,
+        agent_id: &AgentId,
+        subscriber: AccountOwner,
+        notify_chain: Option<ChainId>,
+        timestamp: Timestamp,
+        duration_secs: u64,
+    ) -> Result<(Amount, Timestamp), String> {
+        let badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if !badge.is_active() {
+            return Err(format!("Agent is {:?}", badge.status));
+        }
+        let cost = badge.subscription_cost;
+
+        let mut subscriptions = self.get_subscriptions(agent_id).await?;
+        let expires_at = match subscriptions
+            .iter_mut()
+            .find(|existing| existing.subscriber == subscriber)
+        {
+            Some(existing) => {
+                existing.renew(notify_chain, timestamp, duration_secs, cost);
+                existing.expires_at
+            }
+            None => {
+                let subscription =
+                    Subscription::new(subscriber, notify_chain, timestamp, duration_secs).with_payment(cost);
+                let expires_at = subscription.expires_at;
+                subscriptions.push(subscription);
+                expires_at
+            }
+        };
+        self.subscriptions
+            .insert(agent_id, subscriptions)
+            .map_err(|e| e.to_string())?;
+
+        Ok((cost, expires_at))
+    }
+
+    /// Get all subscriptions (active and expired) for an agent
+    pub async fn get_subscriptions(&self, agent_id: &AgentId) -> Result<Vec<Subscription>, String> {
+        Ok(self
+            .subscriptions
+            .get(agent_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default())
+    }
+
+    /// Pin (or unpin) the code hash an active subscriber expects, returning
+    /// whether the agent currently runs the pinned code
+    pub async fn pin_code_hash(
+        &mut self,
+        agent_id: &AgentId,
+        subscriber: &AccountOwner,
+        code_hash: Option<[u8; 32]>,
+        now: Timestamp,
+    ) -> Result<bool, String> {
+        let badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        let mut subscriptions = self.get_subscriptions(agent_id).await?;
+        let subscription = subscriptions
+            .iter_mut()
+            .find(|existing| existing.subscriber == *subscriber && existing.is_active(now))
+            .ok_or("No active subscription")?;
+
+        subscription.pinned_code_hash = code_hash;
+        self.subscriptions
+            .insert(agent_id, subscriptions)
+            .map_err(|e| e.to_string())?;
+
+        Ok(code_hash.is_none_or(|pinned| pinned == badge.code_hash))
+    }
+
+    /// Chains to notify of a code update, once each, with the pins broken by
+    /// any subscriber on that chain
+    pub async fn code_update_recipients(
+        &self,
+        agent_id: &AgentId,
+        new_code_hash: [u8; 32],
+        now: Timestamp,
+    ) -> Result<BTreeMap<ChainId, Vec<[u8; 32]>>, String> {
+        let mut recipients = BTreeMap::<ChainId, Vec<[u8; 32]>>::new();
+        for subscription in self.get_subscriptions(agent_id).await? {
+            let Some(chain_id) = subscription.notify_chain else {
+                continue;
+            };
+            if !subscription.is_active(now) {
+                continue;
+            }
+            let broken_pins = recipients.entry(chain_id).or_default();
+            if let Some(pinned) = subscription.pinned_code_hash {
+                if pinned != new_code_hash && !broken_pins.contains(&pinned) {
+                    broken_pins.push(pinned);
+                }
+            }
+        }
+        Ok(recipients)
+    }
+
+    /// Store a code update notification received from a registry chain, keeping
+    /// pin warnings from the previous one that the update did not resolve
+    pub async fn record_code_update_notice(&mut self, mut notice: CodeUpdateNotice) -> Result<(), String> {
+        let agent_id = notice.agent_id;
+        if let Some(previous) = self
+            .code_update_notices
+            .get(&agent_id)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            notice.carry_broken_pins(&previous);
+        }
+        self.code_update_notices
+            .insert(&agent_id, notice)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Get the latest code update notification received for an agent
+    pub async fn get_code_update_notice(
+        &self,
+        agent_id: &AgentId,
+    ) -> Result<Option<CodeUpdateNotice>, String> {
+        self.code_update_notices.get(agent_id).await.map_err(|e| e.to_string())
+    }
+
     /// Check if subscription payment is sufficient
-    pub async fn get_subscription_cost(&self, agent_id: &AccountOwner) -> Result<Amount, String> {
+    pub async fn get_subscription_cost(&self, agent_id: &AgentId) -> Result<Amount, String> {
          let badge = self.badges.get(agent_id).await.map_err(|e| e.to_string())?.ok_or("Agent not found")?;
          Ok(badge.subscription_cost)
     }
+
+    // === Lifecycle State Methods ===
+
+    /// Suspend, reinstate or ban an agent and record the transition
+    pub async fn change_status(
+        &mut self,
+        agent_id: &AgentId,
+        status: AgentStatus,
+        actor: AccountOwner,
+        reason: String,
+        timestamp: Timestamp,
+        params: &RegistryParameters,
+    ) -> Result<(), String> {
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        badge.set_status(status, params, timestamp)?;
+        let mut details = reason;
+        if status == AgentStatus::Banned {
+            let forfeited = self.forfeit_deposit(&mut badge);
+            if forfeited > Amount::ZERO {
+                details = format!("{details} (deposit {forfeited} forfeited)");
+            }
+        }
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+
+        let kind = match status {
+            AgentStatus::Active => AgentEventKind::Reinstated,
+            AgentStatus::Suspended => AgentEventKind::Suspended,
+            AgentStatus::Banned => AgentEventKind::Banned,
+            AgentStatus::Retired => AgentEventKind::Retired,
+        };
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind,
+                actor,
+                details,
+                timestamp,
+            },
+        )
+        .await
+    }
+
+    /// Retire an agent, returning its (now released) stake and refunded deposit.
+    /// The deposit is only refunded once the agent has been registered for
+    /// `deposit_lock_secs`; earlier retirements forfeit it to the treasury.
+    pub async fn retire_agent(
+        &mut self,
+        agent_id: &AgentId,
+        timestamp: Timestamp,
+        params: &RegistryParameters,
+    ) -> Result<(Amount, Amount), String> {
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        badge.set_status(AgentStatus::Retired, params, timestamp)?;
+        let returned = badge.stake_balance;
+        badge.stake_balance = Amount::ZERO;
+
+        let refunded = if badge.deposit_refundable(params, timestamp) {
+            let refunded = badge.deposit;
+            badge.deposit = Amount::ZERO;
+            let held = self.total_deposits_held.get().saturating_sub(refunded);
+            self.total_deposits_held.set(held);
+            let total = self.total_deposits_refunded.get().saturating_add(refunded);
+            self.total_deposits_refunded.set(total);
+            refunded
+        } else {
+            self.forfeit_deposit(&mut badge);
+            Amount::ZERO
+        };
+        let owner = badge.owner;
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind: AgentEventKind::Retired,
+                actor: owner,
+                details: format!("returned stake {returned}, refunded deposit {refunded}"),
+                timestamp,
+            },
+        )
+        .await?;
+        Ok((returned, refunded))
+    }
+
+    /// Move an agent's deposit into the treasury, returning the forfeited amount
+    fn forfeit_deposit(&mut self, badge: &mut AgentBadge) -> Amount {
+        let forfeited = badge.deposit;
+        badge.deposit = Amount::ZERO;
+        let held = self.total_deposits_held.get().saturating_sub(forfeited);
+        self.total_deposits_held.set(held);
+        let total = self.total_deposits_forfeited.get().saturating_add(forfeited);
+        self.total_deposits_forfeited.set(total);
+        let treasury = self.treasury.get().saturating_add(forfeited);
+        self.treasury.set(treasury);
+        forfeited
+    }
+
+    // === Ownership State Methods ===
+
+    /// Propose a new owner for an agent
+    pub async fn propose_transfer(
+        &mut self,
+        agent_id: &AgentId,
+        new_owner: AccountOwner,
+        timestamp: Timestamp,
+    ) -> Result<(), String> {
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if new_owner == badge.owner {
+            return Err("New owner must differ from the current owner".to_string());
+        }
+
+        let owner = badge.owner;
+        badge.pending_owner = Some(new_owner);
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind: AgentEventKind::TransferProposed,
+                actor: owner,
+                details: format!("proposed owner {new_owner}"),
+                timestamp,
+            },
+        )
+        .await
+    }
+
+    /// Cancel a pending ownership transfer
+    pub async fn cancel_transfer(&mut self, agent_id: &AgentId, timestamp: Timestamp) -> Result<(), String> {
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        let pending = badge.pending_owner.take().ok_or("No pending transfer")?;
+        let owner = badge.owner;
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind: AgentEventKind::TransferCancelled,
+                actor: owner,
+                details: format!("cancelled transfer to {pending}"),
+                timestamp,
+            },
+        )
+        .await
+    }
+
+    /// Accept a pending transfer, making the signer the agent's owner;
+    /// returns the previous owner
+    pub async fn accept_transfer(
+        &mut self,
+        agent_id: &AgentId,
+        signer: AccountOwner,
+        timestamp: Timestamp,
+    ) -> Result<AccountOwner, String> {
+        let badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if badge.pending_owner != Some(signer) {
+            return Err("No pending transfer to the signer".to_string());
+        }
+
+        // The new custodian registers its own recovery key
+        let old_owner = self.change_owner(agent_id, signer, false).await?;
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind: AgentEventKind::OwnershipTransferred,
+                actor: signer,
+                details: format!("transferred from {old_owner}"),
+                timestamp,
+            },
+        )
+        .await?;
+        Ok(old_owner)
+    }
+
+    /// Set or clear the recovery key for an agent
+    pub async fn set_recovery_key(
+        &mut self,
+        agent_id: &AgentId,
+        recovery_key: Option<AccountOwner>,
+        timestamp: Timestamp,
+    ) -> Result<(), String> {
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if recovery_key == Some(badge.owner) {
+            return Err("Recovery key must differ from the owner key".to_string());
+        }
+
+        let owner = badge.owner;
+        badge.recovery_key = recovery_key;
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+
+        let details = match recovery_key {
+            Some(key) => format!("recovery key set to {key}"),
+            None => "recovery key cleared".to_string(),
+        };
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind: AgentEventKind::RecoveryKeyChanged,
+                actor: owner,
+                details,
+                timestamp,
+            },
+        )
+        .await
+    }
+
+    /// Rotate an agent to a new owner key, authorized by its recovery key;
+    /// returns the previous owner
+    pub async fn rotate_key(
+        &mut self,
+        agent_id: &AgentId,
+        new_owner: AccountOwner,
+        signer: AccountOwner,
+        timestamp: Timestamp,
+    ) -> Result<AccountOwner, String> {
+        let badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if badge.recovery_key != Some(signer) {
+            return Err("Only the recovery key can rotate the owner key".to_string());
+        }
+        if new_owner == signer {
+            return Err("New owner must differ from the recovery key".to_string());
+        }
+        if new_owner == badge.owner {
+            return Err("New owner must differ from the current owner".to_string());
+        }
+
+        let old_owner = self.change_owner(agent_id, new_owner, true).await?;
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind: AgentEventKind::KeyRotated,
+                actor: signer,
+                details: format!("rotated from {old_owner} to {new_owner}"),
+                timestamp,
+            },
+        )
+        .await?;
+        Ok(old_owner)
+    }
+
+    /// Grant (or re-grant) scoped permissions to an operator key
+    pub async fn grant_delegation(
+        &mut self,
+        agent_id: &AgentId,
+        delegation: Delegation,
+    ) -> Result<(), String> {
+        let badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        if delegation.operator == badge.owner {
+            return Err("The owner cannot delegate to itself".to_string());
+        }
+        if delegation.scopes.is_empty() {
+            return Err("A delegation needs at least one scope".to_string());
+        }
+
+        let mut delegations = self.get_delegations(agent_id).await?;
+        delegations.retain(|existing| existing.operator != delegation.operator);
+        let details = format!("{} granted {:?}", delegation.operator, delegation.scopes);
+        let timestamp = delegation.granted_at;
+        delegations.push(delegation);
+        self.delegations
+            .insert(agent_id, delegations)
+            .map_err(|e| e.to_string())?;
+
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind: AgentEventKind::DelegationGranted,
+                actor: badge.owner,
+                details,
+                timestamp,
+            },
+        )
+        .await
+    }
+
+    /// Revoke an operator key's permissions
+    pub async fn revoke_delegation(
+        &mut self,
+        agent_id: &AgentId,
+        operator: &AccountOwner,
+        timestamp: Timestamp,
+    ) -> Result<(), String> {
+        let badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        let mut delegations = self.get_delegations(agent_id).await?;
+        let before = delegations.len();
+        delegations.retain(|existing| existing.operator != *operator);
+        if delegations.len() == before {
+            return Err("Operator has no delegation".to_string());
+        }
+        self.delegations
+            .insert(agent_id, delegations)
+            .map_err(|e| e.to_string())?;
+
+        self.record_event(
+            agent_id,
+            AgentEvent {
+                kind: AgentEventKind::DelegationRevoked,
+                actor: badge.owner,
+                details: format!("{operator} revoked"),
+                timestamp,
+            },
+        )
+        .await
+    }
+
+    /// Get all delegations (active and expired) for an agent
+    pub async fn get_delegations(&self, agent_id: &AgentId) -> Result<Vec<Delegation>, String> {
+        Ok(self
+            .delegations
+            .get(agent_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default())
+    }
+
+    /// Hand an agent to a new owner and update the owner index; returns the previous owner
+    async fn change_owner(
+        &mut self,
+        agent_id: &AgentId,
+        new_owner: AccountOwner,
+        keep_recovery_key: bool,
+    ) -> Result<AccountOwner, String> {
+        let mut badge = self.get_badge(agent_id).await?.ok_or("Agent not found")?;
+        let old_owner = badge.owner;
+        badge.owner = new_owner;
+        badge.pending_owner = None;
+        if !keep_recovery_key {
+            badge.recovery_key = None;
+        }
+        self.badges.insert(agent_id, badge).map_err(|e| e.to_string())?;
+        // Operators were trusted by the previous owner, not the new one
+        self.delegations.remove(agent_id).map_err(|e| e.to_string())?;
+
+        let mut previous = self.agents_of(&old_owner).await?;
+        previous.retain(|id| id != agent_id);
+        if previous.is_empty() {
+            self.owner_agents.remove(&old_owner).map_err(|e| e.to_string())?;
+        } else {
+            self.owner_agents
+                .insert(&old_owner, previous)
+                .map_err(|e| e.to_string())?;
+        }
+        self.add_to_owner(&new_owner, *agent_id).await?;
+        Ok(old_owner)
+    }
+
+    /// Add slash proceeds to an account's claim balance
+    async fn credit_claim(&mut self, claimant: &AccountOwner, amount: Amount) -> Result<(), String> {
+        let balance = self.get_claimable(claimant).await?.saturating_add(amount);
+        self.slash_claims.insert(claimant, balance).map_err(|e| e.to_string())
+    }
+
+    /// Append an agent to an owner's index
+    async fn add_to_owner(&mut self, owner: &AccountOwner, agent_id: AgentId) -> Result<(), String> {
+        let mut agents = self.agents_of(owner).await?;
+        agents.push(agent_id);
+        self.owner_agents.insert(owner, agents).map_err(|e| e.to_string())
+    }
+}
+
+/// Read up to `limit` entries from position `offset` of a per-agent log holding `count` entries
+async fn read_log<T>(
+    log: &MapView<(AgentId, u64), T>,
+    agent_id: &AgentId,
+    offset: u64,
+    limit: u64,
+    count: u64,
+) -> Result<Vec<T>, String>
+where
+    T: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    let end = count.min(offset.saturating_add(limit));
+    let mut entries = Vec::new();
+    for position in offset..end {
+        if let Some(entry) = log.get(&(*agent_id, position)).await.map_err(|e| e.to_string())? {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
 }
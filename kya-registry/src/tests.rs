@@ -40,6 +40,44 @@ fn test_slash_cannot_exceed_balance() {
     assert_eq!(remaining, Amount::ZERO);
 }
 
+#[test]
+fn test_slash_split_distribution() {
+    let split = SlashSplit::default();
+    let amount = Amount::from_tokens(100);
+
+    let full = split.distribute(amount, true, true);
+    assert_eq!(full.treasury, Amount::from_tokens(50));
+    assert_eq!(full.reporter, Amount::from_tokens(25));
+    assert_eq!(full.compensation, Amount::from_tokens(25));
+
+    // Unclaimed shares stay with the treasury
+    let no_claimant = split.distribute(amount, true, false);
+    assert_eq!(no_claimant.treasury, Amount::from_tokens(75));
+    assert_eq!(no_claimant.compensation, Amount::ZERO);
+    assert_eq!(split.distribute(amount, false, false).treasury, amount);
+
+    // Rounding never creates or loses tokens
+    let odd = Amount::from_attos(101);
+    let parts = split.distribute(odd, true, true);
+    assert_eq!(parts.reporter, Amount::from_attos(25));
+    assert_eq!(
+        parts.treasury.saturating_add(parts.reporter).saturating_add(parts.compensation),
+        odd
+    );
+}
+
+#[test]
+fn test_slash_split_validation() {
+    assert!(RegistryParameters::default().validate().is_ok());
+
+    let mut params = RegistryParameters::default();
+    params.slash_split.reporter_percent = 30;
+    assert!(params.validate().is_err());
+
+    params.slash_split.treasury_percent = 45;
+    assert!(params.validate().is_ok());
+}
+
 #[test]
 fn test_subscription_cost_amount() {
     let cost = Amount::from_tokens(5);
@@ -50,32 +88,155 @@ fn test_subscription_cost_amount() {
     assert!(higher_cost > cost);
 }
 
+fn tier_at(score: u16, params: &RegistryParameters) -> (String, Option<u32>) {
+    let mut badge = test_badge();
+    badge.reputation_score = score;
+    badge.update_tier(params, Timestamp::from(0));
+    (badge.tier, badge.rate_limit)
+}
+
 #[test]
 fn test_tier_from_score() {
-    // Test the tier calculation logic
-    assert_eq!(ReputationTier::from_score(0), ReputationTier::Unverified);
-    assert_eq!(ReputationTier::from_score(100), ReputationTier::Unverified);
-    assert_eq!(ReputationTier::from_score(249), ReputationTier::Unverified);
-    
-    assert_eq!(ReputationTier::from_score(250), ReputationTier::Verified);
-    assert_eq!(ReputationTier::from_score(400), ReputationTier::Verified);
-    assert_eq!(ReputationTier::from_score(499), ReputationTier::Verified);
-    
-    assert_eq!(ReputationTier::from_score(500), ReputationTier::Gold);
-    assert_eq!(ReputationTier::from_score(600), ReputationTier::Gold);
-    assert_eq!(ReputationTier::from_score(749), ReputationTier::Gold);
-    
-    assert_eq!(ReputationTier::from_score(750), ReputationTier::Platinum);
-    assert_eq!(ReputationTier::from_score(900), ReputationTier::Platinum);
-    assert_eq!(ReputationTier::from_score(1000), ReputationTier::Platinum);
+    // Test the tier calculation logic against the default table
+    let params = RegistryParameters::default();
+    for (score, tier) in [
+        (0, "Unverified"),
+        (100, "Unverified"),
+        (249, "Unverified"),
+        (250, "Verified"),
+        (400, "Verified"),
+        (499, "Verified"),
+        (500, "Gold"),
+        (600, "Gold"),
+        (749, "Gold"),
+        (750, "Platinum"),
+        (900, "Platinum"),
+        (1000, "Platinum"),
+    ] {
+        assert_eq!(tier_at(score, &params).0, tier, "score {score}");
+    }
 }
 
 #[test]
 fn test_tier_rate_limits() {
-    assert_eq!(ReputationTier::Unverified.rate_limit(), Some(0));
-    assert_eq!(ReputationTier::Verified.rate_limit(), Some(10));
-    assert_eq!(ReputationTier::Gold.rate_limit(), Some(100));
-    assert_eq!(ReputationTier::Platinum.rate_limit(), None); // Unlimited
+    let params = RegistryParameters::default();
+    assert_eq!(tier_at(0, &params).1, Some(0));
+    assert_eq!(tier_at(250, &params).1, Some(10));
+    assert_eq!(tier_at(500, &params).1, Some(100));
+    assert_eq!(tier_at(750, &params).1, None); // Unlimited
+}
+
+#[test]
+fn test_custom_tier_table() {
+    let params = RegistryParameters {
+        tiers: vec![
+            TierDefinition::new("Suspended", 0, Some(0)),
+            TierDefinition::new("Unverified", 100, Some(1)),
+            TierDefinition::new("Trusted", 600, None),
+        ],
+        ..RegistryParameters::default()
+    };
+    assert!(params.validate().is_ok());
+
+    assert_eq!(tier_at(50, &params), ("Suspended".to_string(), Some(0)));
+    assert_eq!(tier_at(100, &params), ("Unverified".to_string(), Some(1)));
+    assert_eq!(tier_at(700, &params), ("Trusted".to_string(), None));
+
+    // Inactive agents fall to the lowest tier
+    let mut badge = test_badge();
+    badge.reputation_score = 700;
+    badge.set_status(AgentStatus::Suspended, &params, Timestamp::from(0)).unwrap();
+    assert_eq!(badge.tier, "Suspended");
+}
+
+#[test]
+fn test_tier_table_validation() {
+    let mut params = RegistryParameters {
+        tiers: Vec::new(),
+        ..RegistryParameters::default()
+    };
+    assert!(params.validate().is_err());
+
+    params.tiers = vec![TierDefinition::new("Low", 10, Some(0))];
+    assert!(params.validate().is_err());
+
+    params.tiers = vec![
+        TierDefinition::new("Low", 0, Some(0)),
+        TierDefinition::new("High", 500, None),
+        TierDefinition::new("Mid", 250, Some(10)),
+    ];
+    assert!(params.validate().is_err());
+}
+
+#[test]
+fn test_stake_gates_tiers() {
+    let params = RegistryParameters::default();
+    let mut badge = test_badge();
+    badge.reputation_score = 800;
+    badge.stake_balance = Amount::ZERO;
+    badge.update_tier(&params, Timestamp::from(0));
+
+    // A high score alone is not enough
+    assert_eq!(badge.tier, "Unverified");
+    assert_eq!(badge.next_tier.as_deref(), Some("Verified"));
+    assert_eq!(badge.promotion_blockers, vec![TierRequirement::Stake]);
+
+    badge.stake_balance = Amount::from_tokens(100);
+    badge.update_tier(&params, Timestamp::from(0));
+    assert_eq!(badge.tier, "Gold");
+    assert_eq!(badge.next_tier.as_deref(), Some("Platinum"));
+    assert_eq!(badge.promotion_blockers, vec![TierRequirement::Stake]);
+
+    badge.stake_balance = Amount::from_tokens(1000);
+    badge.update_tier(&params, Timestamp::from(0));
+    assert_eq!(badge.tier, "Platinum");
+    assert_eq!(badge.next_tier, None);
+    assert!(badge.promotion_blockers.is_empty());
+}
+
+#[test]
+fn test_audit_recency_gates_tiers() {
+    let mut params = RegistryParameters::default();
+    params.tiers[3] = params.tiers[3].clone().with_audit(Some(60));
+
+    let mut badge = test_badge();
+    badge.reputation_score = 800;
+    badge.update_tier(&params, Timestamp::from(0));
+    assert_eq!(badge.tier, "Gold");
+    assert_eq!(badge.promotion_blockers, vec![TierRequirement::Audit]);
+
+    // The audit's +100 lands the score at 900 and satisfies the requirement
+    badge.apply_audit(true, Timestamp::from(1_000_000), &params);
+    assert_eq!(badge.tier, "Platinum");
+
+    // Once the audit is older than 60 seconds the agent drops back
+    badge.update_tier(&params, Timestamp::from(62_000_000));
+    assert_eq!(badge.tier, "Gold");
+    assert_eq!(badge.promotion_blockers, vec![TierRequirement::Audit]);
+
+    // A failed audit does not count as a fresh one
+    badge.apply_audit(false, Timestamp::from(63_000_000), &params);
+    let unmet = badge.unmet_requirements(&params.tiers[3], Timestamp::from(63_000_000));
+    assert!(unmet.contains(&TierRequirement::Audit));
+}
+
+#[test]
+fn test_reaudit_blocks_promotion() {
+    let params = RegistryParameters::default();
+    let mut badge = test_badge();
+    badge.manifest = trading_manifest();
+    badge.reputation_score = 600;
+
+    let mut manifest = trading_manifest();
+    manifest.required_permissions.push("wallet:sign".to_string());
+    update(&mut badge, [1u8; 32], manifest, 1);
+
+    assert_eq!(badge.tier, "Unverified");
+    assert_eq!(badge.next_tier.as_deref(), Some("Verified"));
+    assert_eq!(badge.promotion_blockers, vec![TierRequirement::Reaudit]);
+
+    badge.apply_audit(true, Timestamp::from(2), &params);
+    assert_eq!(badge.tier, "Gold");
 }
 
 #[test]
@@ -104,3 +265,821 @@ fn test_agent_manifest_default() {
     assert_eq!(manifest.runtime, "python3");
     assert_eq!(manifest.license, "MIT");
 }
+
+fn test_owner(byte: u8) -> AccountOwner {
+    AccountOwner::Address32(linera_sdk::linera_base_types::CryptoHash::from([byte; 32]))
+}
+
+fn test_badge() -> AgentBadge {
+    let mut badge = AgentBadge::new(
+        derive_agent_id(test_owner(1), 0),
+        test_owner(1),
+        [7u8; 32],
+        StorageProvider::IPFS,
+        "bafy-test".to_string(),
+        AgentManifest::default(),
+        Timestamp::from(0),
+    );
+    // Enough stake and track record for every default tier, so tests can focus on score
+    badge.stake_balance = Amount::from_tokens(1000);
+    badge.tasks_completed = 100;
+    badge.attested_successes = 100;
+    badge.update_tier(&RegistryParameters::default(), Timestamp::from(0));
+    badge
+}
+
+fn test_verification(computed_hash: [u8; 32], micros: u64) -> VerificationRecord {
+    VerificationRecord {
+        verifier: test_owner(2),
+        storage_cid: "bafy-test".to_string(),
+        computed_hash,
+        matches: computed_hash == [7u8; 32],
+        timestamp: Timestamp::from(micros),
+    }
+}
+
+#[test]
+fn test_matching_verification_updates_status() {
+    let mut badge = test_badge();
+    let params = RegistryParameters::default();
+
+    let penalty = badge.record_verification(&test_verification([7u8; 32], 42), true, &params);
+
+    assert_eq!(penalty, 0);
+    assert_eq!(badge.verification_count, 1);
+    assert_eq!(badge.hash_mismatches, 0);
+    assert_eq!(badge.last_verified_at, Some(Timestamp::from(42)));
+    assert_eq!(badge.last_verified_by, Some(test_owner(2)));
+}
+
+#[test]
+fn test_only_registered_verifiers_attest() {
+    let mut params = RegistryParameters::default();
+    assert!(!params.is_verifier(&test_owner(2)));
+
+    params.verifiers = vec![test_owner(2)];
+    params.governance = Some(test_owner(9));
+    assert!(params.is_verifier(&test_owner(2)));
+    assert!(params.is_verifier(&test_owner(9)));
+    assert!(!params.is_verifier(&test_owner(3)));
+}
+
+#[test]
+fn test_only_registered_auditors_submit_audits() {
+    let mut params = RegistryParameters {
+        verifiers: vec![test_owner(2)],
+        ..RegistryParameters::default()
+    };
+    assert!(!params.is_auditor(&test_owner(2)));
+
+    params.auditors = vec![test_owner(4)];
+    params.governance = Some(test_owner(9));
+    assert!(params.is_auditor(&test_owner(4)));
+    assert!(params.is_auditor(&test_owner(9)));
+    assert!(!params.is_auditor(&test_owner(5)));
+}
+
+#[test]
+fn test_repeated_mismatches_trigger_penalty() {
+    let mut badge = test_badge();
+    badge.reputation_score = 600;
+    badge.update_tier(&RegistryParameters::default(), Timestamp::from(0));
+    let params = RegistryParameters {
+        mismatch_threshold: 2,
+        mismatch_penalty: 150,
+        ..RegistryParameters::default()
+    };
+
+    // First mismatch is tolerated, and repeats from the same verifier do not count
+    assert_eq!(badge.record_verification(&test_verification([9u8; 32], 1), true, &params), 0);
+    assert_eq!(badge.record_verification(&test_verification([9u8; 32], 2), false, &params), 0);
+    assert_eq!(badge.hash_mismatches, 1);
+    assert_eq!(badge.reputation_score, 600);
+
+    // A second verifier reaching the threshold applies the penalty
+    assert_eq!(badge.record_verification(&test_verification([9u8; 32], 3), true, &params), 150);
+    assert_eq!(badge.reputation_score, 450);
+    assert_eq!(badge.tier, "Verified");
+    assert_eq!(badge.hash_mismatches, 2);
+    assert_eq!(badge.last_verified_at, None);
+
+    // Further reporters past the threshold do not penalise again
+    assert_eq!(badge.record_verification(&test_verification([9u8; 32], 4), true, &params), 0);
+    assert_eq!(badge.reputation_score, 450);
+    assert_eq!(badge.hash_mismatches, 3);
+    assert_eq!(badge.verification_count, 4);
+}
+
+#[test]
+fn test_code_update_resets_verification_status() {
+    let mut badge = test_badge();
+    let params = RegistryParameters::default();
+    badge.record_verification(&test_verification([7u8; 32], 1), true, &params);
+    badge.record_verification(&test_verification([9u8; 32], 2), true, &params);
+
+    badge.update_code(
+        [8u8; 32],
+        StorageProvider::IPFS,
+        "bafy-next".to_string(),
+        AgentManifest::default(),
+        Timestamp::from(3),
+        &params,
+    );
+
+    assert_eq!(badge.last_verified_at, None);
+    assert_eq!(badge.hash_mismatches, 0);
+    assert_eq!(badge.verification_count, 2);
+}
+
+fn tool(name: &str, schema: &str) -> ToolDefinition {
+    ToolDefinition {
+        name: name.to_string(),
+        description: String::new(),
+        input_schema: schema.to_string(),
+    }
+}
+
+fn trading_manifest() -> AgentManifest {
+    AgentManifest {
+        capabilities: vec!["defi".to_string()],
+        tools: vec![tool("swap", "{}")],
+        required_permissions: vec!["network".to_string()],
+        ..AgentManifest::default()
+    }
+}
+
+fn update(
+    badge: &mut AgentBadge,
+    code_hash: [u8; 32],
+    manifest: AgentManifest,
+    micros: u64,
+) -> CodeUpdateRecord {
+    let cid = hex::encode(code_hash);
+    badge.update_code(
+        code_hash,
+        StorageProvider::IPFS,
+        cid,
+        manifest,
+        Timestamp::from(micros),
+        &RegistryParameters::default(),
+    )
+}
+
+#[test]
+fn test_manifest_diff_cosmetic() {
+    let old = trading_manifest();
+    let mut new = trading_manifest();
+    new.description = "Fixed a typo".to_string();
+    new.version = "0.1.1".to_string();
+    new.required_permissions.clear();
+
+    let diff = ManifestDiff::between(&old, &new);
+    assert_eq!(diff.severity, ChangeSeverity::Cosmetic);
+    assert_eq!(diff.removed_permissions, vec!["network".to_string()]);
+}
+
+#[test]
+fn test_manifest_diff_functional() {
+    let old = trading_manifest();
+    let mut new = trading_manifest();
+    new.capabilities.push("social".to_string());
+    new.tools = vec![tool("swap", "{\"type\":\"object\"}"), tool("post", "{}")];
+    new.resources.min_memory_mb = 1024;
+
+    let diff = ManifestDiff::between(&old, &new);
+    assert_eq!(diff.severity, ChangeSeverity::Functional);
+    assert_eq!(diff.added_capabilities, vec!["social".to_string()]);
+    assert_eq!(diff.added_tools, vec!["post".to_string()]);
+    assert_eq!(diff.changed_tools, vec!["swap".to_string()]);
+    assert!(diff.resources_changed);
+    assert!(diff.resource_escalations.is_empty());
+}
+
+#[test]
+fn test_manifest_diff_escalation() {
+    let old = trading_manifest();
+    let mut new = trading_manifest();
+    new.resources.requires_filesystem = true;
+
+    let diff = ManifestDiff::between(&old, &new);
+    assert_eq!(diff.severity, ChangeSeverity::Escalation);
+    assert_eq!(diff.resource_escalations, vec!["requires_filesystem".to_string()]);
+
+    let mut new = trading_manifest();
+    new.required_permissions.push("wallet:sign".to_string());
+    assert_eq!(ManifestDiff::between(&old, &new).severity, ChangeSeverity::Escalation);
+}
+
+#[test]
+fn test_code_update_penalty_by_severity() {
+    let params = RegistryParameters::default();
+    let mut badge = test_badge();
+    badge.manifest = trading_manifest();
+    badge.reputation_score = 600;
+    badge.update_tier(&RegistryParameters::default(), Timestamp::from(0));
+
+    // Cosmetic: no penalty
+    let mut manifest = trading_manifest();
+    manifest.version = "0.1.1".to_string();
+    let record = update(&mut badge, [1u8; 32], manifest, 1);
+    assert_eq!(record.penalty, 0);
+    assert_eq!(record.old_version, "0.1.0");
+    assert_eq!(record.new_version, "0.1.1");
+    assert_eq!(badge.reputation_score, 600);
+    assert_eq!(badge.tier, "Gold");
+
+    // Functional: configured penalty
+    let mut manifest = trading_manifest();
+    manifest.capabilities.push("social".to_string());
+    let record = update(&mut badge, [2u8; 32], manifest, 2);
+    assert_eq!(record.penalty, params.functional_update_penalty);
+    assert_eq!(badge.reputation_score, 550);
+    assert_eq!(record.update_number, 2);
+}
+
+#[test]
+fn test_escalation_requires_reaudit() {
+    let mut badge = test_badge();
+    badge.manifest = trading_manifest();
+    badge.reputation_score = 600;
+    badge.update_tier(&RegistryParameters::default(), Timestamp::from(0));
+
+    let mut manifest = trading_manifest();
+    manifest.resources.requires_filesystem = true;
+    let record = update(&mut badge, [1u8; 32], manifest, 1);
+
+    assert_eq!(record.penalty, 0);
+    assert!(badge.pending_reaudit);
+    assert_eq!(badge.reputation_score, 600);
+    assert_eq!(badge.tier, "Unverified");
+
+    // A failed audit keeps the agent pending
+    badge.apply_audit(false, Timestamp::from(2), &RegistryParameters::default());
+    assert!(badge.pending_reaudit);
+    assert_eq!(badge.tier, "Unverified");
+
+    // A passed audit restores the score-based tier
+    badge.apply_audit(true, Timestamp::from(3), &RegistryParameters::default());
+    assert!(!badge.pending_reaudit);
+    assert_eq!(badge.reputation_score, 650);
+    assert_eq!(badge.tier, "Gold");
+    assert_eq!(badge.last_audit_timestamp, Timestamp::from(3));
+}
+
+#[test]
+fn test_subscription_expiry() {
+    let start = Timestamp::from(1_000_000);
+    let subscription = Subscription::new(test_owner(3), None, start, 60);
+
+    assert_eq!(subscription.expires_at, Timestamp::from(61_000_000));
+    assert!(subscription.is_active(start));
+    assert!(subscription.is_active(Timestamp::from(60_999_999)));
+    assert!(!subscription.is_active(Timestamp::from(61_000_000)));
+    assert_eq!(subscription.pinned_code_hash, None);
+}
+
+#[test]
+fn test_resubscribing_does_not_allow_another_rating() {
+    let cost = Amount::from_tokens(1);
+    let mut subscription = Subscription::new(test_owner(3), None, Timestamp::from(0), 60).with_payment(cost);
+    assert!(subscription.check_can_rate(Timestamp::from(1_000_000)).is_ok());
+    subscription.rated = true;
+
+    // Re-subscribing during the period extends it without a fresh rating
+    subscription.renew(None, Timestamp::from(2_000_000), 60, cost);
+    assert_eq!(subscription.subscribed_at, Timestamp::from(0));
+    assert_eq!(subscription.expires_at, Timestamp::from(120_000_000));
+    assert_eq!(subscription.paid, Amount::from_tokens(2));
+    assert!(subscription.check_can_rate(Timestamp::from(3_000_000)).is_err());
+
+    // An expired period cannot be rated, but paying for a new one can
+    let mut lapsed = subscription.clone();
+    lapsed.rated = false;
+    assert!(lapsed.check_can_rate(Timestamp::from(120_000_000)).is_err());
+    subscription.renew(None, Timestamp::from(200_000_000), 60, cost);
+    assert_eq!(subscription.paid, cost);
+    assert!(subscription.check_can_rate(Timestamp::from(200_000_000)).is_ok());
+
+    // Free subscriptions never rate
+    let free = Subscription::new(test_owner(4), None, Timestamp::from(0), 60);
+    assert!(free.check_can_rate(Timestamp::from(0)).is_err());
+}
+
+#[test]
+fn test_subscription_default_duration() {
+    let subscription = Subscription::new(test_owner(3), None, Timestamp::from(0), 0);
+    assert_eq!(
+        subscription.expires_at,
+        Timestamp::from(DEFAULT_SUBSCRIPTION_SECS * 1_000_000)
+    );
+}
+
+#[test]
+fn test_code_update_notice_keeps_unresolved_pin_warnings() {
+    let notice = |new_code_hash: [u8; 32], broken_pins: Vec<[u8; 32]>| CodeUpdateNotice {
+        agent_id: derive_agent_id(test_owner(1), 0),
+        old_code_hash: [0u8; 32],
+        new_code_hash,
+        new_version: "1.0.0".to_string(),
+        broken_pins,
+        timestamp: Timestamp::from(0),
+    };
+    let first = notice([2u8; 32], vec![[1u8; 32]]);
+
+    // A later update without pins on this chain keeps the earlier warning
+    let mut second = notice([3u8; 32], Vec::new());
+    second.carry_broken_pins(&first);
+    assert_eq!(second.broken_pins, vec![[1u8; 32]]);
+
+    // Reverting to the pinned hash resolves it
+    let mut third = notice([1u8; 32], vec![[5u8; 32]]);
+    third.carry_broken_pins(&second);
+    assert_eq!(third.broken_pins, vec![[5u8; 32]]);
+}
+
+#[test]
+fn test_agent_ids_are_unique_per_owner_and_nonce() {
+    let first = derive_agent_id(test_owner(1), 0);
+
+    assert_eq!(first, derive_agent_id(test_owner(1), 0));
+    assert_ne!(first, derive_agent_id(test_owner(1), 1));
+    assert_ne!(first, derive_agent_id(test_owner(2), 0));
+    // IDs round-trip through the hex form used by the GraphQL service
+    assert_eq!(first.to_string().parse::<AgentId>().unwrap(), first);
+}
+
+#[test]
+fn test_delegation_scopes_and_expiry() {
+    let start = Timestamp::from(1_000_000);
+    let delegation = Delegation::new(
+        test_owner(9),
+        vec![DelegationScope::LogTasks],
+        start,
+        Some(60),
+    );
+
+    assert!(delegation.allows(DelegationScope::LogTasks, start));
+    assert!(!delegation.allows(DelegationScope::ManageStake, start));
+    assert!(!delegation.allows(DelegationScope::LogTasks, Timestamp::from(61_000_000)));
+
+    let open_ended = Delegation::new(test_owner(9), vec![DelegationScope::ManagePricing], start, None);
+    assert_eq!(open_ended.expires_at, None);
+    assert!(open_ended.allows(DelegationScope::ManagePricing, Timestamp::from(u64::MAX)));
+}
+
+#[test]
+fn test_owner_and_operators_control_the_agent() {
+    let start = Timestamp::from(1_000_000);
+    let owner = test_owner(1);
+    let delegations = vec![Delegation::new(test_owner(9), vec![DelegationScope::LogTasks], start, Some(60))];
+
+    // Neither the owner nor an operator can rate or attest the agent
+    assert!(controls_agent(&owner, &delegations, &owner, start));
+    assert!(controls_agent(&owner, &delegations, &test_owner(9), start));
+    assert!(!controls_agent(&owner, &delegations, &test_owner(2), start));
+    // Once the delegation expires the key is a second party again
+    assert!(!controls_agent(&owner, &delegations, &test_owner(9), Timestamp::from(61_000_000)));
+}
+
+#[test]
+fn test_status_transitions() {
+    use AgentStatus::*;
+
+    assert!(Active.can_transition_to(Suspended));
+    assert!(Suspended.can_transition_to(Active));
+    assert!(Suspended.can_transition_to(Banned));
+    assert!(Active.can_transition_to(Retired));
+    // Suspended agents cannot retire to recover their locked stake
+    assert!(!Suspended.can_transition_to(Retired));
+    for terminal in [Banned, Retired] {
+        for next in [Active, Suspended, Banned, Retired] {
+            assert!(!terminal.can_transition_to(next));
+        }
+    }
+}
+
+#[test]
+fn test_inactive_agents_lose_their_tier() {
+    let mut badge = test_badge();
+    badge.reputation_score = 800;
+    badge.update_tier(&RegistryParameters::default(), Timestamp::from(0));
+    assert_eq!(badge.tier, "Platinum");
+
+    badge.set_status(AgentStatus::Suspended, &RegistryParameters::default(), Timestamp::from(0)).unwrap();
+    assert_eq!(badge.tier, "Unverified");
+
+    badge.set_status(AgentStatus::Active, &RegistryParameters::default(), Timestamp::from(0)).unwrap();
+    assert_eq!(badge.tier, "Platinum");
+
+    badge.set_status(AgentStatus::Banned, &RegistryParameters::default(), Timestamp::from(0)).unwrap();
+    assert!(badge.set_status(AgentStatus::Active, &RegistryParameters::default(), Timestamp::from(0)).is_err());
+    assert_eq!(badge.tier, "Unverified");
+}
+
+#[test]
+fn test_deposit_refund_lock_period() {
+    let params = RegistryParameters {
+        deposit_lock_secs: 60,
+        ..RegistryParameters::default()
+    };
+    let badge = test_badge();
+
+    assert!(!badge.deposit_refundable(&params, Timestamp::from(59_000_000)));
+    assert!(badge.deposit_refundable(&params, Timestamp::from(60_000_000)));
+}
+
+#[test]
+fn test_attested_tasks_outweigh_self_reports() {
+    let params = RegistryParameters::default();
+    let mut badge = test_badge();
+    let start = badge.reputation_score;
+    let attested = badge.attested_successes;
+
+    // Self-reported successes do not move the score by default
+    badge.record_task_outcome("defi", true, false, &params, Timestamp::from(0));
+    assert_eq!(badge.reputation_score, start);
+    assert_eq!(badge.tasks_completed, 101);
+    assert_eq!(badge.attested_successes, attested);
+
+    badge.record_task_outcome("defi", true, true, &params, Timestamp::from(0));
+    assert_eq!(badge.reputation_score, start + params.task_weights.attested_success);
+    assert_eq!(badge.tasks_completed, 102);
+    assert_eq!(badge.attested_successes, attested + 1);
+
+    badge.record_task_outcome("defi", false, true, &params, Timestamp::from(0));
+    assert_eq!(
+        badge.reputation_score,
+        start + params.task_weights.attested_success - params.task_weights.attested_failure
+    );
+    assert_eq!(badge.tasks_failed, 1);
+    assert_eq!(badge.attested_failures, 1);
+}
+
+#[test]
+fn test_customer_rating() {
+    let params = RegistryParameters::default();
+    let mut badge = test_badge();
+    let start = badge.reputation_score;
+    assert_eq!(badge.customer_rating, None);
+
+    assert!(badge.record_rating(0, &params, Timestamp::from(0)).is_err());
+    assert!(badge.record_rating(6, &params, Timestamp::from(0)).is_err());
+    assert_eq!(badge.rating_count, 0);
+
+    badge.record_rating(5, &params, Timestamp::from(0)).unwrap();
+    assert_eq!(badge.customer_rating, Some(500));
+    assert_eq!(badge.reputation_score, start + 2 * params.rating_weight);
+
+    badge.record_rating(2, &params, Timestamp::from(0)).unwrap();
+    assert_eq!(badge.customer_rating, Some(350));
+    assert_eq!(badge.rating_count, 2);
+    assert_eq!(badge.reputation_score, start + params.rating_weight);
+
+    // A neutral rating leaves the score alone
+    badge.record_rating(3, &params, Timestamp::from(0)).unwrap();
+    assert_eq!(badge.customer_rating, Some(333));
+    assert_eq!(badge.reputation_score, start + params.rating_weight);
+}
+
+#[test]
+fn test_capability_scores_are_tracked_separately() {
+    let params = RegistryParameters::default();
+    let mut badge = test_badge();
+    assert!(badge.capability_score("trading").is_none());
+
+    badge.record_task_outcome("trading", true, true, &params, Timestamp::from(0));
+    badge.record_task_outcome("trading", true, true, &params, Timestamp::from(0));
+    badge.record_task_outcome("social", false, true, &params, Timestamp::from(0));
+
+    let trading = badge.capability_score("trading").unwrap();
+    assert_eq!(trading.score, 100 + 2 * params.task_weights.attested_success);
+    assert_eq!(trading.tasks_completed, 2);
+    assert_eq!(trading.tasks_failed, 0);
+
+    let social = badge.capability_score("social").unwrap();
+    assert_eq!(social.score, 100 - params.task_weights.attested_failure);
+    assert_eq!(social.tasks_failed, 1);
+
+    // The global counters see every task
+    assert_eq!(badge.tasks_completed, 102);
+    assert_eq!(badge.tasks_failed, 1);
+}
+
+#[test]
+fn test_reliability_lower_bound_needs_evidence() {
+    let mut badge = test_badge();
+    badge.tasks_completed = 0;
+    assert_eq!(badge.reliability_bps(), None);
+    assert_eq!(badge.reliability_lower_bound_bps(), 0);
+
+    // A perfect record on 3 tasks is far less convincing than on 3,000
+    badge.tasks_completed = 3;
+    assert_eq!(badge.reliability_bps(), Some(10_000));
+    let thin = badge.reliability_lower_bound_bps();
+    badge.tasks_completed = 3000;
+    let thick = badge.reliability_lower_bound_bps();
+    assert!(thin < 5000, "thin bound was {thin}");
+    assert!(thick > 9980, "thick bound was {thick}");
+
+    badge.tasks_completed = 90;
+    badge.tasks_failed = 10;
+    assert_eq!(badge.reliability_bps(), Some(9000));
+    assert!((8200..8300).contains(&badge.reliability_lower_bound_bps()));
+}
+
+#[test]
+fn test_reliability_gates_tiers() {
+    let params = RegistryParameters::default();
+    let mut badge = test_badge();
+    badge.reputation_score = 800;
+    badge.tasks_completed = 5;
+    badge.attested_successes = 5;
+    badge.update_tier(&params, Timestamp::from(0));
+
+    // 5/5 only bounds reliability at about 57%, below Gold's 80%
+    assert_eq!(badge.tier, "Verified");
+    assert_eq!(badge.next_tier.as_deref(), Some("Gold"));
+    assert_eq!(badge.promotion_blockers, vec![TierRequirement::Reliability]);
+
+    badge.tasks_completed = 40;
+    badge.attested_successes = 40;
+    badge.update_tier(&params, Timestamp::from(0));
+    assert_eq!(badge.tier, "Platinum");
+}
+
+#[test]
+fn test_self_reported_successes_do_not_clear_reliability() {
+    let params = RegistryParameters::default();
+    let mut badge = test_badge();
+    badge.attested_successes = 0;
+    badge.reputation_score = 800;
+
+    // Self-logged successes cost nothing, so any number of them proves nothing
+    let tally = CapabilityTally {
+        capability: "defi".to_string(),
+        successes: 100_000,
+        failures: 0,
+    };
+    badge.record_task_tally(&tally, &params, Timestamp::from(0));
+    assert!(badge.reliability_lower_bound_bps() > 9990);
+    assert_eq!(badge.attested_reliability_lower_bound_bps(), 0);
+    assert_eq!(badge.tier, "Verified");
+    assert!(badge.promotion_blockers.contains(&TierRequirement::Reliability));
+
+    for _ in 0..40 {
+        badge.record_task_outcome("defi", true, true, &params, Timestamp::from(0));
+    }
+    badge.reputation_score = 800;
+    badge.update_tier(&params, Timestamp::from(0));
+    assert_eq!(badge.tier, "Platinum");
+}
+
+#[test]
+fn test_merkle_inclusion_proofs() {
+    let leaves: Vec<[u8; 32]> = (0u8..3)
+        .map(|i| merkle::task_leaf_hash(&[i; 32], "defi", true, Timestamp::from(u64::from(i)), "", None, &TaskDetails::default()))
+        .collect();
+    // Three leaves: the third has no sibling and moves up unchanged
+    let left = merkle::node_hash(&leaves[0], &leaves[1]);
+    let root = merkle::node_hash(&left, &leaves[2]);
+
+    assert_eq!(merkle::merkle_root(&leaves), root);
+    assert_eq!(merkle::merkle_proof(&leaves, 1), Some(vec![leaves[0], leaves[2]]));
+    assert!(merkle::verify_proof(leaves[1], 1, 3, &[leaves[0], leaves[2]], root));
+    assert!(merkle::verify_proof(leaves[2], 2, 3, &[left], root));
+
+    // Tampering with the entry, the proof, the position or the leaf count breaks it
+    let tampered = merkle::task_leaf_hash(&[1; 32], "defi", false, Timestamp::from(1), "", None, &TaskDetails::default());
+    assert!(!merkle::verify_proof(tampered, 1, 3, &[leaves[0], leaves[2]], root));
+    assert!(!merkle::verify_proof(leaves[1], 1, 3, &[leaves[0]], root));
+    assert!(!merkle::verify_proof(leaves[1], 0, 3, &[leaves[0], leaves[2]], root));
+    assert!(!merkle::verify_proof(leaves[2], 2, 4, &[left], root));
+    assert!(!merkle::verify_proof(leaves[2], 3, 3, &[left], root));
+}
+
+#[test]
+fn test_task_leaf_commits_to_description_and_details() {
+    let details = TaskDetails {
+        started_at: Some(Timestamp::from(10)),
+        ended_at: Some(Timestamp::from(30)),
+        cost: Some(5),
+        input_hash: Some([3; 32]),
+        output_hash: None,
+        failure_reason: None,
+    };
+    let leaf = |description: &str, details: &TaskDetails| {
+        merkle::task_leaf_hash(&[1; 32], "defi", true, Timestamp::from(40), description, None, details)
+    };
+    let base = leaf("swap", &details);
+    assert_eq!(base, leaf("swap", &details.clone()));
+    assert_ne!(base, leaf("swap tokens", &details));
+    assert_ne!(base, leaf("swap", &TaskDetails::default()));
+    assert_ne!(base, leaf("swap", &TaskDetails { cost: Some(6), ..details.clone() }));
+    assert_ne!(base, leaf("swap", &TaskDetails { output_hash: Some([3; 32]), ..details.clone() }));
+    assert_ne!(
+        leaf("", &TaskDetails { failure_reason: Some(FailureReason::Timeout), ..TaskDetails::default() }),
+        leaf("", &TaskDetails { failure_reason: Some(FailureReason::Crash), ..TaskDetails::default() })
+    );
+    // The capability and description cannot trade bytes
+    assert_ne!(
+        merkle::task_leaf_hash(&[1; 32], "de", true, Timestamp::from(40), "fiswap", None, &details),
+        merkle::task_leaf_hash(&[1; 32], "defi", true, Timestamp::from(40), "swap", None, &details)
+    );
+}
+
+#[test]
+fn test_task_leaf_commits_to_counterparty() {
+    let leaf = |counterparty: Option<AccountOwner>| {
+        merkle::task_leaf_hash(&[1; 32], "defi", true, Timestamp::from(40), "swap", counterparty, &TaskDetails::default())
+    };
+    // Who a task was done for cannot be rewritten without breaking its proof
+    assert_ne!(leaf(None), leaf(Some(test_owner(2))));
+    assert_ne!(leaf(Some(test_owner(2))), leaf(Some(test_owner(3))));
+    assert_ne!(leaf(Some(AccountOwner::Reserved(0))), leaf(Some(AccountOwner::Address20([0; 20]))));
+    assert_eq!(leaf(Some(test_owner(2))), leaf(Some(test_owner(2))));
+}
+
+#[test]
+fn test_task_tally_matches_individual_outcomes() {
+    let params = RegistryParameters::default();
+    let mut one_by_one = test_badge();
+    for success in [true, true, true, false] {
+        one_by_one.record_task_outcome("defi", success, false, &params, Timestamp::from(0));
+    }
+
+    let mut tallied = test_badge();
+    let tally = CapabilityTally {
+        capability: "defi".to_string(),
+        successes: 3,
+        failures: 1,
+    };
+    tallied.record_task_tally(&tally, &params, Timestamp::from(0));
+
+    assert_eq!(tallied.reputation_score, one_by_one.reputation_score);
+    assert_eq!(tallied.tasks_completed, one_by_one.tasks_completed);
+    assert_eq!(tallied.tasks_failed, one_by_one.tasks_failed);
+    assert_eq!(tallied.capability_score("defi"), one_by_one.capability_score("defi"));
+}
+
+#[test]
+fn test_task_details_validation() {
+    let details = TaskDetails {
+        started_at: Some(Timestamp::from(5_000)),
+        ended_at: Some(Timestamp::from(2_000)),
+        ..TaskDetails::default()
+    };
+    assert!(details.validate(true).is_err());
+
+    let details = TaskDetails {
+        failure_reason: Some(FailureReason::Timeout),
+        ..TaskDetails::default()
+    };
+    assert!(details.validate(true).is_err());
+    assert!(details.validate(false).is_ok());
+    assert!(TaskDetails::default().validate(true).is_ok());
+}
+
+#[test]
+fn test_task_performance_totals() {
+    let mut performance = TaskPerformance::default();
+    performance.record(
+        true,
+        &TaskDetails {
+            started_at: Some(Timestamp::from(1_000)),
+            ended_at: Some(Timestamp::from(4_000)),
+            cost: Some(120),
+            ..TaskDetails::default()
+        },
+    );
+    performance.record(
+        false,
+        &TaskDetails {
+            started_at: Some(Timestamp::from(0)),
+            ended_at: Some(Timestamp::from(9_000)),
+            failure_reason: Some(FailureReason::Timeout),
+            ..TaskDetails::default()
+        },
+    );
+    performance.record(false, &TaskDetails::default());
+
+    assert_eq!(performance.timed_tasks, 2);
+    assert_eq!(performance.total_latency_micros, 12_000);
+    assert_eq!(performance.max_latency_micros, 9_000);
+    assert_eq!(performance.costed_tasks, 1);
+    assert_eq!(performance.total_cost, 120);
+    assert_eq!(performance.failures_with(FailureReason::Timeout), 1);
+    assert_eq!(performance.failures_with(FailureReason::Crash), 0);
+    assert_eq!(performance.failure_count(), 2);
+
+    // Merging a batch's totals adds counts and keeps the longest latency
+    let mut badge_totals = TaskPerformance::default();
+    badge_totals.record(
+        false,
+        &TaskDetails {
+            failure_reason: Some(FailureReason::Timeout),
+            ..TaskDetails::default()
+        },
+    );
+    badge_totals.merge(&performance);
+    assert_eq!(badge_totals.failures_with(FailureReason::Timeout), 2);
+    assert_eq!(badge_totals.unclassified_failures, 1);
+    assert_eq!(badge_totals.max_latency_micros, 9_000);
+    assert_eq!(badge_totals.failure_modes.len(), 1);
+}
+
+#[test]
+fn test_badge_snapshot_tracks_standing() {
+    let mut badge = test_badge();
+    let before = BadgeSnapshot::from(&badge);
+    assert_eq!(before.reputation_score, badge.reputation_score);
+    assert_eq!(before.tier, badge.tier);
+    assert_eq!(before.status, AgentStatus::Active);
+
+    badge.spam_flags += 1;
+    badge.reputation_score = badge.reputation_score.saturating_sub(50);
+    let after = BadgeSnapshot::from(&badge);
+    assert_ne!(before, after);
+    assert_eq!(after.spam_flags, before.spam_flags + 1);
+}
+
+#[test]
+fn test_unstake_and_retirement_are_notified() {
+    let params = RegistryParameters::default();
+    let now = Timestamp::from(0);
+    let mut badge = test_badge();
+    badge.reputation_score = 800;
+    badge.update_tier(&params, now);
+    assert_eq!(badge.tier, "Platinum");
+    let staked = BadgeSnapshot::from(&badge);
+    assert!(BadgeSnapshot::from(&badge).changes_since(&staked).is_empty());
+
+    // Unstaking below the tier's minimum stake costs the tier, not score
+    badge.stake_balance = Amount::ZERO;
+    badge.update_tier(&params, now);
+    let unstaked = BadgeSnapshot::from(&badge);
+    let kinds: Vec<_> = unstaked.changes_since(&staked).into_iter().map(|(kind, _)| kind).collect();
+    assert_eq!(kinds, vec![AgentNotificationKind::TierChanged]);
+
+    // Retirement changes the status and drops the agent to the lowest tier
+    let mut badge = test_badge();
+    badge.reputation_score = 800;
+    badge.update_tier(&params, now);
+    badge.set_status(AgentStatus::Retired, &params, now).unwrap();
+    let changes = BadgeSnapshot::from(&badge).changes_since(&staked);
+    let kinds: Vec<_> = changes.iter().map(|(kind, _)| *kind).collect();
+    assert_eq!(kinds, vec![AgentNotificationKind::TierChanged, AgentNotificationKind::StatusChanged]);
+    assert_eq!(changes[1].1, "Status Active -> Retired");
+}
+
+#[test]
+fn test_capability_changes_are_notified() {
+    let mut badge = test_badge();
+    let before = BadgeSnapshot::from(&badge);
+    badge.manifest.capabilities.push("social".to_string());
+    let changes = BadgeSnapshot::from(&badge).changes_since(&before);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].0, AgentNotificationKind::CapabilitiesChanged);
+    assert_eq!(changes[0].1, "Capabilities: social");
+}
+
+#[test]
+fn test_parse_hash_accepts_optional_prefix() {
+    let hex = "ab".repeat(32);
+    assert_eq!(parse_hash(&hex), Some([0xab; 32]));
+    assert_eq!(parse_hash(&format!("0x{hex}")), Some([0xab; 32]));
+    assert_eq!(parse_hash(&"ab".repeat(31)), None);
+    assert_eq!(parse_hash("0xzz"), None);
+}
+
+#[test]
+fn test_task_anchor_check() {
+    let capabilities = vec!["defi".to_string()];
+    let mut performance = TaskPerformance::default();
+    performance.record(false, &TaskDetails::default());
+    let mut anchor = TaskAnchor {
+        epoch: 0,
+        root: [0u8; 32],
+        first_task: 0,
+        task_count: 4,
+        tallies: vec![CapabilityTally {
+            capability: "defi".to_string(),
+            successes: 2,
+            failures: 1,
+        }],
+        performance,
+        timestamp: Timestamp::from(0),
+    };
+    // One attested task travels next to the tallied three
+    assert_eq!(anchor.check(&capabilities, 1), Ok(3));
+    assert!(anchor.check(&capabilities, 0).is_err());
+
+    // Failure totals must match the tallies
+    anchor.performance = TaskPerformance::default();
+    assert!(anchor.check(&capabilities, 1).is_err());
+    anchor.performance.record(false, &TaskDetails::default());
+
+    // An undeclared capability rejects the whole root, which then uses up its epoch
+    anchor.tallies[0].capability = "social".to_string();
+    let err = anchor.check(&capabilities, 1).unwrap_err();
+    assert!(err.contains("social"), "{err}");
+}
@@ -0,0 +1,173 @@
+//! KYA Verifier - Continuous off-chain package integrity monitoring
+//!
+//! The verifier walks the badges registered in the KYA Registry, fetches each
+//! agent's package from its storage provider, recomputes the canonical
+//! `code_hash` with `kya-package-hash` and submits the result back to the
+//! registry as a signed verification attestation.
+
+#[cfg(test)]
+mod tests;
+
+pub mod registry;
+pub mod storage;
+
+use std::collections::HashMap;
+
+use kya_package_hash::{hash_archive_bytes, hash_directory, HashError, HashOptions};
+
+use registry::{BadgeSummary, RegistryClient, RegistryError};
+use storage::{FetchError, FetchedPackage, StorageBackend};
+
+/// Number of badges requested from the registry per page
+const PAGE_SIZE: u32 = 100;
+
+/// Storage provider name used for agents without off-chain storage
+const NO_STORAGE_PROVIDER: &str = "NONE";
+
+/// Errors that can occur while verifying a single agent
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    #[error(transparent)]
+    Fetch(#[from] FetchError),
+    #[error(transparent)]
+    Hash(#[from] HashError),
+    #[error(transparent)]
+    Registry(#[from] RegistryError),
+}
+
+/// The result of checking one agent
+#[derive(Debug)]
+pub enum Outcome {
+    /// The package was hashed and the attestation submitted
+    Verified {
+        matches: bool,
+        computed_hash: [u8; 32],
+    },
+    /// The agent has no off-chain package to verify
+    Skipped,
+    /// The package could not be checked; nothing was submitted
+    Failed(VerifyError),
+}
+
+/// The outcome for one agent in a verification pass
+#[derive(Debug)]
+pub struct AgentReport {
+    pub agent_id: String,
+    pub storage_cid: String,
+    pub outcome: Outcome,
+}
+
+/// Summary counts for a verification pass
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PassSummary {
+    pub matched: usize,
+    pub mismatched: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+/// Fetches, hashes and reports on every registered package
+pub struct Verifier<R> {
+    registry: R,
+    backends: HashMap<String, Box<dyn StorageBackend>>,
+    options: HashOptions,
+}
+
+impl<R: RegistryClient> Verifier<R> {
+    /// Create a verifier with no storage backends
+    pub fn new(registry: R, options: HashOptions) -> Self {
+        Self {
+            registry,
+            backends: HashMap::new(),
+            options,
+        }
+    }
+
+    /// Register the backend for a storage provider (as named by the registry service, e.g. "IPFS")
+    pub fn with_backend(
+        mut self,
+        provider: impl Into<String>,
+        backend: impl StorageBackend + 'static,
+    ) -> Self {
+        self.backends
+            .insert(provider.into().to_uppercase(), Box::new(backend));
+        self
+    }
+
+    /// Run one pass over all registered badges
+    pub fn run_pass(&self) -> Result<Vec<AgentReport>, RegistryError> {
+        let mut reports = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = self.registry.list_badges(offset, PAGE_SIZE)?;
+            let page_len = page.len() as u32;
+            reports.extend(page.iter().map(|badge| AgentReport {
+                agent_id: badge.agent_id.clone(),
+                storage_cid: badge.storage_cid.clone(),
+                outcome: self.verify_badge(badge),
+            }));
+            if page_len < PAGE_SIZE {
+                return Ok(reports);
+            }
+            offset += page_len;
+        }
+    }
+
+    /// Fetch, hash and report on a single badge
+    pub fn verify_badge(&self, badge: &BadgeSummary) -> Outcome {
+        let provider = badge.storage_provider.to_uppercase();
+        if provider == NO_STORAGE_PROVIDER || badge.storage_cid.is_empty() {
+            return Outcome::Skipped;
+        }
+
+        let result = self.compute_hash(&provider, &badge.storage_cid).and_then(|computed_hash| {
+            self.registry
+                .submit_verification(&badge.agent_id, &badge.storage_cid, computed_hash)?;
+            Ok(computed_hash)
+        });
+
+        match result {
+            Ok(computed_hash) => Outcome::Verified {
+                matches: computed_hash == badge.code_hash,
+                computed_hash,
+            },
+            Err(e) => Outcome::Failed(e),
+        }
+    }
+
+    fn compute_hash(&self, provider: &str, cid: &str) -> Result<[u8; 32], VerifyError> {
+        let backend = self
+            .backends
+            .get(provider)
+            .ok_or_else(|| FetchError::UnsupportedProvider(provider.to_string()))?;
+
+        let hash = match backend.fetch(cid)? {
+            FetchedPackage::Directory(path) => hash_directory(&path, &self.options)?,
+            FetchedPackage::Archive {
+                bytes,
+                strip_components,
+            } => {
+                let options = HashOptions {
+                    strip_components,
+                    ..self.options.clone()
+                };
+                hash_archive_bytes(&bytes, &options)?
+            }
+        };
+        Ok(hash.code_hash)
+    }
+}
+
+/// Tally the outcomes of a pass
+pub fn summarize(reports: &[AgentReport]) -> PassSummary {
+    let mut summary = PassSummary::default();
+    for report in reports {
+        match report.outcome {
+            Outcome::Verified { matches: true, .. } => summary.matched += 1,
+            Outcome::Verified { matches: false, .. } => summary.mismatched += 1,
+            Outcome::Skipped => summary.skipped += 1,
+            Outcome::Failed(_) => summary.failed += 1,
+        }
+    }
+    summary
+}
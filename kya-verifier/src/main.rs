@@ -0,0 +1,108 @@
+//! KYA Verifier daemon - Periodically re-verifies every registered agent package
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+use clap::Parser;
+use kya_package_hash::HashOptions;
+use kya_verifier::{
+    registry::GraphQlRegistry,
+    storage::{GatewayBackend, LocalBackend},
+    summarize, Outcome, Verifier,
+};
+
+#[derive(Parser)]
+#[command(name = "kya-verifier", version, about = "Continuously verify KYA agent packages")]
+struct Cli {
+    /// Registry service endpoint, e.g. http://localhost:8080/chains/<chain>/applications/<app>
+    #[arg(long)]
+    registry_url: String,
+    /// Seconds to wait between verification passes
+    #[arg(long, default_value_t = 3600)]
+    interval_secs: u64,
+    /// Run a single pass and exit
+    #[arg(long)]
+    once: bool,
+    /// IPFS gateway base URL
+    #[arg(long, default_value = "https://ipfs.io")]
+    ipfs_gateway: String,
+    /// Arweave gateway base URL
+    #[arg(long, default_value = "https://arweave.net")]
+    arweave_gateway: String,
+    /// Walrus aggregator base URL
+    #[arg(long)]
+    walrus_aggregator: Option<String>,
+    /// Serve every provider from this local directory instead of the network (`<dir>/<cid>`)
+    #[arg(long)]
+    local_root: Option<PathBuf>,
+}
+
+fn build_verifier(cli: &Cli) -> Verifier<GraphQlRegistry> {
+    let verifier = Verifier::new(GraphQlRegistry::new(&cli.registry_url), HashOptions::default());
+
+    if let Some(root) = &cli.local_root {
+        return ["IPFS", "ARWEAVE", "WALRUS", "HTTP"]
+            .into_iter()
+            .fold(verifier, |verifier, provider| {
+                verifier.with_backend(provider, LocalBackend::new(root))
+            });
+    }
+
+    let verifier = verifier
+        .with_backend("IPFS", GatewayBackend::ipfs(&cli.ipfs_gateway))
+        .with_backend("ARWEAVE", GatewayBackend::arweave(&cli.arweave_gateway))
+        .with_backend("HTTP", GatewayBackend::http());
+    match &cli.walrus_aggregator {
+        Some(aggregator) => verifier.with_backend("WALRUS", GatewayBackend::walrus(aggregator)),
+        None => verifier,
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let verifier = build_verifier(&cli);
+
+    loop {
+        match verifier.run_pass() {
+            Ok(reports) => {
+                for report in &reports {
+                    match &report.outcome {
+                        Outcome::Verified {
+                            matches,
+                            computed_hash,
+                        } => println!(
+                            "{} {} {} {}",
+                            if *matches { "MATCH" } else { "MISMATCH" },
+                            report.agent_id,
+                            report.storage_cid,
+                            hex::encode(computed_hash)
+                        ),
+                        Outcome::Skipped => {}
+                        Outcome::Failed(e) => eprintln!(
+                            "FAILED {} {}: {e}",
+                            report.agent_id, report.storage_cid
+                        ),
+                    }
+                }
+                let summary = summarize(&reports);
+                println!(
+                    "pass complete: {} matched, {} mismatched, {} skipped, {} failed",
+                    summary.matched, summary.mismatched, summary.skipped, summary.failed
+                );
+            }
+            Err(e) => {
+                eprintln!("error: could not list badges: {e}");
+                if cli.once {
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+
+        if cli.once {
+            return ExitCode::SUCCESS;
+        }
+        thread::sleep(Duration::from_secs(cli.interval_secs));
+    }
+}
@@ -0,0 +1,130 @@
+//! Access to the KYA Registry for listing badges and submitting verifications
+
+use serde_json::{json, Value};
+
+/// Errors that can occur while talking to the registry
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    #[error("request to registry service failed: {0}")]
+    Request(String),
+    #[error("registry service returned errors: {0}")]
+    GraphQl(String),
+    #[error("unexpected registry response: {0}")]
+    InvalidResponse(String),
+}
+
+/// The fields of a registered badge needed for verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BadgeSummary {
+    /// The agent's registry ID (hex)
+    pub agent_id: String,
+    /// The registered 32-byte code hash
+    pub code_hash: [u8; 32],
+    /// Storage provider as reported by the service (e.g. "IPFS", "ARWEAVE")
+    pub storage_provider: String,
+    /// Content ID for off-chain storage
+    pub storage_cid: String,
+}
+
+/// A registry the verifier reads badges from and reports results to
+pub trait RegistryClient {
+    /// List registered badges, paginated
+    fn list_badges(&self, offset: u32, limit: u32) -> Result<Vec<BadgeSummary>, RegistryError>;
+
+    /// Submit a verification attestation for an agent
+    fn submit_verification(
+        &self,
+        agent_id: &str,
+        storage_cid: &str,
+        computed_hash: [u8; 32],
+    ) -> Result<(), RegistryError>;
+}
+
+const AGENTS_QUERY: &str = "query Agents($offset: Int!, $limit: Int!) {
+    agents(offset: $offset, limit: $limit) { agentId codeHashHex storageProvider storageCid }
+}";
+
+const VERIFY_MUTATION: &str =
+    "mutation Verify($agentId: String!, $storageCid: String!, $computedHash: String!) {
+    verifyCodeHash(agentId: $agentId, storageCid: $storageCid, computedHash: $computedHash)
+}";
+
+/// Registry client using the registry application's GraphQL service on a node
+/// service whose wallet signs the scheduled verification operations
+#[derive(Debug, Clone)]
+pub struct GraphQlRegistry {
+    service_url: String,
+}
+
+impl GraphQlRegistry {
+    /// Create a client for an endpoint like `http://localhost:8080/chains/<chain>/applications/<app>`
+    pub fn new(service_url: impl Into<String>) -> Self {
+        Self {
+            service_url: service_url.into(),
+        }
+    }
+
+    fn execute(&self, query: &str, variables: Value) -> Result<Value, RegistryError> {
+        let response: Value = ureq::post(&self.service_url)
+            .send_json(json!({ "query": query, "variables": variables }))
+            .map_err(|e| RegistryError::Request(e.to_string()))?
+            .into_json()
+            .map_err(|e| RegistryError::Request(e.to_string()))?;
+
+        if let Some(errors) = response.get("errors").filter(|errors| !errors.is_null()) {
+            return Err(RegistryError::GraphQl(errors.to_string()));
+        }
+        Ok(response["data"].clone())
+    }
+}
+
+impl RegistryClient for GraphQlRegistry {
+    fn list_badges(&self, offset: u32, limit: u32) -> Result<Vec<BadgeSummary>, RegistryError> {
+        let data = self.execute(AGENTS_QUERY, json!({ "offset": offset, "limit": limit }))?;
+        parse_agents(&data)
+    }
+
+    fn submit_verification(
+        &self,
+        agent_id: &str,
+        storage_cid: &str,
+        computed_hash: [u8; 32],
+    ) -> Result<(), RegistryError> {
+        self.execute(
+            VERIFY_MUTATION,
+            json!({
+                "agentId": agent_id,
+                "storageCid": storage_cid,
+                "computedHash": hex::encode(computed_hash),
+            }),
+        )?;
+        Ok(())
+    }
+}
+
+/// Parse the `agents` field of a query response
+pub fn parse_agents(data: &Value) -> Result<Vec<BadgeSummary>, RegistryError> {
+    let agents = data["agents"]
+        .as_array()
+        .ok_or_else(|| RegistryError::InvalidResponse("missing agents".to_string()))?;
+
+    agents
+        .iter()
+        .map(|agent| {
+            let field = |name: &str| {
+                agent[name]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| RegistryError::InvalidResponse(format!("missing field {name}")))
+            };
+            let code_hash = kya_package_hash::parse_code_hash(&field("codeHashHex")?)
+                .map_err(|e| RegistryError::InvalidResponse(e.to_string()))?;
+            Ok(BadgeSummary {
+                agent_id: field("agentId")?,
+                code_hash,
+                storage_provider: field("storageProvider")?,
+                storage_cid: field("storageCid")?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,164 @@
+//! Pluggable storage backends for fetching agent packages
+
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Default upper bound on a downloaded package (256 MiB)
+pub const DEFAULT_MAX_PACKAGE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Errors that can occur while fetching a package
+#[derive(Debug, thiserror::Error)]
+pub enum FetchError {
+    #[error("no backend configured for storage provider {0}")]
+    UnsupportedProvider(String),
+    #[error("package {0} not found")]
+    NotFound(String),
+    #[error("request for {url} failed: {message}")]
+    Request { url: String, message: String },
+    #[error("package {0} exceeds the size limit")]
+    TooLarge(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A package retrieved from off-chain storage
+#[derive(Debug)]
+pub enum FetchedPackage {
+    /// A `.tar` or `.tar.gz` archive held in memory
+    Archive {
+        bytes: Vec<u8>,
+        /// Leading path components to strip (e.g. the CID directory added by IPFS gateways)
+        strip_components: usize,
+    },
+    /// An unpacked package directory (local stand-in)
+    Directory(PathBuf),
+}
+
+/// A source of agent packages for one storage provider
+pub trait StorageBackend {
+    /// Fetch the package stored under `cid`
+    fn fetch(&self, cid: &str) -> Result<FetchedPackage, FetchError>;
+}
+
+/// Fetches packages over HTTP from a gateway URL template
+///
+/// The template's `{cid}` placeholder is replaced with the storage CID, e.g.
+/// `https://ipfs.io/ipfs/{cid}?format=tar` or `https://arweave.net/{cid}`.
+/// A template of just `{cid}` treats the CID itself as the URL (HTTP provider).
+#[derive(Debug, Clone)]
+pub struct GatewayBackend {
+    url_template: String,
+    strip_components: usize,
+    max_bytes: u64,
+}
+
+impl GatewayBackend {
+    /// Create a backend from a URL template
+    pub fn new(url_template: impl Into<String>) -> Self {
+        Self {
+            url_template: url_template.into(),
+            strip_components: 0,
+            max_bytes: DEFAULT_MAX_PACKAGE_BYTES,
+        }
+    }
+
+    /// IPFS gateway returning UnixFS directories as tar archives rooted at the CID
+    pub fn ipfs(gateway: &str) -> Self {
+        Self::new(format!("{}/ipfs/{{cid}}?format=tar", gateway.trim_end_matches('/')))
+            .with_strip_components(1)
+    }
+
+    /// Arweave gateway serving transaction data
+    pub fn arweave(gateway: &str) -> Self {
+        Self::new(format!("{}/{{cid}}", gateway.trim_end_matches('/')))
+    }
+
+    /// Walrus aggregator serving blobs by ID
+    pub fn walrus(aggregator: &str) -> Self {
+        Self::new(format!("{}/v1/blobs/{{cid}}", aggregator.trim_end_matches('/')))
+    }
+
+    /// Plain HTTP(S) where the storage CID is the package URL
+    pub fn http() -> Self {
+        Self::new("{cid}")
+    }
+
+    /// Set the number of leading path components to strip from fetched archives
+    pub fn with_strip_components(mut self, strip_components: usize) -> Self {
+        self.strip_components = strip_components;
+        self
+    }
+
+    /// Set the maximum accepted package size
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Build the URL for a CID
+    pub fn url_for(&self, cid: &str) -> String {
+        self.url_template.replace("{cid}", cid)
+    }
+}
+
+impl StorageBackend for GatewayBackend {
+    fn fetch(&self, cid: &str) -> Result<FetchedPackage, FetchError> {
+        let url = self.url_for(cid);
+        let response = ureq::get(&url).call().map_err(|e| match e {
+            ureq::Error::Status(404, _) => FetchError::NotFound(cid.to_string()),
+            other => FetchError::Request {
+                url: url.clone(),
+                message: other.to_string(),
+            },
+        })?;
+
+        let mut bytes = Vec::new();
+        response
+            .into_reader()
+            .take(self.max_bytes + 1)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() as u64 > self.max_bytes {
+            return Err(FetchError::TooLarge(cid.to_string()));
+        }
+
+        Ok(FetchedPackage::Archive {
+            bytes,
+            strip_components: self.strip_components,
+        })
+    }
+}
+
+/// Local filesystem stand-in: resolves `cid` to `<root>/<cid>`, either an
+/// unpacked directory or an archive file
+#[derive(Debug, Clone)]
+pub struct LocalBackend {
+    root: PathBuf,
+}
+
+impl LocalBackend {
+    /// Create a backend serving packages from `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl StorageBackend for LocalBackend {
+    fn fetch(&self, cid: &str) -> Result<FetchedPackage, FetchError> {
+        // CIDs are single path components; anything else could escape the root
+        if cid.is_empty() || cid.contains(['/', '\\']) || cid == ".." {
+            return Err(FetchError::NotFound(cid.to_string()));
+        }
+
+        let path = self.root.join(cid);
+        if path.is_dir() {
+            Ok(FetchedPackage::Directory(path))
+        } else if path.is_file() {
+            Ok(FetchedPackage::Archive {
+                bytes: std::fs::read(&path)?,
+                strip_components: 0,
+            })
+        } else {
+            Err(FetchError::NotFound(cid.to_string()))
+        }
+    }
+}
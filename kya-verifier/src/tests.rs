@@ -0,0 +1,169 @@
+//! Unit tests for the verifier, using the local storage stand-in
+
+use super::*;
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+
+use registry::parse_agents;
+use storage::{GatewayBackend, LocalBackend};
+
+/// In-memory registry recording submitted attestations
+#[derive(Default)]
+struct MockRegistry {
+    badges: Vec<BadgeSummary>,
+    submitted: RefCell<Vec<(String, String, [u8; 32])>>,
+}
+
+impl RegistryClient for &MockRegistry {
+    fn list_badges(&self, offset: u32, limit: u32) -> Result<Vec<BadgeSummary>, RegistryError> {
+        Ok(self
+            .badges
+            .iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    fn submit_verification(
+        &self,
+        agent_id: &str,
+        storage_cid: &str,
+        computed_hash: [u8; 32],
+    ) -> Result<(), RegistryError> {
+        self.submitted.borrow_mut().push((
+            agent_id.to_string(),
+            storage_cid.to_string(),
+            computed_hash,
+        ));
+        Ok(())
+    }
+}
+
+fn write_package(root: &Path, cid: &str, main: &str) -> [u8; 32] {
+    let dir = root.join(cid);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.py"), main).unwrap();
+    hash_directory(&dir, &HashOptions::default()).unwrap().code_hash
+}
+
+fn badge(agent_id: &str, provider: &str, cid: &str, code_hash: [u8; 32]) -> BadgeSummary {
+    BadgeSummary {
+        agent_id: agent_id.to_string(),
+        code_hash,
+        storage_provider: provider.to_string(),
+        storage_cid: cid.to_string(),
+    }
+}
+
+#[test]
+fn test_pass_reports_matches_and_mismatches() {
+    let storage = tempfile::tempdir().unwrap();
+    let good_hash = write_package(storage.path(), "cid-good", "print('ok')\n");
+    write_package(storage.path(), "cid-tampered", "print('evil')\n");
+
+    let registry = MockRegistry {
+        badges: vec![
+            badge("0xaa", "IPFS", "cid-good", good_hash),
+            badge("0xbb", "IPFS", "cid-tampered", good_hash),
+            badge("0xcc", "NONE", "", [0u8; 32]),
+            badge("0xdd", "IPFS", "cid-missing", good_hash),
+        ],
+        ..MockRegistry::default()
+    };
+    let verifier = Verifier::new(&registry, HashOptions::default())
+        .with_backend("IPFS", LocalBackend::new(storage.path()));
+
+    let reports = verifier.run_pass().unwrap();
+
+    assert_eq!(
+        summarize(&reports),
+        PassSummary {
+            matched: 1,
+            mismatched: 1,
+            skipped: 1,
+            failed: 1,
+        }
+    );
+    // Fetch failures are not evidence of tampering and are not submitted
+    let submitted = registry.submitted.borrow();
+    assert_eq!(submitted.len(), 2);
+    assert_eq!(submitted[0], ("0xaa".to_string(), "cid-good".to_string(), good_hash));
+    assert_eq!(submitted[1].0, "0xbb");
+}
+
+#[test]
+fn test_local_backend_reads_archives() {
+    let storage = tempfile::tempdir().unwrap();
+    let package = tempfile::tempdir().unwrap();
+    let expected = write_package(package.path(), "pkg", "print('ok')\n");
+
+    let file = fs::File::create(storage.path().join("cid-archive")).unwrap();
+    let mut builder = tar::Builder::new(file);
+    builder
+        .append_dir_all(".", package.path().join("pkg"))
+        .unwrap();
+    builder.finish().unwrap();
+
+    let registry = MockRegistry::default();
+    let verifier = Verifier::new(&registry, HashOptions::default())
+        .with_backend("ARWEAVE", LocalBackend::new(storage.path()));
+
+    let outcome = verifier.verify_badge(&badge("0xaa", "Arweave", "cid-archive", expected));
+    assert!(matches!(outcome, Outcome::Verified { matches: true, .. }));
+}
+
+#[test]
+fn test_unconfigured_provider_fails() {
+    let registry = MockRegistry::default();
+    let verifier = Verifier::new(&registry, HashOptions::default());
+
+    let outcome = verifier.verify_badge(&badge("0xaa", "WALRUS", "blob", [0u8; 32]));
+    assert!(matches!(
+        outcome,
+        Outcome::Failed(VerifyError::Fetch(FetchError::UnsupportedProvider(_)))
+    ));
+}
+
+#[test]
+fn test_local_backend_rejects_path_traversal() {
+    let storage = tempfile::tempdir().unwrap();
+    let backend = LocalBackend::new(storage.path());
+    assert!(matches!(backend.fetch("../etc"), Err(FetchError::NotFound(_))));
+    assert!(matches!(backend.fetch(".."), Err(FetchError::NotFound(_))));
+}
+
+#[test]
+fn test_gateway_urls() {
+    assert_eq!(
+        GatewayBackend::ipfs("https://ipfs.io/").url_for("bafy"),
+        "https://ipfs.io/ipfs/bafy?format=tar"
+    );
+    assert_eq!(
+        GatewayBackend::arweave("https://arweave.net").url_for("tx"),
+        "https://arweave.net/tx"
+    );
+    assert_eq!(
+        GatewayBackend::walrus("https://aggregator.walrus").url_for("blob"),
+        "https://aggregator.walrus/v1/blobs/blob"
+    );
+    assert_eq!(
+        GatewayBackend::http().url_for("https://example.com/agent.tgz"),
+        "https://example.com/agent.tgz"
+    );
+}
+
+#[test]
+fn test_parse_agents() {
+    let data = serde_json::json!({
+        "agents": [{
+            "agentId": "aa",
+            "codeHashHex": "22".repeat(32),
+            "storageProvider": "IPFS",
+            "storageCid": "bafy",
+        }]
+    });
+    let badges = parse_agents(&data).unwrap();
+    assert_eq!(badges, vec![badge("aa", "IPFS", "bafy", [0x22; 32])]);
+}
@@ -33,7 +33,8 @@ cargo build -p kya-registry --release --target wasm32-unknown-unknown
 echo "📦 Publishing KYA Registry to Conway Testnet..."
 PUBLISH_OUTPUT=$(linera publish-and-create \
     /build/target/wasm32-unknown-unknown/release/kya-registry-contract.wasm \
-    /build/target/wasm32-unknown-unknown/release/kya-registry-service.wasm 2>&1)
+    /build/target/wasm32-unknown-unknown/release/kya-registry-service.wasm \
+    --json-parameters '{}' 2>&1)
 
 # Try to extract application ID
 APP_ID=$(echo "$PUBLISH_OUTPUT" | grep -oE '[a-f0-9]{64}' | head -1 || true)